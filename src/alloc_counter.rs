@@ -0,0 +1,59 @@
+/// 定义了带计数功能的内存分配器
+/// 库本身不安装全局分配器, 由可执行文件(或嵌入解释器的程序)用 #[global_allocator] 安装
+/// 交互模式中的 ,time 命令借助它统计一次求值过程中的内存分配
+/// 沙箱借助按线程统计的分配次数限制求值过程中分配的单元数, 没有安装时无法使用该项限制
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// 包装系统分配器, 记录分配次数与分配的字节数
+pub struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// 当前线程的分配次数, 不会被其它线程中的求值干扰
+    static THREAD_ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// 记录一次分配
+/// 线程退出阶段线程局部变量可能已经失效, 此时忽略即可
+fn record(size: usize) {
+    ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+    ALLOCATED_BYTES.fetch_add(size, Ordering::Relaxed);
+    _ = THREAD_ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        record(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        record(new_size);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// 返回到目前为止的(分配次数, 分配字节数)
+pub fn snapshot() -> (usize, usize) {
+    (ALLOCATIONS.load(Ordering::Relaxed), ALLOCATED_BYTES.load(Ordering::Relaxed))
+}
+
+/// 返回当前线程到目前为止的分配次数
+pub fn thread_allocations() -> usize {
+    THREAD_ALLOCATIONS.with(|count| count.get())
+}
+
+/// CountingAllocator 是否被安装为全局分配器
+pub fn installed() -> bool {
+    let before: usize = thread_allocations();
+    drop(std::hint::black_box(Box::new(0u8)));
+    thread_allocations() > before
+}
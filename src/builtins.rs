@@ -1,71 +1,255 @@
 /// 定义了所有内置过程
 
-use crate::value::{Value, is_integer};
+use crate::value::{Value, Foreign, BuiltinFn, is_integer};
 use crate::eval_env::EvalEnv;
-use std::process;
-use std::panic;
 use std::rc::Rc;
-use crate::error::ErrorEval;
+use std::cell::RefCell;
+use crate::error::{ErrorEval, ErrorKind};
+use crate::documentation::{describe_name, describe_value};
+use crate::list::columns;
+
+/// 参数中出现了非数字的值, 报告第一个非数字的参数
+fn non_numeric(procedure: &str, params: &[Value]) -> ErrorEval {
+    let got: &Value = params.iter().find(|param| !matches!(param, Value::NumericValue(_))).unwrap_or(&Value::NilValue);
+    ErrorEval::type_error(procedure, "number", got)
+}
+
+/// 参数中出现了非整数的值, 报告第一个非整数的参数
+fn non_integer(procedure: &str, params: &[Value]) -> ErrorEval {
+    let got: &Value = params.iter().find(|param| !matches!(param, Value::NumericValue(n) if is_integer(n))).unwrap_or(&Value::NilValue);
+    ErrorEval::type_error(procedure, "integer", got)
+}
+
+/// 高阶内置过程的过程参数, 不是过程时报错
+pub fn procedure_param(procedure: &str, param: &Value) -> Result<Value, ErrorEval> {
+    match param.is_procedure() {
+        true => Ok(param.clone()),
+        false => Err(ErrorEval::type_error(procedure, "procedure", param)),
+    }
+}
 
 /// apply 内置过程
 /// 将过程proc调用至参数param
 pub fn apply(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 2{
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <apply>: Missing argument", 0), index: 0 });
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("apply", params.len()));
     }
-    else if params.len() > 2 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <apply>: Too many argument", 0), index: 0 });
+    let procedure: Value = procedure_param("apply", &params[0])?;
+    let args: Vec<Value> = params[1].to_vector().map_err(|error| error.context("apply", "Fail to convert a value to vector"))?;
+    env.apply_procedure(procedure, args)
+}
+
+/// 多个返回值, 由 values 产生
+/// call-with-values, let-values 与 define-values 将其展开为各个值
+pub struct MultipleValues(pub Vec<Value>);
+
+impl Foreign for MultipleValues {
+    fn type_name(&self) -> &str {
+        "values"
     }
-    else {
-        match params[0].clone() {
-            Value::ProcedureValue(f) => {
-                // let args: Vec<Value> = params[1..].iter().cloned().map(|value| env.eval(value)).collect();
-                let args: Vec<Value> = params[1].to_vector().map_err(|error| ErrorEval{
-                    message: format!("{}: Builtin Procedure <apply>: Fail to convert a value to vector\n{}", error.index + 1, error.message),
-                    index: error.index + 1
-                })?;
-                return f(args, env);
-            },
-            Value::LambdaValue(params_in_lambda, body, env) => {
-                let env_derived: Rc<EvalEnv> = env.derive(*params_in_lambda, params[1].to_vector().map_err(|error| ErrorEval{
-                    message: format!("{}: Builtin Procedure <apply>: Fail to convert a value to vector\n{}", error.index + 1, error.message),
-                    index: error.index + 1
-                })?).into();
-                let mut result: Value = Value::NilValue;
-                for bodyv in *body {
-                    result = env_derived.clone().eval(bodyv).map_err(|error| ErrorEval{
-                        message: format!("{}: Builtin Procedure <apply>: Fail to evaluate a value\n{}", error.index + 1, error.message),
-                        index: error.index + 1
-                    })?
-                }
-                return Ok(result);
-            },
-            _ => return Err(ErrorEval { message: format!("{}: Builtin Procedure <apply>: Fail to evaluate a value", 0), index: 0 }),
+
+    fn display(&self) -> String {
+        self.0.iter().map(|value| value.to_string()).collect::<Vec<String>>().join(" ")
+    }
+}
+
+/// 将返回值展开为各个值, 不是多个返回值时就是它本身
+pub fn spread_values(value: Value) -> Vec<Value> {
+    match value.downcast_ref::<MultipleValues>() {
+        Some(values) => values.0.clone(),
+        None => vec![value],
+    }
+}
+
+/// values 内置过程
+/// (values 1 2 3) 返回多个值, 只有一个参数时就是这个参数本身
+pub fn values(mut params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() == 1 {
+        return Ok(params.remove(0));
+    }
+    Ok(Value::foreign(MultipleValues(params)))
+}
+
+/// call-with-values 内置过程
+/// 以无参数调用producer, 再将其返回的各个值作为参数调用consumer
+pub fn call_with_values(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("call-with-values", params.len()));
+    }
+    let producer: Value = procedure_param("call-with-values", &params[0])?;
+    let consumer: Value = procedure_param("call-with-values", &params[1])?;
+    let produced: Value = env.apply_procedure(producer, Vec::new())?;
+    env.apply_procedure(consumer, spread_values(produced))
+}
+
+/// 由宿主代码给出的延迟求值过程
+pub type Thunk = Rc<dyn Fn() -> Result<Value, ErrorEval>>;
+
+/// promise的状态
+/// Done: 已经求得的值
+/// Delayed: 尚未求值的表达式及其求值环境, delay_force为真时表达式的值是另一个promise
+/// Thunk: 尚未调用的延迟求值过程, 用于内置的惰性流
+#[derive(Clone)]
+enum PromiseState {
+    Done(Value),
+    Delayed { expr: Value, env: Rc<EvalEnv>, delay_force: bool },
+    Thunk(Thunk),
+}
+
+/// promise, 由 delay, delay-force 与 make-promise 产生, 由 force 求值
+/// delay-force 串起来的promise共享同一个状态, 因此可以迭代地求值而不加深调用
+pub struct Promise(RefCell<Rc<RefCell<PromiseState>>>);
+
+impl Foreign for Promise {
+    fn type_name(&self) -> &str {
+        "promise"
+    }
+}
+
+impl Promise {
+    /// 尚未求值的promise
+    pub fn delayed(expr: Value, env: Rc<EvalEnv>, delay_force: bool) -> Value {
+        Value::foreign(Promise(RefCell::new(Rc::new(RefCell::new(PromiseState::Delayed { expr, env, delay_force })))))
+    }
+
+    /// 第一次 force 时调用thunk的promise
+    pub fn lazy(thunk: Thunk) -> Value {
+        Value::foreign(Promise(RefCell::new(Rc::new(RefCell::new(PromiseState::Thunk(thunk))))))
+    }
+
+    /// 已经求得值的promise
+    pub fn done(value: Value) -> Value {
+        Value::foreign(Promise(RefCell::new(Rc::new(RefCell::new(PromiseState::Done(value))))))
+    }
+
+    /// 求promise的值, 求过之后不再重复求值
+    pub fn force(&self) -> Result<Value, ErrorEval> {
+        loop {
+            let pending: PromiseState = self.0.borrow().borrow().clone();
+            let (value, delay_force) = match pending {
+                PromiseState::Done(value) => return Ok(value),
+                PromiseState::Delayed { expr, env, delay_force } => (env.eval(expr)?, delay_force),
+                PromiseState::Thunk(thunk) => (thunk()?, false),
+            };
+            // 求值期间同一个promise可能已经被求过了, 以先求得的值为准
+            let state: Rc<RefCell<PromiseState>> = self.0.borrow().clone();
+            if let PromiseState::Done(value) = &*state.borrow() {
+                return Ok(value.clone());
+            }
+            match value.downcast_rc::<Promise>() {
+                // 接过下一个promise的状态, 并让它共享这里的状态
+                Some(next) if delay_force => {
+                    let next_state: PromiseState = next.0.borrow().borrow().clone();
+                    *state.borrow_mut() = next_state;
+                    *next.0.borrow_mut() = state;
+                },
+                _ => {
+                    *state.borrow_mut() = PromiseState::Done(value.clone());
+                    return Ok(value);
+                },
+            }
         }
     }
 }
 
+/// force 内置过程
+/// 求promise的值, 参数不是promise时直接返回
+pub fn force(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("force", params.len()));
+    }
+    match params[0].downcast_ref::<Promise>() {
+        Some(promise) => promise.force(),
+        None => Ok(params[0].clone()),
+    }
+}
+
+/// make-promise 内置过程
+/// 返回一个已经求得值的promise, 参数已经是promise时直接返回
+pub fn make_promise(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("make-promise", params.len()));
+    }
+    match params[0].downcast_ref::<Promise>() {
+        Some(_) => Ok(params[0].clone()),
+        None => Ok(Promise::done(params[0].clone())),
+    }
+}
+
+/// promise? 内置过程
+pub fn promise_q(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("promise?", params.len()));
+    }
+    Ok(Value::BooleanValue(params[0].downcast_ref::<Promise>().is_some()))
+}
+
+/// parameterize 修改参数对象时传给它的标记, Lisp代码无法构造
+/// convert为真时新的值要经过转换过程, 恢复原来的值时为假
+pub struct ParameterUpdate {
+    pub convert: bool,
+}
+
+impl Foreign for ParameterUpdate {
+    fn type_name(&self) -> &str {
+        "parameter-update"
+    }
+}
+
+/// make-parameter 内置过程
+/// (make-parameter value [converter]) 返回一个参数对象, 无参数调用时返回当前的值
+/// 给出converter时, 初始值与 parameterize 给出的值都经过它转换
+pub fn make_parameter(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.is_empty() || params.len() > 2 {
+        return Err(ErrorEval::arity("make-parameter", params.len()));
+    }
+    let converter: Option<Value> = match params.get(1) {
+        Some(converter) => Some(procedure_param("make-parameter", converter)?),
+        None => None,
+    };
+    let initial: Value = match &converter {
+        Some(converter) => env.apply_procedure(converter.clone(), vec![params[0].clone()])?,
+        None => params[0].clone(),
+    };
+    let value: Rc<RefCell<Value>> = Rc::new(RefCell::new(initial));
+    let parameter = move |args: Vec<Value>, env: Rc<EvalEnv>| -> Result<Value, ErrorEval> {
+        if args.is_empty() {
+            return Ok(value.borrow().clone());
+        }
+        let convert: Option<bool> = match args.as_slice() {
+            [marker, _] => marker.downcast_ref::<ParameterUpdate>().map(|update| update.convert),
+            _ => None,
+        };
+        match (convert, &converter) {
+            (Some(true), Some(converter)) => {
+                let converted: Value = env.apply_procedure(converter.clone(), vec![args[1].clone()])?;
+                Ok(value.replace(converted))
+            },
+            (Some(_), _) => Ok(value.replace(args[1].clone())),
+            (None, _) => Err(ErrorEval::new(ErrorKind::ArityMismatch { procedure: "parameter".to_string(), expected: "0".to_string(), got: args.len() })),
+        }
+    };
+    Ok(Value::ProcedureValue(Box::new(Rc::new(parameter) as BuiltinFn)))
+}
+
 /// print 内置过程
 /// (print <expr1> <expr2> <expr3>)
 /// 调用分别打印多个表达式
-pub fn print(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    params.iter().for_each(|param| println!("{}", param.to_string()));
+pub fn print(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    params.iter().for_each(|param| env.output.writeln(&param.to_string()));
     Ok(Value::NilValue)
 }
-pub fn display(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <display>: Missing argument", 0), index: 0 });
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <display>: Too many argument", 0), index: 0 });
+pub fn display(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("display", params.len()));
     }
     else {
         match params[0].clone() {
             Value::StringValue(s) => {
-                print!("{}", s);
+                env.output.write(&s);
             },
             v => {
-                print!("{}", v.to_string());
+                env.output.write(&v.to_string());
             }
         }
         Ok(Value::NilValue)
@@ -76,114 +260,141 @@ pub fn display(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval
 /// (display <expr>)
 /// 仅仅支持一个表达式的时候使用
 /// 打印表达式并且换行
-pub fn displayln(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <displayln>: Missing argument", 0), index: 0 });
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <displayln>: Too many argument", 0), index: 0 });
+pub fn displayln(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("displayln", params.len()));
     }
     else {
         match params[0].clone() {
             Value::StringValue(s) => {
-                println!("{}", s);
+                env.output.writeln(&s);
             },
             v => {
-                println!("{}", v.to_string());
+                env.output.writeln(&v.to_string());
             }
         }
         Ok(Value::NilValue)
     }
 }
 
+/// help 内置过程
+/// (help 'map)
+/// 打印符号对应的过程或特殊形式的调用形式, 参数个数与文档
+pub fn help(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("help", params.len()));
+    }
+    else {
+        match params[0].clone() {
+            Value::SymbolValue(s) => env.output.writeln(&describe_name(&s, env.clone())),
+            v => env.output.writeln(&describe_value(&v, env.clone())),
+        }
+        Ok(Value::NilValue)
+    }
+}
+
+/// describe 内置过程
+/// (describe f)
+/// 打印一个值的说明, 过程会给出调用形式, 参数个数与文档
+pub fn describe(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("describe", params.len()));
+    }
+    else {
+        env.output.writeln(&describe_value(&params[0], env.clone()));
+        Ok(Value::NilValue)
+    }
+}
+
+/// break 内置过程
+/// (break)
+/// 在调用处暂停, 进入调试器
+pub fn breakpoint(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if !params.is_empty() {
+        return Err(ErrorEval::arity("break", params.len()));
+    }
+    env.debugger.pause(&env, "Paused at (break)")?;
+    Ok(Value::NilValue)
+}
+
 /// error 内置过程
 /// 调用error以实现内置的错误调用
-/// 可附带至多一个错误退出码, 并不限制数据类型
+/// 可附带至多一个错误信息, 并不限制数据类型
 pub fn error(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
     if params.len() > 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <error>: Too many argument", 0), index: 0 });
+        return Err(ErrorEval::arity("error", params.len()));
     }
     else if params.len() == 1 {
-        panic!("{}", params[0].to_string());
+        return Err(ErrorEval::new(ErrorKind::UserRaised(params[0].clone())));
     }
     else {
-        panic!("Error thrown.");
+        return Err(ErrorEval::runtime("error", "Error thrown"));
     }
 }
 pub fn eval(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <eval>: Missing argument", 0), index: 0 });
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <eval>: Too many argument", 0), index: 0 });
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("eval", params.len()));
     }
     else {
-        env.eval(params[0].clone()).map_err(|error| ErrorEval{
-            message: format!("{}: Builtin Procedure <eval>: Fail to evaluate a value\n{}", error.index + 1, error.message),
-            index: error.index + 1
-        })
+        env.eval(params[0].clone()).map_err(|error| error.context("eval", "Fail to evaluate a value"))
     }
 }
-/// 非安全退出. 
-/// 并不保证能够顺利退出. 
-/// 当exit调用格式不对时会panic而非exit.
-pub fn exit(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.is_empty() {
-        process::exit(0);
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <exit>: Too many argument", 0), index: 0 });
+/// exit 内置过程
+/// 并不直接结束进程, 而是返回一个退出请求, 由读取机或嵌入解释器的调用方决定如何处理
+/// 当exit调用格式不对时返回普通的错误
+pub fn exit(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if env.sandbox.is_some() {
+        return Err(ErrorEval::runtime("exit", "Cannot exit inside a sandbox"));
     }
-    else {
-        match params[0].clone() {
-            Value::NumericValue(n) => process::exit(n as i32),
-            // _ => panic!("SyntaxError: Non integer exit code is forbidden"),
-            _ => return Err(ErrorEval { message: format!("{}: Builtin Procedure <exit>: Non integer exit code is forbidden", 0), index: 0 }),
-        }
+    match params.as_slice() {
+        [] => Err(ErrorEval::exit(0)),
+        [Value::NumericValue(n)] if is_integer(n) => Err(ErrorEval::exit(*n as i32)),
+        [_] => Err(non_integer("exit", &params)),
+        _ => Err(ErrorEval::arity("exit", params.len())),
     }
 }
-/// 强制安全退出. 
-/// 当出现exit_force调用格式不对时会以127退出码退出.
-pub fn exit_force(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.is_empty() {
-        process::exit(0);
+/// exit_force 内置过程
+/// 与exit相同, 但调用格式不对时也会请求以127退出码退出, 原因附在退出请求中
+pub fn exit_force(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if env.sandbox.is_some() {
+        return Err(ErrorEval::runtime("exit_force", "Cannot exit inside a sandbox"));
     }
-    else if params.len() > 1 {
-        eprint!("SyntaxError: Too many argument in procedure <exit>");
-        process::exit(127);
+    match params.as_slice() {
+        [] => Err(ErrorEval::exit(0)),
+        [Value::NumericValue(n)] if is_integer(n) => Err(ErrorEval::exit(*n as i32)),
+        [_] => Err(ErrorEval::exit_with(127, "Non integer exit code is forbidden")),
+        _ => Err(ErrorEval::exit_with(127, "Too many argument in procedure <exit_force>")),
     }
-    else {
-        match params[0].clone() {
-            Value::NumericValue(n) => process::exit(n as i32),
-            _ => { 
-                eprint!("SyntaxError: Non integer exit code is forbidden");
-                process::exit(127);
-            }
-        }
+}
+
+/// command-line 内置过程
+/// 返回命令行参数组成的列表, 第一个元素为脚本名
+pub fn command_line(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if !params.is_empty() {
+        return Err(ErrorEval::runtime("command-line", "Cannot append argument"));
     }
+    let args: Vec<Value> = env.command_line.iter().map(|arg| Value::StringValue(arg.clone())).collect();
+    list(args, env)
 }
 
 /// newline 内置过程
 /// 打印一个空行
 /// 不允许附带任何的参数
-pub fn newline(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+pub fn newline(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
     if params.is_empty() {
-        println!();
+        env.output.write("\n");
         Ok(Value::NilValue)
     }
     else {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <newline>: Cannot append argument", 0), index: 0 });
+        return Err(ErrorEval::runtime("newline", "Cannot append argument"));
     }
 }
 /// atom? 内置过程
 /// 判断是否为原子类型数据
 /// 原子类型数据包括: 布尔类型, 数字类型, 字符串类型, 符号字面量类型, 空表类型
 pub fn atom_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <atom?>: Missing argument", 0), index: 0 });
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <atom?>: Too many argument", 0), index: 0 });
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("atom?", params.len()));
     }
     else {
         match params[0] {
@@ -192,6 +403,7 @@ pub fn atom_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, Error
             Value::StringValue(_) => return Ok(Value::BooleanValue(true)),
             Value::SymbolValue(_) => return Ok(Value::BooleanValue(true)),
             Value::NilValue => return Ok(Value::BooleanValue(true)),
+            Value::ForeignValue(_) => return Ok(Value::BooleanValue(true)),
             _ => return Ok(Value::BooleanValue(false)),
         }
     }
@@ -200,11 +412,8 @@ pub fn atom_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, Error
 /// boolean? 内置过程
 /// 判断是否为布尔类型值
 pub fn boolean_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <boolean?>: Missing argument", 0), index: 0 });
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <boolean?>: Too many argument", 0), index: 0 });
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("boolean?", params.len()));
     }
     else {
         match params[0] {
@@ -217,11 +426,8 @@ pub fn boolean_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, Er
 /// integer? 内置过程
 /// 判断是否为整数
 pub fn integer_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <integer?>: Missing argument", 0), index: 0 });
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <integer?>: Too many argument", 0), index: 0 });
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("integer?", params.len()));
     }
     else {
         match params[0].clone() {
@@ -241,19 +447,13 @@ pub fn integer_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, Er
 /// list? 内置过程
 /// 判断是否为列表类型
 pub fn list_or_not(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval>{
-    if params.len() < 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <list?>: Missing argument", 0), index: 0 });
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <list?>: Too many argument", 0), index: 0 });
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("list?", params.len()));
     }
     else {
         match params[0].clone() {
             Value::NilValue => return Ok(Value::BooleanValue(true)),
-            Value::PairValue(_, cdr) => return list_or_not(vec![*cdr], env).map_err(|error| ErrorEval {
-                message: format!("{}: Builtin Procedure <list?>: Recursivly finding error...\n{}", error.index + 1, error.message),
-                index: error.index + 1
-            }),
+            Value::PairValue(_, cdr) => return list_or_not(vec![*cdr], env).map_err(|error| error.context("list?", "Recursivly finding error...")),
             _ => return Ok(Value::BooleanValue(false)),
         }
     }
@@ -262,11 +462,8 @@ pub fn list_or_not(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorE
 /// number? 内置过程
 /// 判断是否为数字类型
 pub fn number_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <number?>: Missing argument", 0), index: 0 });
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <number?>: Too many argument", 0), index: 0 });
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("number?", params.len()));
     }
     else {
         match params[0] {
@@ -278,11 +475,8 @@ pub fn number_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, Err
 
 /// null? 内置过程, 判断是否
 pub fn null_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <null?>: Missing argument", 0), index: 0 });
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <null?>: Too many argument", 0), index: 0 });
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("null?", params.len()));
     }
     else {
         match params[0] {
@@ -292,11 +486,8 @@ pub fn null_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, Error
     }
 }
 pub fn pair_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <pair?>: Missing argument", 0), index: 0 });
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <pair?>: Too many argument", 0), index: 0 });
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("pair?", params.len()));
     }
     else {
         match params[0] {
@@ -306,26 +497,16 @@ pub fn pair_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, Error
     }
 }
 pub fn procedure_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <procedure?>: Missing argument", 0), index: 0 });
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <procedure?>: Too many argument", 0), index: 0 });
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("procedure?", params.len()));
     }
     else {
-        match params[0] {
-            Value::ProcedureValue(_) => return Ok(Value::BooleanValue(true)),
-            Value::LambdaValue(_, _, _) => return Ok(Value::BooleanValue(true)),
-            _ => return Ok(Value::BooleanValue(false)),
-        }
+        return Ok(Value::BooleanValue(params[0].is_procedure()));
     }
 }
 pub fn string_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <string?>: Missing argument", 0), index: 0 });
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <string?>: Too many argument", 0), index: 0 });
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("string?", params.len()));
     }
     else {
         match params[0] {
@@ -335,11 +516,8 @@ pub fn string_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, Err
     }
 }
 pub fn symbol_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <symbol?>: Missing argument", 0), index: 0 });
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <symbol?>: Too many argument", 0), index: 0 });
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("symbol?", params.len()));
     }
     else {
         match params[0] {
@@ -351,11 +529,8 @@ pub fn symbol_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, Err
 /// 自己拓展的功能
 /// 检查某个符号是否已经在当前环境绑定
 pub fn defined_local_or_not(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <defined_local?>: Missing argument", 0), index: 0 });
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <defined_local?>: Too many argument", 0), index: 0 });
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("defined_local?", params.len()));
     }
     else {
         if env.symbol_map.borrow().contains_key(&params[0].to_string()) {
@@ -369,11 +544,8 @@ pub fn defined_local_or_not(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Valu
 /// 自己拓展的功能
 /// 检查某个符号是否已经在所有可见环境内绑定
 pub fn defined_all_or_not(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <defined_all?>: Missing argument", 0), index: 0 });
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <defined_all?>: Too many argument", 0), index: 0 });
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("defined_all?", params.len()));
     }
     else {
         let bind = env.find_binding(&params[0].to_string());
@@ -385,32 +557,24 @@ pub fn defined_all_or_not(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value,
         }
     }
 }
-/// ( append list1 ... ) 内置过程
-/// 将 list 内的元素按顺序拼接为一个新的列表. 
+/// ( append list1 ... obj ) 内置过程
+/// 将 list 内的元素按顺序拼接为一个新的列表, 最后一个参数作为结果的尾部, 可以不是列表.
 /// 返回值:拼接后的列表
 /// 实参个数为零时返回空表。
-pub fn append(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    let mut ret: Vec<Value> = Vec::new();
-    for param in params {
+pub fn append(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    let (tail, lists) = match params.split_last() {
+        Some((tail, lists)) => (tail.clone(), lists),
+        None => return Ok(Value::NilValue),
+    };
+    let mut items: Vec<Value> = Vec::new();
+    for param in lists {
         match param {
             Value::NilValue => (),
-            Value::PairValue(_, _) => {
-                // 注意这里可能逻辑实现有错误, 如果发生错误请立刻改正为忠实翻译
-                let result = param.to_vector();
-                if result.is_ok() {
-                    ret.append(result.unwrap().as_mut());
-                }
-                else {
-                    return Err(ErrorEval { message: format!("{}: Builtin Procedure <append>: Cannot append a procedure value", 0), index: 0 });
-                }
-            },
-            _ => return Err(ErrorEval { message: format!("{}: Builtin Procedure <append>: Cannot append a procedure value", 0), index: 0 }),
+            Value::PairValue(_, _) => items.append(&mut param.to_vector().map_err(|_| ErrorEval::type_error("append", "list", param))?),
+            _ => return Err(ErrorEval::type_error("append", "list", param)),
         }
     }
-    list(ret, env).map_err(|error| ErrorEval {
-        message: format!("{}: Builtin Procedure <append>: Fail to pack the result\n{}", error.index + 1, error.message),
-        index: error.index + 1
-    })
+    Ok(items.into_iter().rev().fold(tail, |tail, value| Value::PairValue(Box::new(value), Box::new(tail))))
 }
 /// ( push list value ) 自定义过程
 /// 将 value 加入到 list 末尾
@@ -430,76 +594,59 @@ pub fn push(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
                 }
                 else {
                     // panic!("Cannot append a procedure value.");
-                    return Err(ErrorEval { message: format!("{}: Builtin Procedure <push>: Cannot append a procedure value", 0), index: 0 });
+                    return Err(ErrorEval::runtime("push", "Cannot append a procedure value"));
                 }
             },
             Value::BooleanValue(_) => ret.push(param),
             Value::NumericValue(_) => ret.push(param),
             Value::StringValue(_) => ret.push(param),
             Value::SymbolValue(_) => ret.push(param),
-            _ => return Err(ErrorEval { message: format!("{}: Builtin Procedure <push>: Cannot append a procedure value", 0), index: 0 }),
+            _ => return Err(ErrorEval::runtime("push", "Cannot append a procedure value")),
         }
     }
-    list(ret, env).map_err(|error| ErrorEval {
-        message: format!("{}: Builtin Procedure <push>: Fail to pack the result\n{}", error.index + 1, error.message),
-        index: error.index + 1
-    })
+    list(ret, env).map_err(|error| error.context("push", "Fail to pack the result"))
 }
 pub fn car(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <car>: Missing argument", 0), index: 0 });
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <car>: Too many argument", 0), index: 0 });
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("car", params.len()));
     }
     else {
         match params[0].clone() {
             Value::PairValue(car, _) => return Ok(*car),
             // _ => panic!("Cannot get car of a non-pair/list type value."),
-            _ => return Err(ErrorEval { message: format!("{}: Builtin Procedure <length>: Cannot get car of a non-pair/list type value", 0), index: 0 })
+            _ => return Err(ErrorEval::type_error("car", "pair", &params[0]))
         }
     }
 }
 pub fn cdr(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <cdr>: Missing argument", 0), index: 0 });
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <cdr>: Too many argument", 0), index: 0 });
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("cdr", params.len()));
     }
     else {
         match params[0].clone() {
             Value::PairValue(_, cdr) => return Ok(*cdr),
             // _ => panic!("Cannot get car of a non-pair/list type value."),
-            _ => return Err(ErrorEval { message: format!("{}: Builtin Procedure <length>: Cannot get cdr of a non-pair/list type value", 0), index: 0 })
+            _ => return Err(ErrorEval::type_error("cdr", "pair", &params[0]))
         }
     }
 }
 pub fn cons(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 2 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <cons>: Missing argument", 0), index: 0 });
-    }
-    else if params.len() > 2 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <cons>: Too many argument", 0), index: 0 });
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("cons", params.len()));
     }
     else {
         Ok(Value::PairValue(Box::new(params[0].clone()), Box::new(params[1].clone())))
     }
 }
 pub fn length(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <length>: Missing argument", 0), index: 0 });
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <length>: Too many argument", 0), index: 0 });
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("length", params.len()));
     } 
     else {
         match params[0] {
+            Value::NilValue => return Ok(Value::NumericValue(0f64)),
             Value::PairValue(_, _) => {
-                let vec: Vec<Value> = params[0].to_vector().map_err(|error| ErrorEval{
-                    message: format!("{}: Builtin Procedure <length>: Missing argument\n{}", error.index + 1, error.message),
-                    index: error.index + 1
-                })?;
+                let vec: Vec<Value> = params[0].to_vector().map_err(|error| error.context("length", "Missing argument"))?;
                 if vec.len() == 1  {
                     match vec[0] {
                         Value::NilValue => return Ok(Value::NumericValue(0f64)),
@@ -510,243 +657,57 @@ pub fn length(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval>
             },
             _ => {
                 // panic!("TypeError. Cannot get length of a non-list value.");
-                return Err(ErrorEval { message: format!("{}: Builtin Procedure <length>: Cannot get length of a non-list value", 0), index: 0 })
+                return Err(ErrorEval::type_error("length", "list", &params[0]))
             },
         }
     }
 }
-pub fn list(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.is_empty() {
-        Ok(Value::NilValue)
-    }
-    else {
-        Ok(Value::PairValue(Box::new(params[0].clone()), Box::new(list(params[1..].to_vec(), env)?)))
-    }
+pub fn list(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    Ok(params.into_iter().rev().fold(Value::NilValue, |tail, value| Value::PairValue(Box::new(value), Box::new(tail))))
 }
+/// map 内置过程
+/// (map proc list ...) 将过程作用于各个列表中对应位置的元素, 以最短的列表为准
 pub fn map(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
     if params.len() < 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <map>: Missing argument", 0), index: 0});
-    }
-    else if params.len() > 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <map>: Too many argument", 0), index: 0});
-    }
-    else {
-        let args = params[1].to_vector();
-        if args.is_ok() {
-            let mut results: Vec<Value> = Vec::new();
-            match params[0].clone() {
-                Value::ProcedureValue(f) => {
-                    args.unwrap().iter().clone().try_for_each(|arg| -> Result<(), ErrorEval> {
-                        let arg: Value = f(vec![arg.clone()], Rc::clone(&env)).map_err(|error| ErrorEval{
-                            message: format!("{}: Builtin Procedure <map>: Fail to call the given procedure\n{}", error.index + 1, error.message),
-                            index: error.index + 1
-                        })?;
-                        results.push(arg);
-                        Ok(())
-                    })?;
-                    return list(results, Rc::clone(&env));
-                }
-                Value::LambdaValue(params, body, env_in_lambda) => {
-                    args.unwrap().iter().clone().try_for_each(|arg| -> Result<(), ErrorEval>{
-                        let arg: Value = {
-                            let args_in_lambda = vec![arg.clone()];
-                            let env_derived: Rc<EvalEnv> = env_in_lambda.clone().derive(*params.clone(), args_in_lambda).into();
-                            let mut result: Value = Value::NilValue;
-                            for bodyv in *body.clone() {
-                                result = env_derived.clone().eval(bodyv).map_err(|error| ErrorEval{
-                                    message: format!("{}: Builtin Procedure <map>: Fail to evaluate a value\n{}", error.index + 1, error.message),
-                                    index: error.index + 1
-                                })?;
-                            }
-                            result
-                        };
-                        results.push(arg);
-                        Ok(())
-                    })?;
-                    return list(results, env);
-
-                    /*results = args.unwrap().iter().cloned().map(|arg| -> Value {
-                        let args_in_lambda = vec![arg.clone()];
-                        let env_derived: Rc<EvalEnv> = env_in_lambda.clone().derive(*params.clone(), args_in_lambda).into();
-                        let mut result: Value = Value::NilValue;
-                        for bodyv in *body.clone() {
-                            result = env_derived.clone().eval(bodyv).map_err(|error| ErrorEval{
-                                message: format!("{}: Builtin Procedure <map>: Fail to evaluate a value\n{}", error.index + 1, error.message),
-                                index: error.index + 1
-                            })?;
-                        }
-                        arg
-                    }).collect();
-                    return list(results, env);*/
-                },
-                _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <map_expand>: Need a procedure", 0), index: 0}),
-            }
-        }
-        else {
-            return Err(ErrorEval{ message: format!("{}: Builtin Procedure <map_expand>: Cannot map a non-list value", 0), index: 0});
-        }
+        return Err(ErrorEval::arity("map", params.len()));
     }
+    let procedure: Value = procedure_param("map", &params[0])?;
+    let results: Vec<Value> = columns("map", &params[1..])?.into_iter()
+        .map(|args| env.apply_procedure(procedure.clone(), args))
+        .collect::<Result<_, _>>()?;
+    list(results, env)
 }
-pub fn map_expand(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <map_expand>: Missing argument", 0), index: 0});
+pub fn filter(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("filter", params.len()));
     }
-    let mut size: Option<usize> = None;
-    let mut vecs: Vec<Vec<Value>> = Vec::new();
+    let procedure: Value = procedure_param("filter", &params[0])?;
+    let args: Vec<Value> = params[1].to_vector().map_err(|_| ErrorEval::type_error("filter", "list", &params[1]))?;
     let mut results: Vec<Value> = Vec::new();
-    params[1..].iter().try_for_each(|param|->Result<(), ErrorEval> {
-        match param {
-            Value::PairValue(_, _) => {
-                // let vec = param.to_vector().expect("Corruption when converting a value to vector in procedure <map_expand>.");
-                let vec=  param.to_vector().map_err(|error| ErrorEval{
-                    message: format!("{}: Builtin Procedure <map_expand>: Fail to convert a value to vector\n{}", error.index + 1, error.message),
-                    index: error.index + 1
-                })?;
-                if size.is_none() { size = Some(vec.len()); vecs.push(vec); Ok(())}
-                else if size != Some(vec.len()) {
-                    // panic!("Error size in procedure <map_expand>: lists should have the same size.");
-                    return Err(ErrorEval { message: format!("{}: Builtin Procedure <map_expand>: Lists should have the same size", 0), index: 0 })
-                }
-                else { vecs.push(vec); Ok(())}
-            },
-            // _ => panic!("Error type in procedure <map_expand>: need a procedure."),
-            _ => return Err(ErrorEval { message: format!("{}: Builtin Procedure <map_expand>: Need a procedure", 0), index: 0 }),
-        }
-    })?;
-    for i in 0..size.unwrap() {
-        let mut temp_args: Vec<Value> = Vec::new();
-        vecs.iter().try_for_each(|vec| -> Result<(), ErrorEval> {
-            let arg: Value = env.clone().eval(vec[i].clone()).map_err(|error| ErrorEval{
-                message: format!("{}: Builtin Procedure <map_expand>: Need a procedure\n{}", error.index + 1, error.message),
-                index: error.index + 1,
-            })?;
-            temp_args.push(arg);
-            Ok(())
-        })?;
-        match params[0].clone() {
-            Value::ProcedureValue(f) => {
-                let result = f(temp_args, Rc::clone(&env)).map_err(|error| ErrorEval{
-                    message: format!("{}: Builtin Procedure <map_expand>: Fail to call the given procedure\n{}", error.index + 1, error.message),
-                    index: error.index + 1
-                })?;
-                results.push(result);
-            },
-            Value::LambdaValue(params_in_lambda, body, env_in_lambda) => {
-                let env_derived: Rc<EvalEnv> = env_in_lambda.derive(*params_in_lambda, temp_args).into();
-                let mut result: Value = Value::NilValue;
-                for bodyv in *body {
-                    result = env_derived.clone().eval(bodyv).map_err(|error| ErrorEval{
-                        message: format!("{}: Builtin Procedure <map_expand>: Fail to evaluate a value\n{}", error.index + 1, error.message),
-                        index: error.index + 1
-                    })?;
-                }
-                results.push(result);
-            },
-            _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <map_expand>: Need a procedure", 0), index: 0}),
+    for arg in args {
+        match env.apply_procedure(procedure.clone(), vec![arg.clone()])? {
+            Value::BooleanValue(false) => {},
+            _ => results.push(arg),
         }
     }
-    list(results, env).map_err(|error| ErrorEval{
-        message: format!("{}: Builtin Procedure <map_expand>: Fail to pack the result\n{}", error.index + 1, error.message),
-        index: error.index + 1
-    })
+    list(results, env)
 }
-pub fn filter(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <filter>: Missing argument", 0), index: 0});
-    }
-    else if params.len() > 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <filter>: Too many argument", 0), index: 0});
-    }
-    else {
-        let args = params[1].to_vector();
-        if args.is_ok() {
-            let mut results: Vec<Value> = Vec::new();
-            match params[0].clone() {
-                Value::ProcedureValue(f) => {
-                    for arg in args.unwrap() {
-                        let result: Value = f(vec![arg.clone()], Rc::clone(&env)).map_err(|error| ErrorEval{
-                            message: format!("{}: Builtin Procedure <filter>: Fail to call the given procedure\n{}", error.index + 1, error.message),
-                            index: error.index + 1
-                        })?;
-                        match result {
-                            Value::BooleanValue(false) => {},
-                            _ => results.push(arg.clone()),
-                        }
-                    }
-                    return list(results, env);
-                }
-                /*Value::LambdaValue(_, _) => {
-                    todo!();
-                },*/
-                Value::LambdaValue(params, body, env_in_lambda) => {
-                    for arg in args.unwrap() {
-                        let args_in_lambda = vec![arg];
-                        let env_derived: Rc<EvalEnv> = env_in_lambda.clone().derive(*params.clone(), args_in_lambda).into();
-                        let mut result: Value = Value::NilValue;
-                        for bodyv in *body.clone() {
-                            result = env_derived.clone().eval(bodyv).map_err(|error| ErrorEval{
-                                message: format!("{}: Builtin Procedure <filter>: Fail to evaluate a value\n{}", error.index + 1, error.message),
-                                index: error.index + 1
-                            })?;
-                        }
-                        match result {
-                            Value::BooleanValue(false) => continue,
-                            _ => results.push(result),
-                        }
-                    }
-                    return list(results, env);
+pub fn reduce(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("reduce", params.len()));
+    }
+    let procedure: Value = procedure_param("reduce", &params[0])?;
+    match params[1].clone() {
+        Value::PairValue(car, cdr) => {
+            match *cdr {
+                Value::NilValue => Ok(*car),
+                _ => {
+                    let rest: Value = reduce(vec![procedure.clone(), *cdr], Rc::clone(&env))?;
+                    env.apply_procedure(procedure, vec![*car, rest])
                 },
-                _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <reduce>: Need a procedure and a list", 0), index: 0}),
             }
-        }
-        else {
-            return Err(ErrorEval{ message: format!("{}: Builtin Procedure <reduce>: Need a procedure and a list", 0), index: 0});
-        }
-    }
-}
-pub fn reduce(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <reduce>: Missing argument", 0), index: 0});
-    }
-    else if params.len() > 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <reduce>: Too many argument", 0), index: 0});
-    }
-    else {
-        match (params[0].clone(), params[1].clone()) {
-            (Value::ProcedureValue(f), Value::PairValue(car, cdr)) => {
-                match *cdr {
-                    Value::NilValue => return Ok(*car),
-                    _ => {
-                        let args: Vec<Value> = vec![*car, reduce(vec![params[0].clone(), *cdr], Rc::clone(&env)).map_err(|error| ErrorEval{
-                            message: format!("{}: Builtin Procedure <reduce>: Recursive finding error...\n{}", error.index + 1, error.message),
-                            index: error.index + 1
-                        })?];
-                        return f(args, Rc::clone(&env));
-                    },
-                }
-            },
-            (Value::LambdaValue(params_in_lambda, body, env_in_lambda), Value::PairValue(car, cdr)) => {
-                match *cdr {
-                    Value::NilValue => return Ok(*car),
-                    _ => {
-                        let args: Vec<Value> = vec![*car, reduce(vec![params[0].clone(), *cdr], env).map_err(|error| ErrorEval{
-                            message: format!("{}: Builtin Procedure <reduce>: Recursivly finding error...\n{}", error.index + 1, error.message),
-                            index: error.index + 1
-                        })?];
-                        let env_derived: Rc<EvalEnv> = env_in_lambda.derive(*params_in_lambda, args).into();
-                        let mut result: Value = Value::NilValue;
-                        for bodyv in *body.clone() {
-                            result = env_derived.clone().eval(bodyv).map_err(|error| ErrorEval{
-                                message: format!("{}: Builtin Procedure <reduce>: Fail to evaluate a value\n{}", error.index + 1, error.message),
-                                index: error.index + 1
-                            })? //.expect("Corruption when evaluating a value in procedure <reduce>");
-                        }
-                        return Ok(result);
-                    }
-                }
-            },
-            _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <reduce>: need a procedure and a list", 0), index: 0}),
-        }
+        },
+        _ => Err(ErrorEval::type_error("reduce", "list", &params[1])),
     }
 }
 
@@ -755,29 +716,26 @@ pub fn add(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
     for param in params {
         match param {
             Value::NumericValue(n) => result += n,
-            _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'-'>: Cannot add a non-numeric value", 0), index: 0}),
+            other => return Err(ErrorEval::type_error("+", "number", &other)),
         }
     }
     Ok(Value::NumericValue(result))
 }
+/// 读取全部的数字参数
+fn numbers_param(procedure: &str, params: &[Value]) -> Result<Vec<f64>, ErrorEval> {
+    params.iter().map(|param| match param {
+        Value::NumericValue(n) => Ok(*n),
+        _ => Err(non_numeric(procedure, params)),
+    }).collect()
+}
+/// 减法内置过程
+/// 一个参数时取相反数, 多个参数时从第一个数中依次减去其余的数
 pub fn subtract(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'-'>: Missing argument", 0), index: 0});
-    }
-    else if params.len() == 1 {
-        match params[0].clone() {
-            Value::NumericValue(n) => return Ok(Value::NumericValue(-n)),
-            _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'-'>: Cannot subtract a non-numeric value", 0), index: 0}),
-        }
-    }
-    else if params.len() == 2 {
-        match (params[0].clone(), params[1].clone()) {
-            (Value::NumericValue(n1), Value::NumericValue(n2)) => return Ok(Value::NumericValue(n1 - n2)),
-            _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'-'>: Cannot do subtraction with a non-numeric value", 0), index: 0}),
-        }
-    }
-    else {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'-'>: Too many argument", 0), index: 0});
+    let numbers: Vec<f64> = numbers_param("-", &params)?;
+    match numbers.as_slice() {
+        [] => Err(ErrorEval::arity("-", params.len())),
+        [n] => Ok(Value::NumericValue(-n)),
+        [first, rest @ ..] => Ok(Value::NumericValue(rest.iter().fold(*first, |acc, n| acc - n))),
     }
 }
 pub fn multiply(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
@@ -785,84 +743,64 @@ pub fn multiply(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEva
     for param in params {
         match param {
             Value::NumericValue(n) => ret *= n,
-            _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'*'>: Cannot multiply non-numeric values", 0), index: 0}),
+            other => return Err(ErrorEval::type_error("*", "number", &other)),
         }
     }
     Ok(Value::NumericValue(ret))
 }
+/// 除法内置过程
+/// 一个参数时取倒数, 多个参数时用第一个数依次除以其余的数
 pub fn divide(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'/'>: Missing argument", 0), index: 0});
-    }
-    else if params.len() == 1 {
-        match params[0].clone() {
-            Value::NumericValue(n) if n != 0f64 => return Ok(Value::NumericValue(1f64 / n)),
-            Value::NumericValue(n) if n == 0f64 => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'/'>: Division by zero", 0), index: 0}),
-            _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'/'>: Cannot divide a non-numeric value", 0), index: 0}),
-        }
-    }
-    else if params.len() == 2 {
-        match (params[0].clone(), params[1].clone()) {
-            (Value::NumericValue(n1), Value::NumericValue(n2)) => return Ok(Value::NumericValue(n1 - n2)),
-            _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'/'>: Cannot divide a non-numeric value", 0), index: 0}),
-        }
-    }
-    else {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <divide>: Too many argument", 0), index: 0});
+    let numbers: Vec<f64> = numbers_param("/", &params)?;
+    let (first, rest) = match numbers.as_slice() {
+        [] => return Err(ErrorEval::arity("/", params.len())),
+        [n] => (1f64, std::slice::from_ref(n)),
+        [first, rest @ ..] => (*first, rest),
+    };
+    if rest.contains(&0f64) {
+        return Err(ErrorEval::new(ErrorKind::DivisionByZero).frame("/"));
     }
+    Ok(Value::NumericValue(rest.iter().fold(first, |acc, n| acc / n)))
 }
 pub fn abs(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <abs>: Missing argument", 0), index: 0});
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <abs>: Too many argument", 0), index: 0});
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("abs", params.len()));
     }
     else {
         match params[0] {
             Value::NumericValue(n) => return Ok(Value::NumericValue(n.abs())),
-            _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <abs>: Cannot do abs with non-numeric value", 0), index: 0}),
+            _ => return Err(non_numeric("abs", &params)),
         }
     }
 }
 pub fn expt(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <expt>: Missing argument", 0), index: 0});
-    }
-    else if params.len() > 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <expt>: Too many argument", 0), index: 0});
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("expt", params.len()));
     }
     else {
         match (params[0].clone(), params[1].clone()) {
-            (Value::NumericValue(base), Value::NumericValue(expo)) if base != 0f64 && expo != 0f64 => return Ok(Value::NumericValue(base.powf(expo))),
-            (Value::NumericValue(base), Value::NumericValue(expo)) if base == 0f64 && expo == 0f64 => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <expt>: Cannot calculate 0^0", 0), index: 0}),
-            _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <expt>: Cannot do quotient with non-numeric values", 0), index: 0}),
-            
+            // 按 R7RS 的约定, 0 的 0 次幂为 1
+            (Value::NumericValue(base), Value::NumericValue(expo)) => return Ok(Value::NumericValue(base.powf(expo))),
+            _ => return Err(non_numeric("expt", &params)),
         }
     }
 }
 pub fn quotient(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <quotient>: Missing argument", 0), index: 0});
-    }
-    else if params.len() > 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <quotient>: Too many argument", 0), index: 0});
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("quotient", params.len()));
     }
     else {
         match (params[0].clone(), params[1].clone()) {
             (Value::NumericValue(n0), Value::NumericValue(n1)) if n1 != 0f64 => return Ok(Value::NumericValue((n0 / n1) as i64 as f64)),
-            (Value::NumericValue(_), Value::NumericValue(n1)) if n1 == 0f64 => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <quotient>: Division by zero", 0), index: 0}),
-            _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <quotient>: Cannot do quotient with non-numeric values", 0), index: 0}),
+            (Value::NumericValue(_), Value::NumericValue(n1)) if n1 == 0f64 => return Err(ErrorEval::new(ErrorKind::DivisionByZero).frame("quotient")),
+            _ => return Err(non_numeric("quotient", &params)),
             
         }
     }
 }
 pub fn modulo(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <modulo>: Missing argument", 0), index: 0});
-    }
-    else if params.len() > 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <modulo>: Too many argument", 0), index: 0});
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("modulo", params.len()));
     }
     else {
         match (params[0].clone(), params[1].clone()) {
@@ -877,20 +815,17 @@ pub fn modulo(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval>
                     }
                 }
                 else {
-                    return Err(ErrorEval{ message: format!("{}: Builtin Procedure <modulo>: Cannot do modulo with non-integer values", 0), index: 0});
+                    return Err(non_integer("modulo", &params));
                 }
             }
-            (Value::NumericValue(_), Value::NumericValue(n1)) if n1 == 0f64 => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <remainder>: Division by zero", 0), index: 0}),
-            _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <modulo>: Cannot do modulo with non-numeric values", 0), index: 0}),
+            (Value::NumericValue(_), Value::NumericValue(n1)) if n1 == 0f64 => return Err(ErrorEval::new(ErrorKind::DivisionByZero).frame("modulo")),
+            _ => return Err(non_numeric("modulo", &params)),
         }
     }
 }
 pub fn remainder(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <remainder>: Missing argument", 0), index: 0});
-    }
-    else if params.len() > 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <remainder>: Too many argument", 0), index: 0});
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("remainder", params.len()));
     }
     else {
         match (params[0].clone(), params[1].clone()) {
@@ -899,159 +834,88 @@ pub fn remainder(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEv
                     return Ok(Value::NumericValue(n0 % n1));
                 }
                 else {
-                    return Err(ErrorEval{ message: format!("{}: Builtin Procedure <remainder>: Cannot do remainder with non-integer values.", 0), index: 0});
+                    return Err(non_integer("remainder", &params));
                 }
             }
             (Value::NumericValue(_), Value::NumericValue(n1)) if n1 == 0f64 
-                => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <remainder>: Division by zero", 0), index: 0}),
-            _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <remainder>: Cannot do remainder with non-numeric values", 0), index: 0}),
+                => return Err(ErrorEval::new(ErrorKind::DivisionByZero).frame("remainder")),
+            _ => return Err(non_numeric("remainder", &params)),
         }
     }
 }
-pub fn eq_q(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <eq?>: Missing argument", 0), index: 0});
-    }
-    else if params.len() > 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <eq?>: Too many argument", 0), index: 0});
+pub fn eq_q(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("eq?", params.len()));
     }
     else {
-        match (params[0].clone(), params[1].clone()) {
-            (Value::NumericValue(n0), Value::NumericValue(n1)) => return Ok(Value::BooleanValue(n0 == n1)),
-            (Value::BooleanValue(b0), Value::BooleanValue(b1)) => return Ok(Value::BooleanValue(b0 == b1)),
-            (Value::NilValue, Value::NilValue) => return Ok(Value::BooleanValue(true)),
-            (Value::SymbolValue(s0), Value::SymbolValue(s1)) => return Ok(Value::BooleanValue(s0 == s1)),
-            (Value::StringValue(s0), Value::StringValue(s1)) => return Ok(Value::BooleanValue(s0 == s1)),
-            (Value::PairValue(car0, cdr0), Value::PairValue(car1, cdr1)) => {
-                match eq_q(vec![*car0, *car1].to_vec(), Rc::clone(&env))? {
-                    v @ Value::BooleanValue(false) => return Ok(v),
-                    Value::BooleanValue(true) => return eq_q(vec![*cdr0, *cdr1].to_vec(), Rc::clone(&env)),
-                    _ => panic!("You should never see this message."),
-                }
-            },
-            (Value::ProcedureValue(f0), Value::ProcedureValue(f1)) => 
-                return Ok(Value::BooleanValue(std::ptr::eq(&*f0, &*f1))),
-            // 我直接规定, 任何两个lambda表达式都是不一样的! 如何?!
-            (Value::LambdaValue(_params_in_lambda_0, _body0, _env_in_lambda_0), 
-            Value::LambdaValue(_params_in_lambda_1, _body1, _env_in_lambda1)) 
-            => return Ok(Value::BooleanValue(false)),
-            _ => return Ok(Value::BooleanValue(false)),
-        }
+        return Ok(Value::BooleanValue(eq_values(&params[0], &params[1])));
     }
 }
-pub fn equal_q(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <equal?>: Missing argument", 0), index: 0});
+/// eq? 的比较规则
+/// 过程与宿主对象比较是否为同一个对象, 对子逐个比较, 任何两个lambda表达式都是不一样的
+fn eq_values(v0: &Value, v1: &Value) -> bool {
+    match (v0, v1) {
+        (Value::NumericValue(n0), Value::NumericValue(n1)) => n0 == n1,
+        (Value::BooleanValue(b0), Value::BooleanValue(b1)) => b0 == b1,
+        (Value::NilValue, Value::NilValue) => true,
+        (Value::SymbolValue(s0), Value::SymbolValue(s1)) => s0 == s1,
+        (Value::StringValue(s0), Value::StringValue(s1)) => s0 == s1,
+        (Value::PairValue(car0, cdr0), Value::PairValue(car1, cdr1)) => eq_values(car0, car1) && eq_values(cdr0, cdr1),
+        (Value::ProcedureValue(f0), Value::ProcedureValue(f1)) => Rc::ptr_eq(f0, f1),
+        (Value::ForeignValue(o0), Value::ForeignValue(o1)) => Rc::ptr_eq(o0, o1),
+        _ => false,
     }
-    else if params.len() > 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <equal?>: Too many argument", 0), index: 0});
+}
+pub fn equal_q(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("equal?", params.len()));
     }
     else {
-        return Ok(Value::BooleanValue(params[0].to_string() == params[1].to_string()));
+        return Ok(Value::BooleanValue(equal_values(&params[0], &params[1])));
     }
 }
-pub fn not(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <not>: Missing argument", 0), index: 0});
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <not>: Too many argument", 0), index: 0});
+/// equal? 的比较规则
+/// 宿主对象交给其 equals 方法比较, 对子逐个比较, 其余值比较外部表示
+fn equal_values(v0: &Value, v1: &Value) -> bool {
+    match (v0, v1) {
+        (Value::ForeignValue(o0), Value::ForeignValue(o1)) => Rc::ptr_eq(o0, o1) || o0.equals(&**o1),
+        (Value::PairValue(car0, cdr0), Value::PairValue(car1, cdr1)) => equal_values(car0, car1) && equal_values(cdr0, cdr1),
+        _ => v0.to_string() == v1.to_string(),
     }
-    else {
-        let result = env.eval(params[0].clone());
-        if result.is_ok() {
-            match result.unwrap() {
-                Value::BooleanValue(false) => return Ok(Value::BooleanValue(true)),
-                Value::BooleanValue(true) => return Ok(Value::BooleanValue(false)),
-                _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <not>: Unknown Error", 0), index: 0}),
-            }
-        }
-        else {
-            match params[0] {
-                Value::NilValue => return Ok(Value::BooleanValue(false)),
-                Value::PairValue(_, _) => return Ok(Value::BooleanValue(false)),
-                Value::SymbolValue(_) => return Ok(Value::BooleanValue(false)),
-                _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <not>: Unknown Error", 0), index: 0}),
-            }
-        }
+}
+pub fn not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("not", params.len()));
     }
+    // 参数已经求值, 只有 #f 为假
+    Ok(Value::BooleanValue(matches!(params[0], Value::BooleanValue(false))))
 }
-pub fn equal_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+/// 数值比较内置过程的公共部分, 每一对相邻的参数都满足compare时为 #t
+fn compare_numbers(procedure: &str, params: &[Value], compare: fn(f64, f64) -> bool) -> Result<Value, ErrorEval> {
     if params.len() < 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'='>: Missing argument", 0), index: 0});
-    }
-    else if params.len() > 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'='>: Too many argument", 0), index: 0});
-    }
-    else {
-        match (params[0].clone(), params[1].clone()) {
-            (Value::NumericValue(n0), Value::NumericValue(n1)) => return Ok(Value::BooleanValue(n0 == n1)),
-            _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'='>: Cannot compare a non-numeric values", 0), index: 0}),
-        } 
+        return Err(ErrorEval::arity(procedure, params.len()));
     }
+    let numbers: Vec<f64> = numbers_param(procedure, params)?;
+    Ok(Value::BooleanValue(numbers.windows(2).all(|pair| compare(pair[0], pair[1]))))
+}
+pub fn equal_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    compare_numbers("=", &params, |n0, n1| n0 == n1)
 }
 pub fn less_than_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'<'>: Missing argument", 0), index: 0});
-    }
-    else if params.len() > 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'<'>: Too many argument", 0), index: 0});
-    }
-    else {
-        match (params[0].clone(), params[1].clone()) {
-            (Value::NumericValue(n0), Value::NumericValue(n1)) => return Ok(Value::BooleanValue(n0 < n1)),
-            _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'<'>: Cannot compare a non-numeric values", 0), index: 0}),
-        } 
-    }
+    compare_numbers("<", &params, |n0, n1| n0 < n1)
 }
 pub fn more_than_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'>'>: Missing argument", 0), index: 0});
-    }
-    else if params.len() > 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'>'>: Too many argument", 0), index: 0});
-    }
-    else {
-        match (params[0].clone(), params[1].clone()) {
-            (Value::NumericValue(n0), Value::NumericValue(n1)) => return Ok(Value::BooleanValue(n0 > n1)),
-            _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'>'>: Cannot compare a non-numeric values", 0), index: 0}),
-        } 
-    }
+    compare_numbers(">", &params, |n0, n1| n0 > n1)
 }
 pub fn less_than_or_equal_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'<='>: Missing argument", 0), index: 0});
-    }
-    else if params.len() > 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'<='>: Too many argument", 0), index: 0});
-    }
-    else {
-        match (params[0].clone(), params[1].clone()) {
-            (Value::NumericValue(n0), Value::NumericValue(n1)) => return Ok(Value::BooleanValue(n0 <= n1)),
-            _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'<='>: Cannot compare a non-numeric values", 0), index: 0}),
-        } 
-    }
+    compare_numbers("<=", &params, |n0, n1| n0 <= n1)
 }
 pub fn more_than_or_equal_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'>='>: Missing argument", 0), index: 0});
-    }
-    else if params.len() > 2 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'>='>: Too many argument", 0), index: 0});
-    }
-    else {
-        match (params[0].clone(), params[1].clone()) {
-            (Value::NumericValue(n0), Value::NumericValue(n1)) => return Ok(Value::BooleanValue(n0 >= n1)),
-            _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <'>='>: Cannot compare a non-numeric values", 0), index: 0}),
-        } 
-    }
+    compare_numbers(">=", &params, |n0, n1| n0 >= n1)
 }
 pub fn even_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <even?>: Missing argument", 0), index: 0});
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <even?>: Too many argument", 0), index: 0});
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("even?", params.len()));
     }
     else {
         match params[0] {
@@ -1060,19 +924,16 @@ pub fn even_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, Error
                     return Ok(Value::BooleanValue(n as i32 % 2 == 0));
                 }
                 else {
-                    return Err(ErrorEval{ message: format!("{}: Builtin Procedure <even?>: Cannot judge even/odd with a non-integer number", 0), index: 0});
+                    return Err(non_integer("even?", &params));
                 }
             },
-            _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <even?>: Cannot compare a non-numeric values", 0), index: 0}),
+            _ => return Err(non_numeric("even?", &params)),
         }
     }
 }
 pub fn odd_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <odd?>: Missing argument", 0), index: 0});
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <odd?>: Too many argument", 0), index: 0});
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("odd?", params.len()));
     }
     else {
         match params[0] {
@@ -1081,33 +942,45 @@ pub fn odd_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorE
                     return Ok(Value::BooleanValue(n as i32 % 2 == 1));
                 }
                 else {
-                    return Err(ErrorEval{ message: format!("{}: Builtin Procedure <odd?>: Cannot judge even/odd with a non-integer number", 0), index: 0});
+                    return Err(non_integer("odd?", &params));
                 }
             },
-            _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <odd?>: Cannot compare a non-numeric values", 0), index: 0}),
+            _ => return Err(non_numeric("odd?", &params)),
         }
     }
 }
 pub fn zero_or_not(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if params.len() < 1 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <zero?>: Missing argument", 0), index: 0});
-    }
-    else if params.len() > 1 {
-        return Err(ErrorEval{ message: format!("{}: Builtin Procedure <zero?>: Too many argument", 0), index: 0});
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("zero?", params.len()));
     }
     else {
         match params[0] {
             Value::NumericValue(n) => return Ok(Value::BooleanValue(n == 0f64)),
-            _ => return Err(ErrorEval{ message: format!("{}: Builtin Procedure <zero?>: Cannot compare a non-numeric values", 0), index: 0}),
+            _ => return Err(non_numeric("zero?", &params)),
         }
     }
 }
-pub fn sort(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    // todo!();
-    if params.len() < 1 {
-        return Err(ErrorEval { message: format!("{}: Builtin Procedure <sort?>: Missing argument", 0), index: 0 });
+/// sort 内置过程
+/// 将全部由数字或全部由字符串组成的列表按升序排序
+pub fn sort(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("sort", params.len()));
+    }
+    let mut items: Vec<Value> = params[0].to_vector().map_err(|error| error.context("sort", "Fail to convert a value to vector"))?;
+    if items.iter().all(|item| matches!(item, Value::NumericValue(_))) {
+        items.sort_by(|a, b| match (a, b) {
+            (Value::NumericValue(n0), Value::NumericValue(n1)) => n0.total_cmp(n1),
+            _ => std::cmp::Ordering::Equal,
+        });
+    }
+    else if items.iter().all(|item| matches!(item, Value::StringValue(_))) {
+        items.sort_by(|a, b| match (a, b) {
+            (Value::StringValue(s0), Value::StringValue(s1)) => s0.cmp(s1),
+            _ => std::cmp::Ordering::Equal,
+        });
     }
     else {
-        todo!();
+        return Err(ErrorEval::runtime("sort", "Can only sort a list of numbers or a list of strings"));
     }
+    list(items, env)
 }
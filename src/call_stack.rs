@@ -0,0 +1,124 @@
+/// 定义了Lisp层面的调用栈
+/// 求值器在调用过程时压入调用表达式, 调用结束时弹出
+/// 出错时把调用栈拍成快照放进错误里, 由顶层按最近的调用在最后的顺序打印
+use std::cell::{Cell, RefCell};
+use crate::error::ErrorEval;
+use crate::value::Value;
+
+/// 错误中默认保留的帧数
+pub const DEFAULT_TRACE_DEPTH: usize = 20;
+
+/// 调用表达式打印时的最大长度
+const CALL_SITE_WIDTH: usize = 60;
+
+/// 打印表达式, 过长时截断
+pub fn abbreviate(value: &Value) -> String {
+    let text: String = value.to_string();
+    if text.chars().count() > CALL_SITE_WIDTH {
+        text.chars().take(CALL_SITE_WIDTH - 3).collect::<String>() + "..."
+    }
+    else {
+        text
+    }
+}
+
+/// 快照中的一帧
+/// procedure: 被调用过程的名字, 为调用表达式的第一个符号, 计算得到的过程记为 <anonymous>
+/// call_site: 调用表达式
+#[derive(Debug, Clone)]
+pub struct TraceFrame {
+    pub procedure: String,
+    pub call_site: String,
+}
+
+/// 出错时调用栈的快照
+/// frames 由外向内排列, 最近的调用在最后
+/// omitted: 超出保留深度而被省略的外层帧数
+/// inner_frames: 快照时错误已经经过的内部帧数, 即出错的过程内部的帧, 之后的帧已由调用栈给出
+#[derive(Debug, Clone)]
+pub struct Traceback {
+    pub frames: Vec<TraceFrame>,
+    pub omitted: usize,
+    pub inner_frames: usize,
+}
+
+/// 调用栈, 由一个求值环境及其派生出的所有环境共享
+/// depth: 出错时快照保留的帧数, 为0时不保留
+pub struct CallStack {
+    calls: RefCell<Vec<Value>>,
+    depth: Cell<usize>,
+}
+
+/// 一次调用的记录, 离开调用时自动弹出
+pub struct CallGuard<'a> {
+    stack: &'a CallStack,
+}
+
+impl Drop for CallGuard<'_> {
+    fn drop(&mut self) {
+        self.stack.calls.borrow_mut().pop();
+    }
+}
+
+impl CallStack {
+    /// 新建空的调用栈
+    pub fn new() -> Self {
+        Self { calls: RefCell::new(Vec::new()), depth: Cell::new(DEFAULT_TRACE_DEPTH) }
+    }
+
+    /// 快照保留的帧数
+    pub fn depth(&self) -> usize {
+        self.depth.get()
+    }
+
+    /// 设置快照保留的帧数
+    pub fn set_depth(&self, depth: usize) {
+        self.depth.set(depth);
+    }
+
+    /// 当前的调用层数
+    #[allow(dead_code)]
+    pub fn height(&self) -> usize {
+        self.calls.borrow().len()
+    }
+
+    /// 进入一次调用
+    pub fn enter(&self, call: Value) -> CallGuard<'_> {
+        self.calls.borrow_mut().push(call);
+        CallGuard { stack: self }
+    }
+
+    /// 拍下当前调用栈的快照
+    pub fn snapshot(&self) -> Traceback {
+        let calls = self.calls.borrow();
+        let omitted: usize = calls.len().saturating_sub(self.depth.get());
+        let frames: Vec<TraceFrame> = calls[omitted..].iter().map(|call| {
+            let procedure: String = match call {
+                Value::PairValue(car, _) => match car.as_ref() {
+                    Value::SymbolValue(name) => name.clone(),
+                    _ => "<anonymous>".to_string(),
+                },
+                _ => "<anonymous>".to_string(),
+            };
+            TraceFrame { procedure, call_site: abbreviate(call) }
+        }).collect();
+        Traceback { frames, omitted, inner_frames: 0 }
+    }
+
+    /// 错误第一次穿过调用边界时记下快照, 此时调用栈最深
+    /// 退出请求与调试器的中止请求不是错误, 不需要快照
+    pub fn capture(&self, mut error: ErrorEval) -> ErrorEval {
+        if error.traceback.is_none() && !error.is_unwinding() && self.depth.get() > 0 {
+            let mut traceback: Traceback = self.snapshot();
+            traceback.inner_frames = error.backtrace.len();
+            error.traceback = Some(traceback);
+        }
+        error
+    }
+}
+
+impl Default for CallStack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,62 +1,247 @@
 /// 命令行解析模块
 
 use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use crate::eval_env::EvalEnv;
 use crate::reader_interact::ReaderInteract;
 use crate::reader_file::ReaderFile;
-const HELP_FILE: &str = "-i | --interract 交互式\n-h | --help 打开该说明文档\n-f | --file 文件模式, 并且附上输入文件路径\n输入> 后接文件名可将输出导入至该文件.";
+use crate::unit_test::discover;
+use crate::output::{Output, diff};
+const HELP_FILE: &str = "用法: minilisp [选项] [脚本 [参数 ...]]
+      minilisp test [目录]
+test [目录] 运行目录(缺省为当前目录)及其子目录下所有 *-test.scm 中的单元测试, 有测试不通过时以 1 退出
+-i | --interract 交互式 (不给出脚本时的默认模式)
+-h | --help 打开该说明文档
+-f | --file <路径> 文件模式, 并且附上输入文件路径
+-e | --eval <表达式> 对命令行中给出的表达式求值
+-o | --output <路径> 将顶层表达式的结果写入该文件
+--check <路径> 运行脚本, 将 display 等的输出与顶层表达式的结果和该文件比较, 不同时打印差异并以 1 退出
+--bless 与 --check 一起使用, 用实际的输出更新该文件
+--debug 调试模式, 从第一个表达式开始单步执行, 未捕获的错误之后进入交互模式
+-  从标准输入读取程序
+-- 结束选项, 之后的第一个参数为脚本, 其余为脚本参数
+脚本之后的所有参数都原样传给脚本, 可以用 (command-line) 取得.
+退出码: 0 正常结束, 65 语法错误, 70 运行时错误, 127 读写错误, (exit n) 以 n 退出.";
 pub fn run(config: Config) -> Result<(), Box<dyn Error>> {
-    match (config.interract_mode, config.open_help, config.input_file_path, config.output_file_path) {
-        (true, false, None, None) => {
-            let mut reader_interact: ReaderInteract = ReaderInteract::new();
-            reader_interact.call();
-            Ok(())
-        },
-        (false, true, None, None) => {
-            println!("{}", HELP_FILE);
-            Ok(())
+    if config.open_help {
+        if config.interract_mode || config.input_file_path.is_some() || config.expression.is_some() {
+            return Err("Conflict occur.\nPlease use 'minilisp -h' or 'minilisp --help' to check the usage".into());
+        }
+        println!("{}", HELP_FILE);
+        return Ok(());
+    }
+    if let Some(dir) = config.test_dir {
+        if config.interract_mode || config.input_file_path.is_some() || config.expression.is_some() || config.output_file_path.is_some() {
+            return Err("Conflict occur.\nPlease use 'minilisp -h' or 'minilisp --help' to check the usage".into());
+        }
+        return run_tests(Path::new(&dir));
+    }
+    if config.bless && config.check_file.is_none() {
+        return Err("--bless should be used together with --check".into());
+    }
+    if config.check_file.is_some() && (config.interract_mode || config.debug || config.output_file_path.is_some() || (config.input_file_path.is_none() && config.expression.is_none())) {
+        return Err("Conflict occur.\nPlease use 'minilisp -h' or 'minilisp --help' to check the usage".into());
+    }
+    // (command-line) 的第一个元素为脚本名, 交互模式与 -e 时为程序名
+    let mut command_line: Vec<String> = vec![config.input_file_path.clone().unwrap_or(config.program_name.clone())];
+    command_line.extend(config.script_args.iter().cloned());
+    let env: Rc<EvalEnv> = Rc::new(EvalEnv::with_command_line(command_line));
+    if config.debug {
+        env.debugger.step();
+    }
+    // --check 时捕获输出, 求值结束后与期望的输出比较
+    let output: Rc<Output> = env.output.clone();
+    if config.check_file.is_some() {
+        output.capture();
+    }
+    // 解释器内部不会结束进程, 由这里根据返回的退出码结束
+    let code: i32 = match (config.interract_mode, config.input_file_path, config.expression, config.output_file_path) {
+        (_, None, None, None) => {
+            let mut reader_interact: ReaderInteract = ReaderInteract::with_env(env);
+            reader_interact.call()
         },
-        (false, false, Some(in_path), None) => {
-            let mut reader_file: ReaderFile = ReaderFile::new(Some(in_path), None);
-            reader_file.call();
-            Ok(())
+        (false, Some(in_path), None, out_path) => {
+            let mut reader_file: ReaderFile = ReaderFile::with_env(Some(in_path), out_path, env.clone());
+            let code: i32 = reader_file.call();
+            post_mortem(config.debug && reader_file.failed(), code, env)
         },
-        (false, false, Some(in_path), Some(out_path)) => {
-            let mut reader_file: ReaderFile = ReaderFile::new(Some(in_path), Some(out_path));
-            reader_file.call();
-            Ok(()) 
+        (false, None, Some(expression), out_path) => {
+            let mut reader_file: ReaderFile = ReaderFile::from_expression(expression, out_path, env.clone());
+            let code: i32 = reader_file.call();
+            post_mortem(config.debug && reader_file.failed(), code, env)
         },
         _ => return Err("Conflict occur.\nPlease use 'minilisp -h' or 'minilisp --help' to check the usage".into()),
+    };
+    let code: i32 = match config.check_file {
+        Some(path) => check(&path, &output.take(), config.bless, code)?,
+        None => code,
+    };
+    if code != 0 {
+        std::process::exit(code);
+    }
+    Ok(())
+}
+
+/// --check: 比较捕获到的输出actual与文件path, 不同时打印差异并返回 1, 相同时返回脚本的退出码code
+/// --bless: 将actual写入文件path
+fn check(path: &str, actual: &str, bless: bool, code: i32) -> Result<i32, Box<dyn Error>> {
+    if bless {
+        std::fs::write(path, actual).map_err(|e| format!("Cannot write {}: {}", path, e))?;
+        println!("Blessed {}", path);
+        return Ok(code);
+    }
+    let expected: String = std::fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}, use --bless to create it", path, e))?;
+    match diff(&expected, actual) {
+        None => Ok(code),
+        Some(difference) => {
+            println!("--- {}\n+++ actual output\n{}", path, difference);
+            Ok(1)
+        },
     }
 }
 
+/// minilisp test: 在新的求值环境中逐个运行测试文件, 最后打印汇总
+/// 测试文件求值出错, 或者有测试组没有结束时, 也记为一个不通过的测试
+fn run_tests(dir: &Path) -> Result<(), Box<dyn Error>> {
+    let paths: Vec<PathBuf> = discover(dir)?;
+    if paths.is_empty() {
+        return Err(format!("No *-test.scm found in {}", dir.display()).into());
+    }
+    let (mut passed, mut failed) = (0, 0);
+    for path in paths.iter() {
+        let name: String = path.display().to_string();
+        println!("== {}", name);
+        let env: Rc<EvalEnv> = Rc::new(EvalEnv::with_command_line(vec![name.clone()]));
+        let code: i32 = ReaderFile::with_env(Some(name.clone()), None, env.clone()).call();
+        passed += env.test_runner.passed();
+        failed += env.test_runner.failed();
+        if code != 0 {
+            failed += 1;
+            println!("FAIL {}: exited with code {}", name, code);
+        }
+        let open: Vec<String> = env.test_runner.open_groups();
+        if !open.is_empty() {
+            failed += 1;
+            println!("FAIL {}: test-begin without test-end: {}", name, open.join(", "));
+        }
+    }
+    println!("{} passed, {} failed in {} files", passed, failed, paths.len());
+    if failed > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// 调试模式下出现未捕获的错误时进入交互模式, 可以在出错时的全局环境中继续检查
+/// 交互模式正常退出时仍然使用出错的退出码
+fn post_mortem(failed: bool, code: i32, env: Rc<EvalEnv>) -> i32 {
+    if !failed {
+        return code;
+    }
+    println!("Entering the REPL after an uncaught error. Type ,quit to leave.");
+    let mut reader_interact: ReaderInteract = ReaderInteract::with_env(env);
+    match reader_interact.call() {
+        0 => code,
+        repl_code => repl_code,
+    }
+}
+
+/// 命令行配置
+/// input_file_path 为"-"时从标准输入读取程序
+/// script_args: 传给脚本的参数
+/// debug: 调试模式
+/// test_dir: minilisp test 的测试目录
+/// check_file: --check 给出的期望输出文件, bless: 是否用实际的输出更新该文件
 pub struct Config {
+    pub program_name: String,
     pub interract_mode: bool,
     pub open_help: bool,
     pub input_file_path: Option<String>,
     pub output_file_path: Option<String>,
+    pub expression: Option<String>,
+    pub script_args: Vec<String>,
+    pub debug: bool,
+    pub test_dir: Option<String>,
+    pub check_file: Option<String>,
+    pub bless: bool,
 }
 impl Config {
-    pub fn build(mut args: impl Iterator<Item = String>) -> Result<Config, &'static str> {
+    pub fn build(mut args: impl Iterator<Item = String>) -> Result<Config, String> {
         let mut interract_mode: bool = false;
         let mut open_help: bool = false;
         let mut input_file_path: Option<String> = None;
-        let output_file_path: Option<String> = None;
-        args.next();
+        let mut output_file_path: Option<String> = None;
+        let mut expression: Option<String> = None;
+        let mut script_args: Vec<String> = Vec::new();
+        let mut debug: bool = false;
+        let mut check_file: Option<String> = None;
+        let mut bless: bool = false;
+        let program_name: String = args.next().unwrap_or("minilisp".to_string());
+        let mut args = args.peekable();
+        // 子命令只能是第一个参数
+        let mut test_dir: Option<String> = None;
+        if args.peek().is_some_and(|s| s == "test") {
+            args.next();
+            test_dir = Some(args.next_if(|s| !s.starts_with('-')).unwrap_or(".".to_string()));
+        }
         loop {
             match args.next() {
                 None => break,
-                Some(s) if s == "-i".to_string() || s == "--interract".to_string() => interract_mode = true,
-                Some(s) if s == "-h".to_string() || s == "--help".to_string() => open_help = true,
-                Some(s) if s == "-f".to_string() || s == "--file".to_string() => {
+                Some(s) if s == "-i" || s == "--interract" => interract_mode = true,
+                Some(s) if s == "-h" || s == "--help" => open_help = true,
+                Some(s) if s == "--debug" => debug = true,
+                Some(s) if s == "--bless" => bless = true,
+                Some(s) if s == "--check" => {
                     match args.next() {
-                        None => return Err("Should give an input file path"),
+                        None => return Err("Should give an expected output file path".to_string()),
+                        Some(path) => check_file = Some(path),
+                    }
+                },
+                Some(s) if s == "-f" || s == "--file" => {
+                    match args.next() {
+                        None => return Err("Should give an input file path".to_string()),
                         Some(path) => input_file_path = Some(path),
                     }
                 },
-                Some(s) if s == "-o".to_string() || s == "--output".to_string() => return Err("Unknown"),
-                _ => return Err("Fail to parse the command, please retry"),
+                Some(s) if s == "-o" || s == "--output" => {
+                    match args.next() {
+                        None => return Err("Should give an output file path".to_string()),
+                        Some(path) => output_file_path = Some(path),
+                    }
+                },
+                Some(s) if s == "-e" || s == "--eval" => {
+                    match args.next() {
+                        None => return Err("Should give an expression to evaluate".to_string()),
+                        Some(expr) => {
+                            // 多个 -e 依次求值
+                            match expression.as_mut() {
+                                None => expression = Some(expr),
+                                Some(previous) => { previous.push('\n'); previous.push_str(&expr); },
+                            }
+                        },
+                    }
+                },
+                // 脚本(或标准输入)之后的参数全部交给脚本
+                Some(s) if s == "--" || s == "-" || !s.starts_with('-') => {
+                    let mut rest: Vec<String> = Vec::new();
+                    if s != "--" {
+                        rest.push(s);
+                    }
+                    rest.extend(args.by_ref());
+                    let mut rest = rest.into_iter();
+                    if input_file_path.is_none() && expression.is_none() {
+                        match rest.next() {
+                            None => {},
+                            Some(path) => input_file_path = Some(path),
+                        }
+                    }
+                    script_args.extend(rest);
+                    break;
+                },
+                Some(s) => return Err(format!("Unknown option {s}, please use 'minilisp -h' to check the usage")),
             }
         }
-        Ok(Config { interract_mode, open_help, input_file_path, output_file_path })
+        Ok(Config { program_name, interract_mode, open_help, input_file_path, output_file_path, expression, script_args, debug, test_dir, check_file, bless })
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,162 @@
+/// 定义了一致性测试的运行器
+/// 测试文件是普通的 .scm 源文件, 其中的测试用例写作
+/// (test expected expr): expected 与 expr 的值满足 equal? 时通过
+/// (test-error expr): 对 expr 求值出错时通过
+/// 其余的表达式(define 等)照常求值, 出错时也记为失败的用例
+/// 已知不通过的用例写在同名的 .xfail 文件中, 每行一个用例名, 用例名是被测表达式的外部表示, 以 ; 开头的行是注释
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use crate::builtins::equal_q;
+use crate::error::{ErrorEval, ErrorKind, ErrorRead};
+use crate::eval_env::EvalEnv;
+use crate::parse::Parser;
+use crate::tokenizer::{Tokenizer, IncrementalReader};
+use crate::value::Value;
+
+/// 用例的结果
+/// Pass: 通过, Fail: 不通过, 附带原因
+/// ExpectedFail: 在 .xfail 中且确实不通过, UnexpectedPass: 在 .xfail 中却通过了, 应当把它从列表中移除
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Pass,
+    Fail(String),
+    ExpectedFail,
+    UnexpectedPass,
+}
+
+/// 一个用例: 所在行号, 用例名与结果
+#[derive(Debug, Clone)]
+pub struct Case {
+    pub line: usize,
+    pub name: String,
+    pub outcome: Outcome,
+}
+
+/// 一个测试文件的运行报告
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub path: PathBuf,
+    pub cases: Vec<Case>,
+}
+
+impl Report {
+    /// 通过的用例数, 包括预期不通过的用例
+    pub fn passed(&self) -> usize {
+        self.cases.iter().filter(|case| matches!(case.outcome, Outcome::Pass | Outcome::ExpectedFail)).count()
+    }
+
+    /// 不通过的用例数, 包括意外通过的用例
+    pub fn failed(&self) -> usize {
+        self.cases.len() - self.passed()
+    }
+}
+
+/// 逐个列出结果不是 PASS 的用例, 最后给出汇总
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for case in self.cases.iter() {
+            let location: String = format!("{}:{}", self.path.display(), case.line);
+            match &case.outcome {
+                Outcome::Pass => {},
+                Outcome::Fail(reason) => writeln!(f, "FAIL  {} {}\n      {}", location, case.name, reason)?,
+                Outcome::ExpectedFail => writeln!(f, "XFAIL {} {}", location, case.name)?,
+                Outcome::UnexpectedPass => writeln!(f, "XPASS {} {}", location, case.name)?,
+            }
+        }
+        let expected: usize = self.cases.iter().filter(|case| case.outcome == Outcome::ExpectedFail).count();
+        write!(f, "{}: {} passed, {} failed, {} expected failures", self.path.display(), self.passed() - expected, self.failed(), expected)
+    }
+}
+
+/// 读取测试文件旁边的 .xfail 文件, 不存在时为空
+pub fn expected_failures(path: &Path) -> Vec<String> {
+    match std::fs::read_to_string(path.with_extension("xfail")) {
+        Ok(text) => text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with(';')).map(String::from).collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// 在新的全局环境中运行测试文件
+pub fn run_file(path: &Path) -> Result<Report, ErrorEval> {
+    let source: String = std::fs::read_to_string(path).map_err(|e| {
+        ErrorEval::new(ErrorKind::Runtime(format!("{:?}: {}: {}", ErrorRead::FileOpenError, path.display(), e)))
+    })?;
+    let mut report: Report = run_source(&source, &expected_failures(path), Rc::new(EvalEnv::new()))?;
+    report.path = path.to_path_buf();
+    Ok(report)
+}
+
+/// 在环境env中运行一段测试源代码, xfail为预期不通过的用例名
+/// 源代码本身无法读取, 或者有用例请求退出或被调试器中止时返回错误, 其余用例的错误都记录在报告中
+pub fn run_source(source: &str, xfail: &[String], env: Rc<EvalEnv>) -> Result<Report, ErrorEval> {
+    let mut reader: IncrementalReader = IncrementalReader::new();
+    reader.feed(source);
+    reader.finish();
+    let mut cases: Vec<Case> = Vec::new();
+    loop {
+        let datum = match reader.next_datum() {
+            Ok(Some(datum)) => datum,
+            Ok(None) if reader.is_pending() => return Err(ErrorEval::new(ErrorKind::BadSyntax(format!("{:?}: Unexpected end of input", ErrorRead::SyntaxFailure)))),
+            Ok(None) => break,
+            Err(e) => return Err(ErrorEval::new(ErrorKind::BadSyntax(format!("{:?}", e)))),
+        };
+        let expr: Value = Parser::new(Tokenizer::new(datum.text).tokenize()?).parse();
+        let (name, result, is_test) = run_case(expr, env.clone())?;
+        if !is_test && result.is_ok() {
+            continue;
+        }
+        let outcome: Outcome = match (result, xfail.contains(&name)) {
+            (Ok(()), false) => Outcome::Pass,
+            (Err(reason), false) => Outcome::Fail(reason),
+            (Ok(()), true) => Outcome::UnexpectedPass,
+            (Err(_), true) => Outcome::ExpectedFail,
+        };
+        cases.push(Case { line: datum.line, name, outcome });
+    }
+    Ok(Report { path: PathBuf::from("<string>"), cases })
+}
+
+/// 运行一个顶层表达式, 返回用例名, 不通过的原因与它是否是测试用例
+/// 不是测试用例的表达式只在出错时记入报告
+/// 退出请求与调试器的中止请求不记入报告, 而是结束整个运行并继续传回
+fn run_case(expr: Value, env: Rc<EvalEnv>) -> Result<(String, Result<(), String>, bool), ErrorEval> {
+    let items: Vec<Value> = match expr {
+        Value::PairValue(_, _) => expr.to_vector().unwrap_or_default(),
+        _ => Vec::new(),
+    };
+    match items.as_slice() {
+        [Value::SymbolValue(head), expected, tested] if head == "test" => {
+            let result: Result<(), String> = match eval_case(expected, &env)? {
+                Err(error) => Err(format!("expected value: {}", error)),
+                Ok(expected) => match eval_case(tested, &env)? {
+                    Err(error) => Err(format!("{}", error)),
+                    Ok(got) => match equal_q(vec![expected.clone(), got.clone()], env.clone()) {
+                        Ok(Value::BooleanValue(true)) => Ok(()),
+                        _ => Err(format!("expected {} but got {}", expected.to_string(), got.to_string())),
+                    },
+                },
+            };
+            Ok((tested.to_string(), result, true))
+        },
+        [Value::SymbolValue(head), tested] if head == "test-error" => {
+            let result: Result<(), String> = match eval_case(tested, &env)? {
+                Ok(value) => Err(format!("expected an error but got {}", value.to_string())),
+                Err(_) => Ok(()),
+            };
+            Ok((tested.to_string(), result, true))
+        },
+        _ => {
+            let result: Result<(), String> = eval_case(&expr, &env)?.map(|_| ()).map_err(|error| format!("{}", error));
+            Ok((expr.to_string(), result, false))
+        },
+    }
+}
+
+/// 对用例中的表达式求值, 外层的错误是需要继续传回的退出请求与中止请求
+fn eval_case(expr: &Value, env: &Rc<EvalEnv>) -> Result<Result<Value, ErrorEval>, ErrorEval> {
+    match env.clone().eval(expr.clone()) {
+        Err(error) if error.is_unwinding() => Err(error),
+        result => Ok(result),
+    }
+}
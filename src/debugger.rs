@@ -0,0 +1,284 @@
+/// 定义了调试器
+/// 调试器由求值环境及其派生出的所有环境共享, 在断点处暂停求值并读取调试命令
+/// 断点来自 (break), 调试命令 b 以及单步执行, 命令行的 --debug 从第一个表达式开始单步执行
+/// (trace f) 的调用记录也由调试器打印
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, BufReader, Write};
+use std::rc::Rc;
+use crate::call_stack::abbreviate;
+use crate::error::{ErrorEval, ErrorKind};
+use crate::eval_env::EvalEnv;
+use crate::parse::Parser;
+use crate::tokenizer::{Tokenizer, IncrementalReader};
+use crate::value::{Value, BuiltinFn};
+
+/// 调试命令说明
+const DEBUG_HELP: &str = "s | step          执行到下一个表达式
+n | next          跳过当前表达式中的调用, 执行到同一层的下一个表达式
+f | finish        执行到当前调用返回
+c | continue      继续执行, 直到下一个断点
+b | break <name>  在名为name的过程上设置断点, 每次调用时暂停
+l | locals        列出当前帧及各级父级帧中的局部变量
+w | where         打印调用栈
+p | eval <expr>   在暂停的帧中对表达式求值
+q | quit          放弃本次求值
+h | help          打开该说明文档";
+
+/// 调试器的执行方式
+/// Run: 只在断点处暂停
+/// Step: 在下一个表达式处暂停
+/// Next: 在调用栈不高于给定高度的下一个表达式处暂停
+/// Finish: 调用栈低于给定高度后, 在下一个表达式处暂停
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Run,
+    Step,
+    Next(usize),
+    Finish(usize),
+}
+
+/// 被 trace 的过程
+/// wrapper: 代替原来绑定的包装过程
+/// restore: untrace 时恢复的绑定, 内置过程为None, 此时去掉包装过程的绑定即可
+pub struct Traced {
+    pub wrapper: BuiltinFn,
+    pub restore: Option<Value>,
+}
+
+/// 调试器
+/// paused: 正在暂停, 此时在暂停的帧中求值的表达式不会再次暂停
+/// breakpoints: 调用时暂停的过程名
+/// traced: 被 trace 的过程名
+/// trace_depth: 正在进行的被 trace 的调用层数, 用于缩进
+/// input, output: 调试命令的来源与调试信息的去向, 默认为标准输入与标准输出
+pub struct Debugger {
+    mode: Cell<Mode>,
+    paused: Cell<bool>,
+    breakpoints: RefCell<HashSet<String>>,
+    traced: RefCell<HashMap<String, Traced>>,
+    trace_depth: Cell<usize>,
+    input: RefCell<Box<dyn BufRead>>,
+    output: RefCell<Box<dyn Write>>,
+}
+
+impl Debugger {
+    /// 新建调试器, 不在任何地方暂停
+    pub fn new() -> Self {
+        Self {
+            mode: Cell::new(Mode::Run),
+            paused: Cell::new(false),
+            breakpoints: RefCell::new(HashSet::new()),
+            traced: RefCell::new(HashMap::new()),
+            trace_depth: Cell::new(0),
+            input: RefCell::new(Box::new(BufReader::new(io::stdin()))),
+            output: RefCell::new(Box::new(io::stdout())),
+        }
+    }
+
+    /// 更换调试命令的来源与调试信息的去向
+    #[allow(dead_code)]
+    pub fn set_io(&self, input: Box<dyn BufRead>, output: Box<dyn Write>) {
+        *self.input.borrow_mut() = input;
+        *self.output.borrow_mut() = output;
+    }
+
+    /// 从下一个表达式开始单步执行
+    pub fn step(&self) {
+        self.mode.set(Mode::Step);
+    }
+
+    /// 调用名为name的过程时暂停
+    pub fn add_breakpoint(&self, name: &str) {
+        self.breakpoints.borrow_mut().insert(name.to_string());
+    }
+
+    /// 记录被 trace 的过程
+    pub fn add_traced(&self, name: &str, traced: Traced) {
+        self.traced.borrow_mut().insert(name.to_string(), traced);
+    }
+
+    /// 名为name的过程是否正在被 trace
+    pub fn is_traced(&self, name: &str) -> bool {
+        self.traced.borrow().contains_key(name)
+    }
+
+    /// 不再 trace 名为name的过程, 返回其记录
+    pub fn remove_traced(&self, name: &str) -> Option<Traced> {
+        self.traced.borrow_mut().remove(name)
+    }
+
+    /// 进入被 trace 的调用, 打印调用形式并增加缩进
+    pub fn trace_enter(&self, name: &str, args: &[Value]) {
+        let depth: usize = self.trace_depth.get();
+        let call: String = std::iter::once(name.to_string()).chain(args.iter().map(|arg| arg.to_string())).collect::<Vec<String>>().join(" ");
+        self.print(&format!("{}> ({})", "  ".repeat(depth), call));
+        self.trace_depth.set(depth + 1);
+    }
+
+    /// 离开被 trace 的调用, 减少缩进并打印返回值
+    pub fn trace_leave(&self, result: &Result<Value, ErrorEval>) {
+        let depth: usize = self.trace_depth.get().saturating_sub(1);
+        self.trace_depth.set(depth);
+        match result {
+            Ok(value) => self.print(&format!("{}< {}", "  ".repeat(depth), value.to_string())),
+            Err(error) if !error.is_unwinding() => self.print(&format!("{}! {}", "  ".repeat(depth), error.kind)),
+            Err(_) => {},
+        }
+    }
+
+    /// 调用表达式call调用的过程上是否有断点
+    pub fn is_breakpoint(&self, call: &Value) -> bool {
+        if self.paused.get() || self.breakpoints.borrow().is_empty() {
+            return false;
+        }
+        match call {
+            Value::PairValue(car, _) => match car.as_ref() {
+                Value::SymbolValue(name) => self.breakpoints.borrow().contains(name),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// 调用栈高度为height时, 即将求值的表达式处是否应当暂停
+    pub fn should_pause(&self, height: usize) -> bool {
+        if self.paused.get() {
+            return false;
+        }
+        match self.mode.get() {
+            Mode::Run => false,
+            Mode::Step => true,
+            Mode::Next(level) => height <= level,
+            Mode::Finish(level) => height < level,
+        }
+    }
+
+    /// 在求值环境env中暂停, reason说明暂停的原因
+    /// 读取调试命令直到继续执行, 放弃求值时返回错误
+    pub fn pause(&self, env: &Rc<EvalEnv>, reason: &str) -> Result<(), ErrorEval> {
+        self.paused.set(true);
+        let result: Result<(), ErrorEval> = self.prompt(env, reason);
+        self.paused.set(false);
+        result
+    }
+
+    /// 调试命令循环
+    fn prompt(&self, env: &Rc<EvalEnv>, reason: &str) -> Result<(), ErrorEval> {
+        self.print(reason);
+        loop {
+            {
+                let mut output = self.output.borrow_mut();
+                _ = write!(output, "debug> ");
+                _ = output.flush();
+            }
+            let mut line: String = String::new();
+            match self.input.borrow_mut().read_line(&mut line) {
+                // 没有更多调试命令时继续执行
+                Ok(0) | Err(_) => {
+                    self.mode.set(Mode::Run);
+                    return Ok(());
+                },
+                Ok(_) => {},
+            }
+            let (command, argument) = match line.trim().split_once(char::is_whitespace) {
+                Some((command, argument)) => (command.to_string(), argument.trim().to_string()),
+                None => (line.trim().to_string(), String::new()),
+            };
+            let height: usize = env.call_stack.height();
+            match command.as_str() {
+                "s" | "step" => { self.mode.set(Mode::Step); return Ok(()); },
+                "n" | "next" => { self.mode.set(Mode::Next(height)); return Ok(()); },
+                "f" | "finish" => { self.mode.set(Mode::Finish(height)); return Ok(()); },
+                "c" | "continue" => { self.mode.set(Mode::Run); return Ok(()); },
+                "q" | "quit" => {
+                    self.mode.set(Mode::Run);
+                    return Err(ErrorEval::aborted());
+                },
+                "b" | "break" if !argument.is_empty() => {
+                    self.add_breakpoint(&argument);
+                    self.print(&format!("Breakpoint set on {}.", argument));
+                },
+                "l" | "locals" => self.locals(env),
+                "w" | "where" => self.backtrace(env),
+                "p" | "eval" if !argument.is_empty() => {
+                    match self.eval(env, &argument) {
+                        Ok(value) => self.print(&value.to_string()),
+                        Err(error) => self.print(&format!("Error:\n{}", error)),
+                    }
+                },
+                "h" | "help" => self.print(DEBUG_HELP),
+                "" => {},
+                _ => self.print(&format!("Unknown command {}. Type h for the list of commands.", command)),
+            }
+        }
+    }
+
+    /// 打印一行调试信息
+    fn print(&self, message: &str) {
+        _ = writeln!(self.output.borrow_mut(), "{}", message);
+    }
+
+    /// locals 由内向外列出各级帧中的绑定, 全局环境除外
+    fn locals(&self, env: &Rc<EvalEnv>) {
+        let mut current: Rc<EvalEnv> = env.clone();
+        let mut level: usize = 0;
+        while let Some(parent) = current.parent.clone() {
+            let bindings = current.symbol_map.borrow();
+            let mut names: Vec<&String> = bindings.keys().collect();
+            names.sort();
+            let items: Vec<String> = names.iter().map(|name| match &bindings[*name] {
+                Value::LambdaValue(params, _, _, _) => format!("{} = #<lambda {}>", name, params),
+                value => format!("{} = {}", name, abbreviate(value)),
+            }).collect();
+            self.print(&format!("[{}] {}", level, items.join(", ")));
+            drop(bindings);
+            current = parent;
+            level += 1;
+        }
+        if level == 0 {
+            self.print("No local bindings at top level.");
+        }
+    }
+
+    /// where 打印调用栈, 最近的调用在最后
+    fn backtrace(&self, env: &Rc<EvalEnv>) {
+        let traceback = env.call_stack.snapshot();
+        if traceback.omitted > 0 {
+            self.print(&format!("  ... {} earlier call(s) omitted", traceback.omitted));
+        }
+        for frame in traceback.frames.iter() {
+            self.print(&format!("  {}, in {}", frame.call_site, frame.procedure));
+        }
+        if traceback.frames.is_empty() {
+            self.print("At top level.");
+        }
+    }
+
+    /// 在暂停的帧中对源代码求值, 返回最后一个表达式的值
+    fn eval(&self, env: &Rc<EvalEnv>, source: &str) -> Result<Value, ErrorEval> {
+        let mut reader: IncrementalReader = IncrementalReader::new();
+        reader.feed(source);
+        reader.finish();
+        let mut result: Value = Value::NilValue;
+        loop {
+            match reader.next_datum() {
+                Ok(Some(datum)) => {
+                    let mut tokenizer: Tokenizer = Tokenizer::new(datum.text);
+                    let tokens = tokenizer.tokenize()?;
+                    let mut parser = Parser::new(tokens);
+                    result = env.clone().eval(parser.parse())?;
+                },
+                Ok(None) if reader.is_pending() => return Err(ErrorEval::new(ErrorKind::BadSyntax("Unexpected end of input".to_string()))),
+                Ok(None) => return Ok(result),
+                Err(e) => return Err(ErrorEval::new(ErrorKind::BadSyntax(format!("{:?}", e)))),
+            }
+        }
+    }
+}
+
+impl Default for Debugger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
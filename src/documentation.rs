@@ -0,0 +1,226 @@
+/// 定义了内置过程与特殊形式的文档表
+/// 供 help / describe 内置过程以及交互模式的 ,describe 命令使用
+use std::rc::Rc;
+use crate::eval_env::EvalEnv;
+use crate::value::{Value, Params};
+
+/// 一条文档
+/// signature: 调用形式
+/// arity: 参数个数说明
+/// doc: 说明文字
+pub struct Doc {
+    pub name: &'static str,
+    pub signature: &'static str,
+    pub arity: &'static str,
+    pub doc: &'static str,
+}
+
+const fn doc(name: &'static str, signature: &'static str, arity: &'static str, doc: &'static str) -> Doc {
+    Doc { name, signature, arity, doc }
+}
+
+/// 所有特殊形式的文档
+pub const SPECIAL_FORM_DOCS: &[Doc] = &[
+    doc("define", "(define name expr) | (define (name params ...) body ...) | (define (name params ... . rest) body ...)", "至少 2", "向当前求值环境绑定变量, 或定义一个过程"),
+    doc("quote", "(quote expr)", "1", "其引导的表达式将不被求值, 原样返回"),
+    doc("if", "(if condition then else)", "3", "条件为 #f 时对假分支求值, 否则对真分支求值"),
+    doc("and", "(and expr ...)", "任意", "逐个求值, 遇到 #f 立即返回 #f, 否则返回最后一个表达式的值"),
+    doc("or", "(or expr ...)", "任意", "逐个求值, 返回第一个不为 #f 的值, 全部为 #f 时返回 #f"),
+    doc("lambda", "(lambda (params ... [#!optional opt ...] [#:key key ...] [. rest]) [docstring] body ...) | (lambda args body ...)", "至少 2", "构造一个lambda表达式, 函数体开头的字符串作为文档字符串. 可选参数与关键字参数写作 (name default) 时带默认值, 否则缺省为 #f; 调用时关键字参数写作 #:key value; 剩余参数收集多出的实参组成列表"),
+    doc("case-lambda", "(case-lambda (params body ...) ...)", "任意", "构造一个按实参个数选择子句的过程, 使用第一个能接受这么多实参的子句"),
+    doc("cond", "(cond (condition expr ...) ... (condition => proc) ... (else expr ...))", "任意", "逐个条件求值, 返回第一个为真的条件对应的值; 写作 => proc 时以条件的值调用proc"),
+    doc("case", "(case key ((datum ...) expr ...) ... (else expr ...))", "至少 1", "选择第一个包含与key相同的datum的子句并求值, 子句也可以写作 ((datum ...) => proc)"),
+    doc("when", "(when condition body ...)", "至少 1", "条件不为 #f 时依次对函数体求值"),
+    doc("unless", "(unless condition body ...)", "至少 1", "条件为 #f 时依次对函数体求值"),
+    doc("do", "(do ((var init step) ...) (test expr ...) body ...)", "至少 2", "循环: test为真时返回expr的值, 否则对函数体求值并以step的值进入下一轮"),
+    doc("delay", "(delay expr)", "1", "返回一个promise, 第一次 force 时才对表达式求值"),
+    doc("delay-force", "(delay-force expr)", "1", "与delay类似, 表达式的值是另一个promise, force 时迭代地求值"),
+    doc("stream-cons", "(stream-cons a b)", "2", "构造一个惰性流, a与b都在需要时才求值, b的值应当是一个流"),
+    doc("test-begin", "(test-begin name)", "1", "开始一个名为name的测试组"),
+    doc("test-end", "(test-end [name])", "0 到 1", "结束当前测试组并打印该组通过与不通过的测试数"),
+    doc("test-equal", "(test-equal [name] expected expr)", "2 到 3", "两者的值满足 equal? 时测试通过"),
+    doc("test-assert", "(test-assert [name] expr)", "1 到 2", "值不为 #f 时测试通过"),
+    doc("test-error", "(test-error [[name] error-type] expr)", "1 到 3", "求值出错时测试通过, 不检查错误类型"),
+    doc("test-approximate", "(test-approximate [name] expected expr error)", "3 到 4", "两者相差不超过error时测试通过"),
+    doc("parameterize", "(parameterize ((param value) ...) body ...)", "至少 1", "对函数体求值期间将参数对象设为新的值, 结束后恢复"),
+    doc("begin", "(begin expr ...)", "至少 1", "逐个对子句求值, 返回最后一个子句的值"),
+    doc("let", "(let ((name expr) ...) body ...) | (let loop ((name expr) ...) body ...)", "至少 1", "在新的求值环境中绑定一些临时变量并对函数体求值; 命名 let 中可以调用loop以新的值重新进入函数体"),
+    doc("let*", "(let* ((name expr) ...) body ...)", "至少 1", "依次绑定临时变量, 后面的表达式可以引用前面的变量"),
+    doc("letrec", "(letrec ((name expr) ...) body ...)", "至少 1", "在包含所有变量的新环境中求值并绑定, 用于定义相互递归的局部过程"),
+    doc("letrec*", "(letrec* ((name expr) ...) body ...)", "至少 1", "与letrec类似, 但是依次求值并绑定, 后面的表达式可以使用前面的变量"),
+    doc("let-values", "(let-values ((formals expr) ...) body ...)", "至少 1", "将各个表达式返回的多个值按formals绑定, formals的写法与lambda表达式的参数表相同"),
+    doc("let*-values", "(let*-values ((formals expr) ...) body ...)", "至少 1", "与let-values类似, 但是依次绑定, 后面的表达式可以引用前面的变量"),
+    doc("define-values", "(define-values formals expr)", "2", "将表达式返回的多个值按formals绑定到当前求值环境"),
+    doc("quasiquote", "(quasiquote expr) | `expr", "1", "与quote类似, 不过由unquote引导的表达式会被求值"),
+    doc("unquote", "(unquote expr) | ,expr", "1", "用于在quasiquote中豁免表达式, 使其被求值"),
+    doc("trace", "(trace name ...)", "至少 1", "跟踪这些名字的过程, 每次调用时按深度缩进打印参数与返回值"),
+    doc("untrace", "(untrace name ...)", "至少 1", "停止跟踪这些名字的过程, 恢复原来的定义"),
+];
+
+/// 所有内置过程的文档
+pub const BUILTIN_DOCS: &[Doc] = &[
+    doc("apply", "(apply proc list)", "2", "将过程proc调用至列表list中的参数"),
+    doc("values", "(values expr ...)", "任意", "返回多个值, 只有一个参数时就是这个参数本身"),
+    doc("call-with-values", "(call-with-values producer consumer)", "2", "以无参数调用producer, 再将其返回的各个值作为参数调用consumer"),
+    doc("force", "(force promise)", "1", "求promise的值, 只求值一次; 参数不是promise时直接返回"),
+    doc("make-promise", "(make-promise expr)", "1", "返回一个已经求得值的promise, 参数已经是promise时直接返回"),
+    doc("promise?", "(promise? expr)", "1", "判断是否为promise"),
+    doc("make-parameter", "(make-parameter value [converter])", "1 或 2", "返回一个参数对象, 无参数调用时返回当前的值, 可以用 parameterize 临时修改"),
+    doc("stream-null?", "(stream-null? stream)", "1", "判断是否为空流, 空流就是空表"),
+    doc("stream-pair?", "(stream-pair? expr)", "1", "判断是否为非空的流"),
+    doc("stream-car", "(stream-car stream)", "1", "求流的第一个元素"),
+    doc("stream-cdr", "(stream-cdr stream)", "1", "返回流除第一个元素外剩下的流"),
+    doc("stream-take", "(stream-take n stream)", "2", "由流的前n个元素组成的流"),
+    doc("stream-map", "(stream-map proc stream ...)", "至少 2", "将过程作用于各个流中对应位置的元素, 返回惰性的结果流"),
+    doc("stream-filter", "(stream-filter pred stream)", "2", "由使谓词不为 #f 的元素组成的惰性流"),
+    doc("stream-fold", "(stream-fold proc base stream)", "3", "以 (proc 累积值 元素) 从左向右归约流, 只占用常数的内存"),
+    doc("stream->list", "(stream->list [n] stream)", "1 或 2", "将流(的前n个元素)转化为列表"),
+    doc("list->stream", "(list->stream list)", "1", "将列表转化为流"),
+    doc("stream-range", "(stream-range first past [step])", "2 或 3", "从first开始每次增加step直到past之前的惰性流, step缺省时为1或-1"),
+    doc("print", "(print expr ...)", "任意", "分别打印多个表达式, 每个表达式占一行"),
+    doc("display", "(display expr)", "1", "打印表达式, 字符串不带引号, 不换行"),
+    doc("displayln", "(displayln expr)", "1", "打印表达式并且换行, 字符串不带引号"),
+    doc("break", "(break)", "0", "在调用处暂停, 进入调试器查看局部变量, 单步执行或继续"),
+    doc("error", "(error [reason])", "0 或 1", "抛出错误, 可附带至多一个错误信息"),
+    doc("eval", "(eval expr)", "1", "在当前求值环境中对表达式求值"),
+    doc("exit", "(exit [code])", "0 或 1", "请求以给定的整数退出码退出, 由读取机或嵌入解释器的调用方结束程序"),
+    doc("exit_force", "(exit_force [code])", "0 或 1", "强制退出, 调用格式不对时以127退出码退出"),
+    doc("command-line", "(command-line)", "0", "返回命令行参数组成的列表, 第一个元素为脚本名"),
+    doc("newline", "(newline)", "0", "打印一个空行"),
+    doc("atom?", "(atom? expr)", "1", "判断是否为原子类型数据: 布尔, 数字, 字符串, 符号, 空表与宿主对象"),
+    doc("boolean?", "(boolean? expr)", "1", "判断是否为布尔类型值"),
+    doc("integer?", "(integer? expr)", "1", "判断是否为整数"),
+    doc("list?", "(list? expr)", "1", "判断是否为列表类型"),
+    doc("number?", "(number? expr)", "1", "判断是否为数字类型"),
+    doc("null?", "(null? expr)", "1", "判断是否为空表"),
+    doc("pair?", "(pair? expr)", "1", "判断是否为对子"),
+    doc("procedure?", "(procedure? expr)", "1", "判断是否为过程或lambda表达式"),
+    doc("string?", "(string? expr)", "1", "判断是否为字符串"),
+    doc("symbol?", "(symbol? expr)", "1", "判断是否为符号"),
+    doc("defined_local?", "(defined_local? 'name)", "1", "检查某个符号是否已经在当前环境绑定"),
+    doc("defined_all?", "(defined_all? 'name)", "1", "检查某个符号是否已经在所有可见环境内绑定"),
+    doc("append", "(append list ... obj)", "任意", "将各个列表内的元素按顺序拼接为一个新的列表, 最后一个参数作为结果的尾部"),
+    doc("push", "(push list value ...)", "任意", "将原子类型的值加入到列表末尾"),
+    doc("car", "(car pair)", "1", "取对子的左半部分"),
+    doc("cdr", "(cdr pair)", "1", "取对子的右半部分"),
+    doc("cons", "(cons car cdr)", "2", "构造一个对子"),
+    doc("length", "(length list)", "1", "返回列表的长度"),
+    doc("list", "(list expr ...)", "任意", "将参数依次组成一个列表"),
+    doc("map", "(map proc list ...)", "至少 2", "将过程作用于各个列表中对应位置的元素, 返回结果组成的列表, 以最短的列表为准"),
+    doc("caar", "(caar pair)", "1", "依次取 car car"),
+    doc("cadr", "(cadr pair)", "1", "依次取 cdr car"),
+    doc("cdar", "(cdar pair)", "1", "依次取 car cdr"),
+    doc("cddr", "(cddr pair)", "1", "依次取 cdr cdr"),
+    doc("caaar", "(caaar pair)", "1", "依次取 car car car"),
+    doc("caadr", "(caadr pair)", "1", "依次取 cdr car car"),
+    doc("cadar", "(cadar pair)", "1", "依次取 car cdr car"),
+    doc("caddr", "(caddr pair)", "1", "依次取 cdr cdr car"),
+    doc("cdaar", "(cdaar pair)", "1", "依次取 car car cdr"),
+    doc("cdadr", "(cdadr pair)", "1", "依次取 cdr car cdr"),
+    doc("cddar", "(cddar pair)", "1", "依次取 car cdr cdr"),
+    doc("cdddr", "(cdddr pair)", "1", "依次取 cdr cdr cdr"),
+    doc("list-ref", "(list-ref list k)", "2", "列表中下标为k的元素"),
+    doc("list-tail", "(list-tail list k)", "2", "去掉前k个元素之后剩下的列表"),
+    doc("reverse", "(reverse list)", "1", "将列表反转"),
+    doc("last", "(last list)", "1", "非空列表的最后一个元素"),
+    doc("take", "(take list k)", "2", "由前k个元素组成的列表"),
+    doc("drop", "(drop list k)", "2", "去掉前k个元素之后剩下的列表"),
+    doc("fold-left", "(fold-left proc init list ...)", "至少 3", "以 (proc 累积值 元素 ...) 从左向右归约"),
+    doc("fold-right", "(fold-right proc init list ...)", "至少 3", "以 (proc 元素 ... 累积值) 从右向左归约"),
+    doc("assoc", "(assoc key alist [=])", "2 或 3", "以 equal? 或给出的比较过程在关联列表中查找键为key的对子, 找不到时返回 #f"),
+    doc("assq", "(assq key alist)", "2", "以 eq? 在关联列表中查找键为key的对子"),
+    doc("assv", "(assv key alist)", "2", "以 eq? 在关联列表中查找键为key的对子, 数字按值比较"),
+    doc("member", "(member x list [=])", "2 或 3", "以 equal? 或给出的比较过程查找x, 返回从它开始的部分, 找不到时返回 #f"),
+    doc("memq", "(memq x list)", "2", "以 eq? 查找x, 返回从它开始的部分"),
+    doc("memv", "(memv x list)", "2", "以 eq? 查找x, 返回从它开始的部分, 数字按值比较"),
+    doc("delete", "(delete x list [=])", "2 或 3", "去掉所有与x equal? 的元素"),
+    doc("remove", "(remove pred list)", "2", "去掉使谓词不为 #f 的元素"),
+    doc("partition", "(partition pred list)", "2", "返回两个值: 使谓词不为 #f 的元素与其余的元素"),
+    doc("iota", "(iota count [start [step]])", "1 到 3", "从start开始每次增加step的count个数组成的列表"),
+    doc("any", "(any pred list ...)", "至少 2", "返回第一个不为 #f 的谓词值, 都为 #f 时返回 #f"),
+    doc("every", "(every pred list ...)", "至少 2", "所有谓词值都不为 #f 时返回最后一个, 否则返回 #f"),
+    doc("for-each", "(for-each proc list ...)", "至少 2", "依次将过程作用于各个列表中对应位置的元素"),
+    doc("append-map", "(append-map proc list ...)", "至少 2", "与map相同, 但是将各个结果列表拼接起来"),
+    doc("delete-duplicates", "(delete-duplicates list [=])", "1 或 2", "去掉重复的元素, 保留第一次出现的位置"),
+    doc("list-index", "(list-index pred list ...)", "至少 2", "第一个使谓词不为 #f 的位置, 找不到时返回 #f"),
+    doc("filter", "(filter pred list)", "2", "返回列表中使谓词不为 #f 的元素组成的列表"),
+    doc("reduce", "(reduce proc list)", "2", "使用二元过程从右向左归约列表"),
+    doc("+", "(+ number ...)", "任意", "求和"),
+    doc("-", "(- number number ...)", "至少 1", "一个参数时取相反数, 多个参数时从第一个数中依次减去其余的数"),
+    doc("*", "(* number ...)", "任意", "求积"),
+    doc("/", "(/ number number ...)", "至少 1", "一个参数时取倒数, 多个参数时用第一个数依次除以其余的数"),
+    doc("abs", "(abs number)", "1", "取绝对值"),
+    doc("expt", "(expt base exponent)", "2", "求幂"),
+    doc("quotient", "(quotient n1 n2)", "2", "整数除法, 结果向零取整"),
+    doc("modulo", "(modulo n1 n2)", "2", "取模, 结果与除数同号"),
+    doc("remainder", "(remainder n1 n2)", "2", "取余, 结果与被除数同号"),
+    doc("eq?", "(eq? expr1 expr2)", "2", "判断两个值是否为同一个对象"),
+    doc("equal?", "(equal? expr1 expr2)", "2", "判断两个值的外部表示是否相同"),
+    doc("not", "(not expr)", "1", "逻辑取反"),
+    doc("=", "(= n1 n2 ...)", "至少 2", "判断各个数是否都相等"),
+    doc("<", "(< n1 n2 ...)", "至少 2", "判断各个数是否严格递增"),
+    doc(">", "(> n1 n2 ...)", "至少 2", "判断各个数是否严格递减"),
+    doc("<=", "(<= n1 n2 ...)", "至少 2", "判断各个数是否单调不减"),
+    doc(">=", "(>= n1 n2 ...)", "至少 2", "判断各个数是否单调不增"),
+    doc("even?", "(even? integer)", "1", "判断整数是否为偶数"),
+    doc("odd?", "(odd? integer)", "1", "判断整数是否为奇数"),
+    doc("zero?", "(zero? number)", "1", "判断数字是否为零"),
+    doc("sort", "(sort list)", "1", "将全部由数字或全部由字符串组成的列表按升序排序"),
+    doc("help", "(help 'name)", "1", "打印符号对应的过程或特殊形式的调用形式, 参数个数与文档"),
+    doc("describe", "(describe expr)", "1", "打印一个值的说明, 过程会给出调用形式, 参数个数与文档"),
+];
+
+/// 查找内置过程或特殊形式的文档
+pub fn lookup(name: &str) -> Option<&'static Doc> {
+    BUILTIN_DOCS.iter().chain(SPECIAL_FORM_DOCS.iter()).find(|doc| doc.name == name)
+}
+
+/// 将一条文档格式化为说明文字
+fn format_doc(doc: &Doc, kind: &str) -> String {
+    format!("{}\n{}\narity: {}\n{}", doc.signature, kind, doc.arity, doc.doc)
+}
+
+/// 将lambda表达式格式化为说明文字
+/// 没有名字时调用形式写作 (lambda (params ...))
+fn format_lambda(name: Option<&str>, params: &Params, doc: &Option<String>) -> String {
+    let signature: String = match name {
+        Some(name) => params.signature(name),
+        None => format!("(lambda {})", params),
+    };
+    let doc: &str = doc.as_deref().unwrap_or("No documentation.");
+    format!("{}\nlambda expression\narity: {}\n{}", signature, params.arity(), doc)
+}
+
+/// 根据名字给出说明, 用户定义优先于内置过程与特殊形式
+pub fn describe_name(name: &str, env: Rc<EvalEnv>) -> String {
+    match env.clone().find_binding(&name.to_string()) {
+        Some(Value::LambdaValue(params, _, _, doc)) => format_lambda(Some(name), &params, &doc),
+        Some(Value::ProcedureValue(f)) => describe_value(&Value::ProcedureValue(f), env),
+        Some(value) => format!("{}: variable bound to {}", name, value.to_string()),
+        None if env.builtin_procs.contains_key(name) => lookup(name).map(|doc| format_doc(doc, "builtin procedure")).unwrap_or(format!("{}: builtin procedure", name)),
+        None if env.special_forms.contains_key(name) => lookup(name).map(|doc| format_doc(doc, "special form")).unwrap_or(format!("{}: special form", name)),
+        None => format!("{}: not defined", name),
+    }
+}
+
+/// 根据值给出说明
+/// 内置过程没有名字, 通过在内置过程表中比较指针找回其名字
+pub fn describe_value(value: &Value, env: Rc<EvalEnv>) -> String {
+    match value {
+        Value::LambdaValue(params, _, _, doc) => format_lambda(None, params, doc),
+        Value::ProcedureValue(f) => {
+            let builtin = env.builtin_procs.iter().find(|(_, g)| Rc::ptr_eq(g, f));
+            if let Some((name, _)) = builtin {
+                return lookup(name).map(|doc| format_doc(doc, "builtin procedure")).unwrap_or(format!("{}: builtin procedure", name));
+            }
+            format!("{}: procedure", value.to_string())
+        },
+        Value::SymbolValue(s) => format!("{}: symbol", s),
+        Value::StringValue(_) => format!("{}: string", value.to_string()),
+        Value::NumericValue(_) => format!("{}: number", value.to_string()),
+        Value::BooleanValue(_) => format!("{}: boolean", value.to_string()),
+        Value::NilValue => "(): empty list".to_string(),
+        Value::PairValue(_, _) => format!("{}: pair", value.to_string()),
+        Value::ForeignValue(o) => format!("{}: foreign object of type {}", value.to_string(), o.type_name()),
+    }
+}
@@ -4,25 +4,221 @@
 
 use std::error;
 use std::fmt;
+use std::ops::{Deref, DerefMut};
+use crate::documentation::lookup;
+use crate::value::Value;
+use crate::call_stack::Traceback;
 
-/// 求值错误类型
-#[derive(Debug)]
-pub struct ErrorEval {
-    pub message: String,
-    pub index: usize,
+/// 求值错误的种类
+/// UnboundVariable: 变量未定义
+/// ArityMismatch: 参数个数不符, expected 为文档中的参数个数说明
+/// TypeError: 参数类型不符, got 为实际值的外部表示
+/// DivisionByZero: 除以零
+/// UserRaised: 由 (error obj) 抛出, 保存抛出的值
+/// BadSyntax: 特殊形式或表达式的格式不对
+/// ResourceLimit: 超出沙箱的资源限制
+/// Runtime: 其它运行时错误
+/// Exit: (exit n) 请求结束程序, 沿调用链传回读取机或嵌入解释器的调用方, 由其决定是否结束进程
+///       附带的原因说明了 exit_force 调用格式不对的地方, 由读取机打印
+/// Aborted: 在调试器中用 q 命令中止了求值, 沿调用链传回顶层, 但不结束程序
+#[derive(Debug, Clone)]
+pub enum ErrorKind {
+    UnboundVariable(String),
+    ArityMismatch { procedure: String, expected: String, got: usize },
+    TypeError { expected: String, got: String },
+    DivisionByZero,
+    UserRaised(Value),
+    BadSyntax(String),
+    ResourceLimit(String),
+    Runtime(String),
+    Exit(i32, Option<String>),
+    Aborted,
 }
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ErrorKind::UnboundVariable(name) => write!(f, "Unbound variable: {}", name),
+            ErrorKind::ArityMismatch { procedure, expected, got } => write!(f, "Arity mismatch: <{}> expects {} argument(s), got {}", procedure, expected, got),
+            ErrorKind::TypeError { expected, got } => write!(f, "Type error: expected {}, got {}", expected, got),
+            ErrorKind::DivisionByZero => write!(f, "Division by zero"),
+            ErrorKind::UserRaised(Value::StringValue(s)) => write!(f, "Error: {}", s),
+            ErrorKind::UserRaised(value) => write!(f, "Error: {}", value.to_string()),
+            ErrorKind::BadSyntax(reason) => write!(f, "Bad syntax: {}", reason),
+            ErrorKind::ResourceLimit(reason) => write!(f, "Resource limit: {}", reason),
+            ErrorKind::Runtime(reason) => write!(f, "{}", reason),
+            ErrorKind::Exit(code, None) => write!(f, "Exit with code {}", code),
+            ErrorKind::Exit(code, Some(reason)) => write!(f, "Exit with code {}: {}", code, reason),
+            ErrorKind::Aborted => write!(f, "Evaluation aborted by the debugger"),
+        }
+    }
+}
+
+/// 调用栈中的一帧
+/// procedure: 内置过程, 特殊形式或求值器(eval)的名字
+/// context: 该帧正在做的事情
+#[derive(Debug, Clone)]
+pub struct Frame {
+    pub procedure: String,
+    pub context: Option<String>,
+}
+
+/// 求值错误的内容
+/// backtrace 从出错处开始, 向外逐层记录经过的帧
+/// location 为出错的顶层表达式在源代码中的位置, 由读取机填写
+/// traceback 为出错时Lisp层面调用栈的快照, 由求值器填写
+#[derive(Debug, Clone)]
+pub struct ErrorInfo {
+    pub kind: ErrorKind,
+    pub backtrace: Vec<Frame>,
+    pub location: Option<String>,
+    pub traceback: Option<Traceback>,
+}
+
+/// 求值错误类型, 通过解引用访问错误的内容
+/// 内容放在堆上, 使得 Result<Value, ErrorEval> 不比 Value 大, 避免求值器的栈帧随之变大
+#[derive(Debug, Clone)]
+pub struct ErrorEval(Box<ErrorInfo>);
+impl Deref for ErrorEval {
+    type Target = ErrorInfo;
+    fn deref(&self) -> &ErrorInfo {
+        &self.0
+    }
+}
+impl DerefMut for ErrorEval {
+    fn deref_mut(&mut self) -> &mut ErrorInfo {
+        &mut self.0
+    }
+}
+impl ErrorEval {
+    /// 构造一个错误
+    pub fn new(kind: ErrorKind) -> Self {
+        ErrorEval(Box::new(ErrorInfo { kind, backtrace: Vec::new(), location: None, traceback: None }))
+    }
+
+    /// 参数个数不符, 期望的参数个数取自文档
+    pub fn arity(procedure: &str, got: usize) -> Self {
+        let expected: String = lookup(procedure).map(|doc| doc.arity.to_string()).unwrap_or("?".to_string());
+        Self::new(ErrorKind::ArityMismatch { procedure: procedure.to_string(), expected, got })
+    }
+
+    /// 在procedure中遇到了类型不符的值
+    pub fn type_error(procedure: &str, expected: &str, got: &Value) -> Self {
+        Self::new(ErrorKind::TypeError { expected: expected.to_string(), got: got.to_string() }).frame(procedure)
+    }
+
+    /// 在procedure中发生的其它运行时错误
+    pub fn runtime(procedure: &str, reason: &str) -> Self {
+        Self::new(ErrorKind::Runtime(reason.to_string())).frame(procedure)
+    }
+
+    /// procedure的格式不对
+    pub fn bad_syntax(procedure: &str, reason: &str) -> Self {
+        Self::new(ErrorKind::BadSyntax(reason.to_string())).frame(procedure)
+    }
+
+    /// 构造一个退出请求
+    pub fn exit(code: i32) -> Self {
+        Self::new(ErrorKind::Exit(code, None))
+    }
+
+    /// 构造一个附带原因的退出请求
+    pub fn exit_with(code: i32, reason: &str) -> Self {
+        Self::new(ErrorKind::Exit(code, Some(reason.to_string())))
+    }
+
+    /// 构造一个调试器的中止请求
+    pub fn aborted() -> Self {
+        Self::new(ErrorKind::Aborted)
+    }
+
+    /// 是否为退出请求或调试器的中止请求
+    /// 它们不是求值本身出的错, 捕获错误的地方(如 test-error)也必须原样传回
+    pub fn is_unwinding(&self) -> bool {
+        matches!(self.kind, ErrorKind::Exit(_, _) | ErrorKind::Aborted)
+    }
+
+    /// 若为退出请求, 返回退出码
+    pub fn exit_code(&self) -> Option<i32> {
+        match self.kind {
+            ErrorKind::Exit(code, _) => Some(code),
+            _ => None,
+        }
+    }
+
+    /// 若为附带原因的退出请求, 返回原因
+    pub fn exit_reason(&self) -> Option<&str> {
+        match &self.kind {
+            ErrorKind::Exit(_, reason) => reason.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// 记录错误经过的一帧
+    pub fn frame(mut self, procedure: &str) -> Self {
+        self.backtrace.push(Frame { procedure: procedure.to_string(), context: None });
+        self
+    }
+
+    /// 记录错误经过的一帧, 并说明该帧正在做的事情
+    pub fn context(mut self, procedure: &str, context: &str) -> Self {
+        self.backtrace.push(Frame { procedure: procedure.to_string(), context: Some(context.to_string()) });
+        self
+    }
+
+    /// 记录出错的位置, 已有位置时作为前缀, 如文件名
+    pub fn at(mut self, location: String) -> Self {
+        self.location = match self.location.take() {
+            None => Some(location),
+            Some(inner) => Some(format!("{}:{}", location, inner)),
+        };
+        self
+    }
+}
+/// 错误信息只在顶层格式化一次
+/// 第一行给出位置与错误种类, 之后是Lisp层面的调用栈, 最后由外向内列出经过的帧
+/// 两者都是最近的调用在最后, 有调用栈时只列出出错的过程内部的帧
 impl fmt::Display for ErrorEval {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", self.message)
+        match &self.location {
+            Some(location) => write!(f, "{}: {}", location, self.kind)?,
+            None => write!(f, "{}", self.kind)?,
+        }
+        if let Some(traceback) = &self.traceback {
+            write!(f, "\nTraceback (most recent call last):")?;
+            if traceback.omitted > 0 {
+                write!(f, "\n  ... {} earlier call(s) omitted", traceback.omitted)?;
+            }
+            for frame in traceback.frames.iter() {
+                write!(f, "\n  {}, in {}", frame.call_site, frame.procedure)?;
+            }
+        }
+        let shown: usize = self.traceback.as_ref().map_or(self.backtrace.len(), |traceback| traceback.inner_frames);
+        for frame in self.backtrace[..shown].iter().rev() {
+            match &frame.context {
+                Some(context) => write!(f, "\n  in <{}>: {}", frame.procedure, context)?,
+                None => write!(f, "\n  in <{}>", frame.procedure)?,
+            }
+        }
+        Ok(())
     }
 }
 impl error::Error for ErrorEval {}
-impl Clone for ErrorEval {
-    fn clone(&self) -> Self {
-        ErrorEval { message: self.message.clone(), index: self.index}
+
+/// 对读入的文本求值时遇到的读写错误, 作为格式错误
+impl From<ErrorRead> for ErrorEval {
+    fn from(error: ErrorRead) -> Self {
+        ErrorEval::new(ErrorKind::BadSyntax(format!("{:?}", error)))
     }
 }
 
+/// 进程退出码
+/// 读写错误沿用原来的127, 语法错误与运行时错误分别使用 sysexits 中的 EX_DATAERR 与 EX_SOFTWARE
+/// (exit n) 直接以 n 退出
+pub const EXIT_IO_ERROR: i32 = 127;
+pub const EXIT_PARSE_ERROR: i32 = 65;
+pub const EXIT_RUNTIME_ERROR: i32 = 70;
+
 /// 读写错误类型
 #[derive(Debug, PartialEq)]
 pub enum ErrorRead {
@@ -3,23 +3,43 @@
 use std::collections::HashMap;
 use std::rc::Rc;
 use std::cell::RefCell;
-use crate::value::Value;
+use crate::value::{Value, Params};
 use crate::special_forms::*;
 use crate::builtins::*;
 use crate::value::BuiltinFn;
-use crate::error::ErrorEval;
+use crate::error::{ErrorEval, ErrorKind};
+use crate::sandbox::{Sandbox, Limits, SAFE_BUILTINS, SAFE_SPECIAL_FORMS};
+use crate::call_stack::{CallStack, abbreviate};
+use crate::debugger::Debugger;
+use crate::stream::*;
+use crate::list::*;
+use crate::unit_test::*;
+use crate::output::Output;
+use crate::alloc_counter;
 
 /// 求值环境的定义
 /// symbol_map: 利用RefCell, 使得在整个求值环境实例不可变的情况下内部可变. 具有重要意义: 函数类型定义中不可以出现&mut, 整个求值环境实例不可变, 但是内部又需要修改内部
 /// parent: 父级求值环境
 /// special_forms: 特殊形式对应表
 /// builtin_procs: 内置过程对应表
+/// command_line: 命令行参数, 第一个元素为脚本名, 由 (command-line) 返回
+/// sandbox: 沙箱状态, 沙箱环境及其派生环境共享同一份, 普通环境为None
+/// call_stack: Lisp层面的调用栈, 求值环境及其派生环境共享同一份
+/// debugger: 调试器, 求值环境及其派生环境共享同一份
+/// test_runner: 单元测试的运行器, 求值环境及其派生环境共享同一份
+/// output: display 等内置过程的输出, 求值环境及其派生环境共享同一份
 #[derive(Clone)]
 pub struct EvalEnv{
     pub symbol_map: RefCell<HashMap<String, Value>>,
     pub parent: Option<Rc<EvalEnv>>,
     pub special_forms: HashMap<String, SpecialForm>,
-    pub builtin_procs: HashMap<String, BuiltinFn>
+    pub builtin_procs: HashMap<String, BuiltinFn>,
+    pub command_line: Rc<Vec<String>>,
+    pub sandbox: Option<Rc<Sandbox>>,
+    pub call_stack: Rc<CallStack>,
+    pub debugger: Rc<Debugger>,
+    pub test_runner: Rc<TestRunner>,
+    pub output: Rc<Output>,
 }
 
 impl EvalEnv {
@@ -32,83 +52,205 @@ impl EvalEnv {
             ("and".to_string(), and_form as SpecialForm),
             ("or".to_string(), or_form as SpecialForm),
             ("lambda".to_string(), lambda_form as SpecialForm),
+            ("case-lambda".to_string(), case_lambda_form as SpecialForm),
             ("cond".to_string(), cond_form as SpecialForm),
+            ("case".to_string(), case_form as SpecialForm),
+            ("when".to_string(), when_form as SpecialForm),
+            ("unless".to_string(), unless_form as SpecialForm),
+            ("do".to_string(), do_form as SpecialForm),
+            ("delay".to_string(), delay_form as SpecialForm),
+            ("delay-force".to_string(), delay_force_form as SpecialForm),
+            ("parameterize".to_string(), parameterize_form as SpecialForm),
+            ("stream-cons".to_string(), stream_cons_form as SpecialForm),
+            ("test-begin".to_string(), test_begin_form as SpecialForm),
+            ("test-end".to_string(), test_end_form as SpecialForm),
+            ("test-equal".to_string(), test_equal_form as SpecialForm),
+            ("test-assert".to_string(), test_assert_form as SpecialForm),
+            ("test-error".to_string(), test_error_form as SpecialForm),
+            ("test-approximate".to_string(), test_approximate_form as SpecialForm),
             ("begin".to_string(), begin_form as SpecialForm),
             ("let".to_string(), let_form as SpecialForm),
+            ("let*".to_string(), let_star_form as SpecialForm),
+            ("letrec".to_string(), letrec_form as SpecialForm),
+            ("letrec*".to_string(), letrec_star_form as SpecialForm),
+            ("let-values".to_string(), let_values_form as SpecialForm),
+            ("let*-values".to_string(), let_star_values_form as SpecialForm),
+            ("define-values".to_string(), define_values_form as SpecialForm),
             ("quasiquote".to_string(), quasiquote_form as SpecialForm),
             ("unquote".to_string(), unquote_form as SpecialForm),
+            ("trace".to_string(), trace_form as SpecialForm),
+            ("untrace".to_string(), untrace_form as SpecialForm),
         ]);
-        let builtin_procs: HashMap<String, BuiltinFn> = HashMap::from([
-            ("apply".to_string(), apply as BuiltinFn),
-            ("print".to_string(), print as BuiltinFn),
-            ("display".to_string(), display as BuiltinFn),
-            ("displayln".to_string(), displayln as BuiltinFn),
-            ("error".to_string(), error as BuiltinFn),
-            ("eval".to_string(), eval as BuiltinFn),
-            ("exit".to_string(), exit as BuiltinFn),
-            ("exit_force".to_string(), exit_force as BuiltinFn),
-            ("newline".to_string(), newline as BuiltinFn),
+        let mut builtin_procs: HashMap<String, BuiltinFn> = HashMap::from([
+            ("apply".to_string(), Rc::new(apply) as BuiltinFn),
+            ("values".to_string(), Rc::new(values) as BuiltinFn),
+            ("call-with-values".to_string(), Rc::new(call_with_values) as BuiltinFn),
+            ("force".to_string(), Rc::new(force) as BuiltinFn),
+            ("make-promise".to_string(), Rc::new(make_promise) as BuiltinFn),
+            ("promise?".to_string(), Rc::new(promise_q) as BuiltinFn),
+            ("make-parameter".to_string(), Rc::new(make_parameter) as BuiltinFn),
+            ("stream-null?".to_string(), Rc::new(stream_null_q) as BuiltinFn),
+            ("stream-pair?".to_string(), Rc::new(stream_pair_q) as BuiltinFn),
+            ("stream-car".to_string(), Rc::new(stream_car) as BuiltinFn),
+            ("stream-cdr".to_string(), Rc::new(stream_cdr) as BuiltinFn),
+            ("stream-take".to_string(), Rc::new(stream_take) as BuiltinFn),
+            ("stream-map".to_string(), Rc::new(stream_map) as BuiltinFn),
+            ("stream-filter".to_string(), Rc::new(stream_filter) as BuiltinFn),
+            ("stream-fold".to_string(), Rc::new(stream_fold) as BuiltinFn),
+            ("stream->list".to_string(), Rc::new(stream_to_list) as BuiltinFn),
+            ("list->stream".to_string(), Rc::new(list_to_stream) as BuiltinFn),
+            ("stream-range".to_string(), Rc::new(stream_range) as BuiltinFn),
+            ("print".to_string(), Rc::new(print) as BuiltinFn),
+            ("display".to_string(), Rc::new(display) as BuiltinFn),
+            ("displayln".to_string(), Rc::new(displayln) as BuiltinFn),
+            ("error".to_string(), Rc::new(error) as BuiltinFn),
+            ("eval".to_string(), Rc::new(eval) as BuiltinFn),
+            ("exit".to_string(), Rc::new(exit) as BuiltinFn),
+            ("exit_force".to_string(), Rc::new(exit_force) as BuiltinFn),
+            ("command-line".to_string(), Rc::new(command_line) as BuiltinFn),
+            ("newline".to_string(), Rc::new(newline) as BuiltinFn),
+            ("help".to_string(), Rc::new(help) as BuiltinFn),
+            ("describe".to_string(), Rc::new(describe) as BuiltinFn),
+            ("break".to_string(), Rc::new(breakpoint) as BuiltinFn),
 
-            ("atom?".to_string(), atom_or_not as BuiltinFn),
-            ("boolean?".to_string(), boolean_or_not as BuiltinFn),
-            ("integer?".to_string(), integer_or_not as BuiltinFn),
-            ("list?".to_string(), list_or_not as BuiltinFn),
-            ("number?".to_string(), number_or_not as BuiltinFn),
-            ("null?".to_string(), null_or_not as BuiltinFn),
-            ("pair?".to_string(), pair_or_not as BuiltinFn),
-            ("procedure?".to_string(), procedure_or_not as BuiltinFn),
-            ("string?".to_string(), string_or_not as BuiltinFn),
-            ("symbol?".to_string(), symbol_or_not as BuiltinFn),
-            ("defined_local?".to_string(), defined_local_or_not as BuiltinFn),
-            ("defined_all?".to_string(), defined_all_or_not as BuiltinFn),
+            ("atom?".to_string(), Rc::new(atom_or_not) as BuiltinFn),
+            ("boolean?".to_string(), Rc::new(boolean_or_not) as BuiltinFn),
+            ("integer?".to_string(), Rc::new(integer_or_not) as BuiltinFn),
+            ("list?".to_string(), Rc::new(list_or_not) as BuiltinFn),
+            ("number?".to_string(), Rc::new(number_or_not) as BuiltinFn),
+            ("null?".to_string(), Rc::new(null_or_not) as BuiltinFn),
+            ("pair?".to_string(), Rc::new(pair_or_not) as BuiltinFn),
+            ("procedure?".to_string(), Rc::new(procedure_or_not) as BuiltinFn),
+            ("string?".to_string(), Rc::new(string_or_not) as BuiltinFn),
+            ("symbol?".to_string(), Rc::new(symbol_or_not) as BuiltinFn),
+            ("defined_local?".to_string(), Rc::new(defined_local_or_not) as BuiltinFn),
+            ("defined_all?".to_string(), Rc::new(defined_all_or_not) as BuiltinFn),
 
-            ("append".to_string(), append as BuiltinFn),
-            ("push".to_string(), push as BuiltinFn),
-            ("car".to_string(), car as BuiltinFn),
-            ("cdr".to_string(), cdr as BuiltinFn),
-            ("cons".to_string(), cons as BuiltinFn),
-            ("length".to_string(), length as BuiltinFn),
-            ("list".to_string(), list as BuiltinFn),
-            ("map".to_string(), map as BuiltinFn),
-            ("map_expand".to_string(), map_expand as BuiltinFn),
-            ("filter".to_string(), filter as BuiltinFn),
-            ("reduce".to_string(), reduce as BuiltinFn),
+            ("append".to_string(), Rc::new(append) as BuiltinFn),
+            ("push".to_string(), Rc::new(push) as BuiltinFn),
+            ("car".to_string(), Rc::new(car) as BuiltinFn),
+            ("cdr".to_string(), Rc::new(cdr) as BuiltinFn),
+            ("cons".to_string(), Rc::new(cons) as BuiltinFn),
+            ("length".to_string(), Rc::new(length) as BuiltinFn),
+            ("list".to_string(), Rc::new(list) as BuiltinFn),
+            ("map".to_string(), Rc::new(map) as BuiltinFn),
+            ("filter".to_string(), Rc::new(filter) as BuiltinFn),
+            ("list-ref".to_string(), Rc::new(list_ref) as BuiltinFn),
+            ("list-tail".to_string(), Rc::new(list_tail) as BuiltinFn),
+            ("reverse".to_string(), Rc::new(reverse) as BuiltinFn),
+            ("last".to_string(), Rc::new(last) as BuiltinFn),
+            ("take".to_string(), Rc::new(take) as BuiltinFn),
+            ("drop".to_string(), Rc::new(drop) as BuiltinFn),
+            ("fold-left".to_string(), Rc::new(fold_left) as BuiltinFn),
+            ("fold-right".to_string(), Rc::new(fold_right) as BuiltinFn),
+            ("assoc".to_string(), Rc::new(assoc) as BuiltinFn),
+            ("assq".to_string(), Rc::new(assq) as BuiltinFn),
+            ("assv".to_string(), Rc::new(assv) as BuiltinFn),
+            ("member".to_string(), Rc::new(member) as BuiltinFn),
+            ("memq".to_string(), Rc::new(memq) as BuiltinFn),
+            ("memv".to_string(), Rc::new(memv) as BuiltinFn),
+            ("delete".to_string(), Rc::new(delete) as BuiltinFn),
+            ("remove".to_string(), Rc::new(remove) as BuiltinFn),
+            ("partition".to_string(), Rc::new(partition) as BuiltinFn),
+            ("iota".to_string(), Rc::new(iota) as BuiltinFn),
+            ("any".to_string(), Rc::new(any) as BuiltinFn),
+            ("every".to_string(), Rc::new(every) as BuiltinFn),
+            ("for-each".to_string(), Rc::new(for_each) as BuiltinFn),
+            ("append-map".to_string(), Rc::new(append_map) as BuiltinFn),
+            ("delete-duplicates".to_string(), Rc::new(delete_duplicates) as BuiltinFn),
+            ("list-index".to_string(), Rc::new(list_index) as BuiltinFn),
+            ("reduce".to_string(), Rc::new(reduce) as BuiltinFn),
 
-            ("+".to_string(), add as BuiltinFn),
-            ("-".to_string(), subtract as BuiltinFn),
-            ("*".to_string(), multiply as BuiltinFn),
-            ("/".to_string(), divide as BuiltinFn),
-            ("abs".to_string(), abs as BuiltinFn),
-            ("expt".to_string(), expt as BuiltinFn),
-            ("quotient".to_string(), quotient as BuiltinFn),
-            ("modulo".to_string(), modulo as BuiltinFn),
-            ("remainder".to_string(), remainder as BuiltinFn),
+            ("+".to_string(), Rc::new(add) as BuiltinFn),
+            ("-".to_string(), Rc::new(subtract) as BuiltinFn),
+            ("*".to_string(), Rc::new(multiply) as BuiltinFn),
+            ("/".to_string(), Rc::new(divide) as BuiltinFn),
+            ("abs".to_string(), Rc::new(abs) as BuiltinFn),
+            ("expt".to_string(), Rc::new(expt) as BuiltinFn),
+            ("quotient".to_string(), Rc::new(quotient) as BuiltinFn),
+            ("modulo".to_string(), Rc::new(modulo) as BuiltinFn),
+            ("remainder".to_string(), Rc::new(remainder) as BuiltinFn),
 
-            ("eq?".to_string(), eq_q as BuiltinFn),
-            ("equal?".to_string(), equal_q as BuiltinFn),
-            ("not".to_string(), not as BuiltinFn),
-            ("=".to_string(), equal_or_not as BuiltinFn),
-            ("<".to_string(), less_than_or_not as BuiltinFn),
-            (">".to_string(), more_than_or_not as BuiltinFn),
-            ("<=".to_string(), less_than_or_equal_or_not as BuiltinFn),
-            (">=".to_string(), more_than_or_equal_or_not as BuiltinFn),
-            ("even?".to_string(), even_or_not as BuiltinFn),
-            ("odd?".to_string(), odd_or_not as BuiltinFn),
-            ("zero?".to_string(), zero_or_not as BuiltinFn),
-            ("sort".to_string(), sort as BuiltinFn),
+            ("eq?".to_string(), Rc::new(eq_q) as BuiltinFn),
+            ("equal?".to_string(), Rc::new(equal_q) as BuiltinFn),
+            ("not".to_string(), Rc::new(not) as BuiltinFn),
+            ("=".to_string(), Rc::new(equal_or_not) as BuiltinFn),
+            ("<".to_string(), Rc::new(less_than_or_not) as BuiltinFn),
+            (">".to_string(), Rc::new(more_than_or_not) as BuiltinFn),
+            ("<=".to_string(), Rc::new(less_than_or_equal_or_not) as BuiltinFn),
+            (">=".to_string(), Rc::new(more_than_or_equal_or_not) as BuiltinFn),
+            ("even?".to_string(), Rc::new(even_or_not) as BuiltinFn),
+            ("odd?".to_string(), Rc::new(odd_or_not) as BuiltinFn),
+            ("zero?".to_string(), Rc::new(zero_or_not) as BuiltinFn),
+            ("sort".to_string(), Rc::new(sort) as BuiltinFn),
         ]);
+        builtin_procs.extend(CXR_NAMES.iter().map(|name| (name.to_string(), cxr(name))));
         let symbol_map: RefCell<HashMap<String, Value>> = RefCell::new(HashMap::new());
         let parent: Option<Rc<EvalEnv>> = None;
-        Self {symbol_map, parent, special_forms, builtin_procs}
+        let command_line: Rc<Vec<String>> = Rc::new(Vec::new());
+        let sandbox: Option<Rc<Sandbox>> = None;
+        let call_stack: Rc<CallStack> = Rc::new(CallStack::new());
+        let debugger: Rc<Debugger> = Rc::new(Debugger::new());
+        let test_runner: Rc<TestRunner> = Rc::new(TestRunner::new());
+        let output: Rc<Output> = Rc::new(Output::new());
+        Self {symbol_map, parent, special_forms, builtin_procs, command_line, sandbox, call_stack, debugger, test_runner, output}
     }
 
-    /// 从当前求值环境, 插入params - args键值对, 形成新的环境
-    pub fn derive(self: Rc<EvalEnv>, params: Vec<String>, args: Vec<Value>) -> Self {
-        if params.len() < args.len() {
-            panic!("Too many parameters.");
+    /// 新建求值环境, 并指定 (command-line) 返回的命令行参数
+    pub fn with_command_line(command_line: Vec<String>) -> Self {
+        let mut env: EvalEnv = Self::new();
+        env.command_line = Rc::new(command_line);
+        env
+    }
+
+    /// 以相同的配置新建全局环境, 丢弃所有的定义
+    /// 内置过程与特殊形式照原样安装, 命令行参数, 沙箱, 调用栈, 调试器, 测试运行器与输出都与原来共享
+    pub fn fresh(&self) -> Self {
+        Self {
+            symbol_map: RefCell::new(HashMap::new()),
+            parent: None,
+            special_forms: self.special_forms.clone(),
+            builtin_procs: self.builtin_procs.clone(),
+            command_line: self.command_line.clone(),
+            sandbox: self.sandbox.clone(),
+            call_stack: self.call_stack.clone(),
+            debugger: self.debugger.clone(),
+            test_runner: self.test_runner.clone(),
+            output: self.output.clone(),
+        }
+    }
+
+    /// 新建沙箱求值环境
+    /// 只安装允许列表中的内置过程与特殊形式, 列表中出现未知的名字时报错
+    /// 求值过程受limits限制, 可以使用 sandbox::SAFE_BUILTINS 与 sandbox::SAFE_SPECIAL_FORMS 作为允许列表
+    /// 限制分配单元数时需要安装 alloc_counter::CountingAllocator, 否则报错
+    #[allow(dead_code)]
+    pub fn sandboxed(builtins: &[&str], special_forms: &[&str], limits: Limits) -> Result<Self, ErrorEval> {
+        if limits.max_cells.is_some() && !alloc_counter::installed() {
+            return Err(ErrorEval::new(ErrorKind::Runtime("max_cells needs alloc_counter::CountingAllocator installed as the global allocator".to_string())));
+        }
+        let mut env: EvalEnv = Self::new();
+        for name in builtins.iter().chain(special_forms.iter()) {
+            if !env.builtin_procs.contains_key(*name) && !env.special_forms.contains_key(*name) {
+                return Err(ErrorEval::new(ErrorKind::Runtime(format!("Unknown builtin or special form {} in the allow-list", name))));
+            }
         }
-        else if params.len() > args.len() {
-            panic!("Missing parameters.");
+        env.builtin_procs.retain(|name, _| builtins.contains(&name.as_str()));
+        env.special_forms.retain(|name, _| special_forms.contains(&name.as_str()));
+        env.sandbox = Some(Rc::new(Sandbox::new(limits)));
+        Ok(env)
+    }
+
+    /// 新建沙箱求值环境, 以 sandbox::SAFE_BUILTINS 与 sandbox::SAFE_SPECIAL_FORMS 作为允许列表
+    #[allow(dead_code)]
+    pub fn safe(limits: Limits) -> Result<Self, ErrorEval> {
+        Self::sandboxed(SAFE_BUILTINS, SAFE_SPECIAL_FORMS, limits)
+    }
+
+    /// 从当前求值环境, 插入params - args键值对, 形成新的环境
+    pub fn derive(self: Rc<EvalEnv>, params: Vec<String>, args: Vec<Value>) -> Result<Self, ErrorEval> {
+        if params.len() != args.len() {
+            return Err(ErrorEval::new(ErrorKind::ArityMismatch { procedure: "lambda".to_string(), expected: params.len().to_string(), got: args.len() }));
         }
         let special_forms: HashMap<String, SpecialForm> = self.special_forms.clone();
         let builtin_procs: HashMap<String, BuiltinFn> = self.builtin_procs.clone();
@@ -118,7 +260,72 @@ impl EvalEnv {
             symbol_map.insert(key.clone(), value.clone());
         }
         let symbol_map = RefCell::new(symbol_map);
-        Self {symbol_map, parent, special_forms, builtin_procs}
+        let command_line: Rc<Vec<String>> = self.command_line.clone();
+        let sandbox: Option<Rc<Sandbox>> = self.sandbox.clone();
+        let call_stack: Rc<CallStack> = self.call_stack.clone();
+        let debugger: Rc<Debugger> = self.debugger.clone();
+        let test_runner: Rc<TestRunner> = self.test_runner.clone();
+        let output: Rc<Output> = self.output.clone();
+        Ok(Self {symbol_map, parent, special_forms, builtin_procs, command_line, sandbox, call_stack, debugger, test_runner, output})
+    }
+
+    /// 按lambda表达式的参数表params绑定实参args, 形成新的环境
+    /// 可选参数与关键字参数的默认值在新的环境中依次求值, 可以引用前面的参数
+    pub fn bind(self: Rc<EvalEnv>, params: &Params, args: Vec<Value>) -> Result<Rc<EvalEnv>, ErrorEval> {
+        if !params.accepts(args.len()) {
+            return Err(ErrorEval::new(ErrorKind::ArityMismatch { procedure: "lambda".to_string(), expected: params.arity(), got: args.len() }));
+        }
+        let env: Rc<EvalEnv> = self.derive(Vec::new(), Vec::new())?.into();
+        let mut args = args.into_iter();
+        for name in params.required.iter() {
+            env.symbol_map.borrow_mut().insert(name.clone(), args.next().unwrap_or(Value::NilValue));
+        }
+        for (name, default) in params.optional.iter() {
+            let value: Value = match args.next() {
+                Some(value) => value,
+                None => env.clone().default_value(default)?,
+            };
+            env.symbol_map.borrow_mut().insert(name.clone(), value);
+        }
+        let rest: Vec<Value> = args.collect();
+        if !params.keys.is_empty() {
+            let mut supplied: HashMap<String, Value> = HashMap::new();
+            let mut i: usize = 0;
+            while i < rest.len() {
+                match &rest[i] {
+                    Value::SymbolValue(keyword) if keyword.starts_with("#:") && i + 1 < rest.len() => {
+                        let name: &str = &keyword[2..];
+                        if !params.keys.iter().any(|(key, _)| key == name) && params.rest.is_none() {
+                            return Err(ErrorEval::runtime("lambda", &format!("Unknown keyword argument {}", keyword)));
+                        }
+                        supplied.entry(name.to_string()).or_insert(rest[i + 1].clone());
+                        i += 2;
+                    },
+                    _ if params.rest.is_some() => i += 1,
+                    value => return Err(ErrorEval::runtime("lambda", &format!("Expected a keyword followed by a value, got {}", value.to_string()))),
+                }
+            }
+            for (name, default) in params.keys.iter() {
+                let value: Value = match supplied.remove(name) {
+                    Some(value) => value,
+                    None => env.clone().default_value(default)?,
+                };
+                env.symbol_map.borrow_mut().insert(name.clone(), value);
+            }
+        }
+        if let Some(name) = &params.rest {
+            let value: Value = list(rest, env.clone())?;
+            env.symbol_map.borrow_mut().insert(name.clone(), value);
+        }
+        Ok(env)
+    }
+
+    /// 未给出的可选参数或关键字参数的值, 没有默认值时为 #f
+    fn default_value(self: Rc<EvalEnv>, default: &Option<Value>) -> Result<Value, ErrorEval> {
+        match default {
+            Some(expr) => self.eval(expr.clone()),
+            None => Ok(Value::BooleanValue(false)),
+        }
     }
 
     /// 在当前求值环境及其各级父级环境中查找变量绑定
@@ -136,18 +343,79 @@ impl EvalEnv {
         }
     }
     
+    /// 以求值后的参数args调用过程procedure
+    /// 求值器与高阶内置过程都经过这里调用过程, 被 trace 包装的过程也是普通的过程值
+    pub fn apply_procedure(self: &Rc<EvalEnv>, procedure: Value, args: Vec<Value>) -> Result<Value, ErrorEval> {
+        self.tick()?;
+        self.invoke(procedure, args, None)
+    }
+
+    /// 在沙箱中计作一步并检查资源限制, 不在沙箱中时什么也不做
+    /// 在Rust中循环的内置过程每一轮调用一次, 使得限制在一次调用内部也能生效
+    pub fn tick(&self) -> Result<(), ErrorEval> {
+        match &self.sandbox {
+            Some(sandbox) => sandbox.tick(),
+            None => Ok(()),
+        }
+    }
+
+    /// 调用过程, 给出breakpoint时在进入过程之后暂停
+    fn invoke(self: &Rc<EvalEnv>, procedure: Value, args: Vec<Value>, breakpoint: Option<String>) -> Result<Value, ErrorEval> {
+        match procedure {
+            Value::ProcedureValue(f) => match breakpoint {
+                Some(reason) => self.debugger.pause(self, &reason).and_then(|_| f(args, Rc::clone(self))),
+                None => f(args, Rc::clone(self)),
+            },
+            Value::LambdaValue(params, body, env, _) => {
+                let env_derived: Rc<EvalEnv> = env.bind(&params, args)?;
+                match breakpoint {
+                    Some(reason) => self.debugger.pause(&env_derived, &reason),
+                    None => Ok(()),
+                }.and_then(|_| body.into_iter().try_fold(Value::NilValue, |_, bodyv| env_derived.clone().eval(bodyv)))
+            },
+            Value::ForeignValue(object) if object.callable() => match breakpoint {
+                Some(reason) => self.debugger.pause(self, &reason).and_then(|_| object.call(args, Rc::clone(self))),
+                None => object.call(args, Rc::clone(self)),
+            },
+            _ => Err(ErrorEval::type_error("apply", "procedure", &procedure)),
+        }
+    }
+
+    /// 求值器对调用表达式call的调用
+    /// 调用期间call位于调用栈顶, 出错时记下调用栈的快照, 过程上有断点时在进入过程之后暂停
+    fn call(self: &Rc<EvalEnv>, call: Value, procedure: Value, args: Vec<Value>) -> Result<Value, ErrorEval> {
+        let breakpoint: Option<String> = match self.debugger.is_breakpoint(&call) {
+            true => Some(format!("Breakpoint: {}", abbreviate(&call))),
+            false => None,
+        };
+        let _call = self.call_stack.enter(call);
+        self.invoke(procedure, args, breakpoint).map_err(|error| self.call_stack.capture(error))
+    }
+
+    /// 单步执行时, 在对表达式expr求值之前暂停
+    fn pause_before(self: &Rc<EvalEnv>, expr: &Value) -> Result<(), ErrorEval> {
+        self.debugger.pause(self, &format!("-> {}", abbreviate(expr)))
+    }
+
     /// 解释器求值过程
     /// 拿到parse之后的"值"
     /// 一般来说, 一个表达式一定是一个字面量(直接返回本身即可)
     /// 或者是一个由括号表达式括起来的对子值(对这个PairValue进行求值即可)
     pub fn eval(self: Rc<EvalEnv>, expr: Value) -> Result<Value, ErrorEval> {
+        // 沙箱中每次求值都要检查资源限制, 守卫在求值结束时离开作用域
+        let sandbox: Option<Rc<Sandbox>> = self.sandbox.clone();
+        let _guard = match &sandbox {
+            Some(sandbox) => Some(sandbox.enter()?),
+            None => None,
+        };
         match expr {
             Value::BooleanValue(_) => return Ok(expr),
             Value::NumericValue(_) => return Ok(expr),
             Value::StringValue(_) => return Ok(expr),
-            Value::NilValue => return Err(ErrorEval{message: format!("{}: [eval]: evaluate NilValue is prohibited", 0), index: 0}),
+            Value::NilValue => return Err(ErrorEval::bad_syntax("eval", "evaluate NilValue is prohibited")),
             Value::ProcedureValue(_) => return Ok(expr),
-            Value::LambdaValue(_, _, _) => return Ok(expr),
+            Value::LambdaValue(_, _, _, _) => return Ok(expr),
+            Value::ForeignValue(_) => return Ok(expr),
             Value::SymbolValue(s) => {
                 let item1 =  self.clone().find_binding(&s);
                 if item1.is_some() {
@@ -157,17 +425,21 @@ impl EvalEnv {
                     if s == "else".to_string() {
                         return Ok(Value::SymbolValue("else".to_string()));
                     }
+                    // 关键字 #:name 求值为自身
+                    if s.starts_with("#:") {
+                        return Ok(Value::SymbolValue(s));
+                    }
                     let item2 = self.builtin_procs.get(&s);
                     if item2.is_some() {
-                        return Ok(Value::ProcedureValue(Box::new(*item2.unwrap())));
+                        return Ok(Value::ProcedureValue(Box::new(item2.unwrap().clone())));
                     }
                     else {
                         let item3 = self.special_forms.get(&s);
                         if item3.is_some() {
-                            return Ok(Value::ProcedureValue(Box::new(*item3.unwrap())));
+                            return Ok(Value::ProcedureValue(Box::new(form_procedure(*item3.unwrap()))));
                         }
                         else {
-                            return Err(ErrorEval{message: format!("{}: [eval]: Variable {s} not defined", 0), index: 0});
+                            return Err(ErrorEval::new(ErrorKind::UnboundVariable(s.clone())));
                         }
                     }
                 }
@@ -175,100 +447,66 @@ impl EvalEnv {
             
             // 对子值比较特殊, 需要展开求解
             exprs @ Value::PairValue(_, _) => {
-                let v: Vec<Value> = exprs.to_vector().map_err(|error| ErrorEval{
-                    message: format!("{}: [eval]: Fail to convert a value to vector\n{}", error.index + 1, error.message),
-                    index: error.index + 1
-                })?;
+                if self.debugger.should_pause(self.call_stack.height()) {
+                    self.pause_before(&exprs)?;
+                }
+                let v: Vec<Value> = exprs.to_vector().map_err(|error| error.context("eval", "Fail to convert a value to vector"))?;
                 match &v[0] {
                     Value::SymbolValue(s) => {
                         match self.clone().find_binding(s) {
                             None => {},
-                            Some(Value::ProcedureValue(f)) => {
-                                let result_args: Result<Vec<Value>, ErrorEval> = v[1..].iter().cloned().map(|value| self.clone().eval(value)).collect();
-                                let args = result_args.map_err(|error| ErrorEval{
-                                    message: format!("{}: [eval]: Fail to evaluate a value\n{}", error.index + 1, error.message),
-                                    index: error.index + 1
-                                })?;
-                                return f(args, Rc::clone(&self));
-                            },
-                            Some(Value::LambdaValue(params_in_lambda, body, env_in_lambda)) => {
-                                let result_args: Result<Vec<Value>, ErrorEval >= v[1..].iter().map(|value| self.clone().eval(value.clone())).collect();
-                                let args = result_args.map_err(|error| ErrorEval{
-                                    message: format!("{}: [eval]: Fail to evaluate a value\n{}", error.index + 1, error.message),
-                                    index: error.index + 1
-                                })?;
-                                let env_derived: Rc<EvalEnv> = env_in_lambda.derive(*params_in_lambda, args).into();
-                                let mut result: Value = Value::NilValue;
-                                for bodyv in *body {
-                                    result = env_derived.clone().eval(bodyv).map_err(|error| ErrorEval{
-                                        message: format!("{}: [eval]: Fail to evaluate a value\n{}", error.index + 1, error.message),
-                                        index: error.index + 1
-                                    })?;
-                                }
-                                return Ok(result);
+                            Some(procedure) if procedure.is_procedure() => {
+                                let args: Vec<Value> = v[1..].iter().map(|value| self.clone().eval(value.clone())).collect::<Result<_, _>>()?;
+                                return self.call(exprs, procedure, args);
                             },
-                            _ => return Err(ErrorEval{message: format!("{}: [eval]: Invalid format", 0), index: 0}),
+                            _ => return Err(ErrorEval::bad_syntax("eval", "Invalid format")),
                         }
                         if self.special_forms.contains_key(s) {
                             if *s == "unquote".to_string() {
-                                return Err(ErrorEval{message: format!("{}: [eval]: Calling unquote outside quasiquote is an undefined behavior", 0), index: 0});
+                                return Err(ErrorEval::bad_syntax("eval", "Calling unquote outside quasiquote is an undefined behavior"));
                             }
                             return self.special_forms.get(s).unwrap()(v[1..].to_vec(), Rc::clone(&self));
                         }
                         else if self.builtin_procs.contains_key(s) {
-                            let result_args: Result<Vec<Value>, ErrorEval> = v[1..].iter().map(|value| self.clone().eval(value.clone())).collect();
-                            let args: Vec<Value> = result_args.map_err(|error| ErrorEval{
-                                message: format!("{}: [eval]: Fail to evaluate a value\n{}", error.index + 1, error.message),
-                                index: error.index + 1
-                            })?;
-                            return self.builtin_procs.get(s).unwrap()(args, Rc::clone(&self));
+                            let args: Vec<Value> = v[1..].iter().map(|value| self.clone().eval(value.clone())).collect::<Result<_, _>>()?;
+                            let procedure: Value = Value::ProcedureValue(Box::new(self.builtin_procs.get(s).unwrap().clone()));
+                            return self.call(exprs, procedure, args);
                         }
                         else {
-                            return Err(ErrorEval{message: format!("{}: [eval]: Name {s} not defined", 0), index: 0});
+                            return Err(ErrorEval::new(ErrorKind::UnboundVariable(s.clone())));
                         }
 
                     },
-                    Value::PairValue(_, _) => {
-                        let mut new_vec: Vec<Value> = Vec::new();
-                        v.iter().try_for_each(|value| -> Result<(), ErrorEval>{
-                            let result_arg: Value = self.clone().eval(value.clone()).map_err(|error| ErrorEval{
-                                message: format!("{}: [eval]: Fail to evaluate a value\n{}", error.index + 1, error.message),
-                                index: error.index + 1
-                            })?;
-                            new_vec.push(result_arg);
-                            Ok(())
-                        })?;
-                        let new_expr: Value = list(new_vec, Rc::clone(&self)).map_err(|error|ErrorEval{
-                            message: format!("{}: [eval]: Fail to pack the value\n{}", error.index + 1, error.message),
-                            index: error.index + 1
-                        })?;
-                        self.eval(new_expr)
-                    },
-                    Value::ProcedureValue(f) => {
-                        f(v[1..].to_vec(), Rc::clone(&self)).map_err(|error| ErrorEval {
-                            message: format!("{}: [eval]: Fail to call the given procedure\n{}", error.index + 1, error.message),
-                            index: error.index + 1
-                        })
-                    },
-                    Value::LambdaValue(params, body, env) => {
-                        let env_derived: Rc<EvalEnv> = env.clone().derive(*params.clone(), v[1..].to_vec()).into();
-                        let mut result: Value = Value::NilValue;
-                        for bodyv in *body.clone() {
-                            result = env_derived.clone().eval(bodyv).map_err(|error| ErrorEval{
-                                message: format!("{}: [eval]: Fail to evaluate a value\n{}", error.index + 1, error.message),
-                                index: error.index + 1
-                            })?;
+                    // 运算符是组合式或者已经求得的过程: 先求值运算符, 再依次求值各个参数, 最后调用
+                    Value::PairValue(_, _) | Value::ProcedureValue(_) | Value::LambdaValue(_, _, _, _) | Value::ForeignValue(_) => {
+                        let procedure: Value = self.clone().eval(v[0].clone())?;
+                        if !procedure.is_procedure() {
+                            return Err(ErrorEval::type_error("eval", "procedure", &procedure));
                         }
-                        return Ok(result);
+                        let args: Vec<Value> = v[1..].iter().map(|value| self.clone().eval(value.clone())).collect::<Result<_, _>>()?;
+                        self.call(exprs, procedure, args)
                     },
                     _ => {
-                        return Err(ErrorEval {
-                            message: format!("{}: [eval]: Invalid format. Cannot evaluate it as a symbol or procedure", 0),
-                            index: 0
-                        })
+                        return Err(ErrorEval::bad_syntax("eval", "Invalid format. Cannot evaluate it as a symbol or procedure"))
                     },
                 }
             },
         }
     }
-}
\ No newline at end of file
+}
+
+impl Default for EvalEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+/// 将特殊形式包装为过程值, 供高阶内置过程调用
+/// 参数已经求值, 传给特殊形式之前逐个加上 quote, 避免再次求值
+fn form_procedure(form: SpecialForm) -> BuiltinFn {
+    Rc::new(move |args: Vec<Value>, env: Rc<EvalEnv>| {
+        let quoted: Vec<Value> = args.into_iter().map(|arg| {
+            Value::PairValue(Box::new(Value::SymbolValue("quote".to_string())), Box::new(Value::PairValue(Box::new(arg), Box::new(Value::NilValue))))
+        }).collect();
+        form(quoted, env)
+    })
+}
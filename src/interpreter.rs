@@ -0,0 +1,301 @@
+/// 定义了嵌入接口, 供宿主程序把解释器当作库使用
+/// Interpreter 持有一个全局求值环境, 可以对字符串或文件求值, 定义全局变量, 注册宿主函数
+/// 宿主函数的参数与返回值通过 FromValue / IntoValue 在 Value 与 Rust 类型之间转换
+use crate::error::{ErrorEval, ErrorKind, ErrorRead};
+use crate::eval_env::EvalEnv;
+use crate::parse::Parser;
+use crate::tokenizer::{Tokenizer, IncrementalReader};
+use crate::value::{Value, BuiltinFn, Foreign};
+use std::path::Path;
+use std::rc::Rc;
+
+/// 从 Value 转换为 Rust 类型
+/// 转换失败时返回类型错误, 由调用方补充所在的帧
+pub trait FromValue: Sized {
+    fn from_value(value: Value) -> Result<Self, ErrorEval>;
+}
+
+/// 转换失败时的类型错误
+fn mismatch(expected: &str, got: Value) -> ErrorEval {
+    ErrorEval::new(ErrorKind::TypeError { expected: expected.to_string(), got: got.to_string() })
+}
+
+/// 从 Rust 类型转换为 Value
+pub trait IntoValue {
+    fn into_value(self) -> Value;
+}
+
+/// 宿主函数的返回值
+/// 所有 IntoValue 类型都可以直接返回, 返回 Result 时 Err 会成为求值错误
+pub trait IntoResult {
+    fn into_result(self) -> Result<Value, String>;
+}
+
+impl<T: IntoValue> IntoResult for T {
+    fn into_result(self) -> Result<Value, String> {
+        Ok(self.into_value())
+    }
+}
+
+impl<T: IntoValue, E: ToString> IntoResult for Result<T, E> {
+    fn into_result(self) -> Result<Value, String> {
+        self.map(IntoValue::into_value).map_err(|e| e.to_string())
+    }
+}
+
+impl FromValue for Value {
+    fn from_value(value: Value) -> Result<Self, ErrorEval> {
+        Ok(value)
+    }
+}
+
+impl IntoValue for Value {
+    fn into_value(self) -> Value {
+        self
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: Value) -> Result<Self, ErrorEval> {
+        match value {
+            Value::NumericValue(n) => Ok(n),
+            other => Err(mismatch("number", other)),
+        }
+    }
+}
+
+impl IntoValue for f64 {
+    fn into_value(self) -> Value {
+        Value::NumericValue(self)
+    }
+}
+
+impl FromValue for bool {
+    fn from_value(value: Value) -> Result<Self, ErrorEval> {
+        match value {
+            Value::BooleanValue(b) => Ok(b),
+            other => Err(mismatch("boolean", other)),
+        }
+    }
+}
+
+impl IntoValue for bool {
+    fn into_value(self) -> Value {
+        Value::BooleanValue(self)
+    }
+}
+
+impl FromValue for String {
+    fn from_value(value: Value) -> Result<Self, ErrorEval> {
+        match value {
+            Value::StringValue(s) => Ok(s),
+            other => Err(mismatch("string", other)),
+        }
+    }
+}
+
+impl IntoValue for String {
+    fn into_value(self) -> Value {
+        Value::StringValue(self)
+    }
+}
+
+impl IntoValue for &str {
+    fn into_value(self) -> Value {
+        Value::StringValue(self.to_string())
+    }
+}
+
+/// 空返回值对应空表, 与 display 等内置过程一致
+impl IntoValue for () {
+    fn into_value(self) -> Value {
+        Value::NilValue
+    }
+}
+
+/// 列表中的每个元素分别转换, 只接受真列表
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: Value) -> Result<Self, ErrorEval> {
+        let mut vec: Vec<T> = Vec::new();
+        let mut rest: Value = value;
+        loop {
+            match rest {
+                Value::NilValue => return Ok(vec),
+                Value::PairValue(car, cdr) => {
+                    vec.push(T::from_value(*car)?);
+                    rest = *cdr;
+                },
+                other => return Err(mismatch("list", other)),
+            }
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self) -> Value {
+        self.into_iter().rev().fold(Value::NilValue, |cdr, car| Value::PairValue(Box::new(car.into_value()), Box::new(cdr)))
+    }
+}
+
+/// 空表对应 None, 其余值按 T 转换
+/// 注意 Option<Vec<T>> 中的空列表因此会得到 None
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: Value) -> Result<Self, ErrorEval> {
+        match value {
+            Value::NilValue => Ok(None),
+            other => T::from_value(other).map(Some),
+        }
+    }
+}
+
+impl<T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self) -> Value {
+        match self {
+            Some(v) => v.into_value(),
+            None => Value::NilValue,
+        }
+    }
+}
+
+/// 宿主对象按类型取回, 类型不符时报错
+impl<T: Foreign> FromValue for Rc<T> {
+    fn from_value(value: Value) -> Result<Self, ErrorEval> {
+        match value.downcast_rc::<T>() {
+            Some(object) => Ok(object),
+            None => Err(mismatch(std::any::type_name::<T>(), value)),
+        }
+    }
+}
+
+impl<T: Foreign> IntoValue for Rc<T> {
+    fn into_value(self) -> Value {
+        Value::ForeignValue(self)
+    }
+}
+
+/// 可以注册为宿主函数的闭包
+/// Args 是参数类型组成的元组, 仅用于区分不同参数个数的实现
+pub trait HostFn<Args> {
+    fn into_builtin(self, name: String) -> BuiltinFn;
+}
+
+macro_rules! impl_host_fn {
+    ($($arg:ident),*) => {
+        impl<F, R, $($arg,)*> HostFn<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> R + 'static,
+            R: IntoResult,
+            $($arg: FromValue,)*
+        {
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn into_builtin(self, name: String) -> BuiltinFn {
+                Rc::new(move |params: Vec<Value>, _env: Rc<EvalEnv>| {
+                    let arity: usize = <[&str]>::len(&[$(stringify!($arg)),*]);
+                    if params.len() != arity {
+                        return Err(ErrorEval::new(ErrorKind::ArityMismatch { procedure: name.clone(), expected: arity.to_string(), got: params.len() }));
+                    }
+                    let mut params = params.into_iter().enumerate();
+                    $(
+                        let (position, param) = params.next().unwrap();
+                        let $arg: $arg = $arg::from_value(param).map_err(|error| error.context(&name, &format!("argument {}", position + 1)))?;
+                    )*
+                    (self)($($arg),*).into_result().map_err(|message| ErrorEval::runtime(&name, &message))
+                })
+            }
+        }
+    };
+}
+
+impl_host_fn!();
+impl_host_fn!(A);
+impl_host_fn!(A, B);
+impl_host_fn!(A, B, C);
+impl_host_fn!(A, B, C, D);
+impl_host_fn!(A, B, C, D, E);
+
+/// 嵌入用的解释器
+pub struct Interpreter {
+    env: Rc<EvalEnv>,
+}
+
+impl Interpreter {
+    /// 新建解释器
+    pub fn new() -> Self {
+        Self { env: Rc::new(EvalEnv::new()) }
+    }
+
+    /// 新建解释器, 在给定的求值环境中执行
+    pub fn with_env(env: Rc<EvalEnv>) -> Self {
+        Self { env }
+    }
+
+    /// 解释器的全局求值环境
+    pub fn env(&self) -> Rc<EvalEnv> {
+        self.env.clone()
+    }
+
+    /// 对一段源代码中的所有表达式依次求值, 返回最后一个表达式的值
+    /// 没有表达式时返回空表, 出错时错误信息带有出错表达式的行列位置
+    pub fn eval_str(&self, source: &str) -> Result<Value, ErrorEval> {
+        let mut reader: IncrementalReader = IncrementalReader::new();
+        reader.feed(source);
+        reader.finish();
+        let mut result: Value = Value::NilValue;
+        loop {
+            match reader.next_datum() {
+                Ok(Some(datum)) => {
+                    let (line, column) = (datum.line, datum.column);
+                    let mut tokenizer: Tokenizer = Tokenizer::new(datum.text);
+                    let tokens = tokenizer.tokenize().map_err(|error| ErrorEval::from(error).at(format!("{}:{}", line, column)))?;
+                    let mut parser = Parser::new(tokens);
+                    let value = parser.parse();
+                    result = self.env.clone().eval(value).map_err(|error| error.at(format!("{}:{}", line, column)))?;
+                },
+                Ok(None) if reader.is_pending() => return Err(ErrorEval::new(ErrorKind::BadSyntax(format!("{:?}: Unexpected end of input", ErrorRead::SyntaxFailure)))),
+                Ok(None) => return Ok(result),
+                Err(e) => return Err(ErrorEval::new(ErrorKind::BadSyntax(format!("{:?}", e)))),
+            }
+        }
+    }
+
+    /// 读入文件并求值, 返回最后一个表达式的值
+    pub fn eval_file<P: AsRef<Path>>(&self, path: P) -> Result<Value, ErrorEval> {
+        let path: &Path = path.as_ref();
+        let source: String = std::fs::read_to_string(path).map_err(|e| {
+            ErrorEval::new(ErrorKind::Runtime(format!("{:?}: {}: {}", ErrorRead::FileOpenError, path.display(), e)))
+        })?;
+        self.eval_str(&source).map_err(|error| error.at(path.display().to_string()))
+    }
+
+    /// 在全局环境中绑定变量, 已有的绑定会被覆盖
+    pub fn define_global<T: IntoValue>(&self, name: &str, value: T) {
+        self.env.symbol_map.borrow_mut().insert(name.to_string(), value.into_value());
+    }
+
+    /// 读取全局变量并转换为 Rust 类型
+    pub fn get_global<T: FromValue>(&self, name: &str) -> Result<T, ErrorEval> {
+        let value: Value = self.env.symbol_map.borrow().get(name).cloned().ok_or(ErrorEval::new(ErrorKind::UnboundVariable(name.to_string())))?;
+        T::from_value(value).map_err(|error| error.context("get_global", name))
+    }
+
+    /// 注册宿主函数, 参数与返回值自动转换
+    /// 参数个数不符或者参数类型不符时, 调用会得到求值错误而不会执行闭包
+    pub fn register_fn<Args, F: HostFn<Args>>(&self, name: &str, f: F) {
+        let builtin: BuiltinFn = f.into_builtin(name.to_string());
+        self.define_global(name, Value::ProcedureValue(Box::new(builtin)));
+    }
+
+    /// 注册不经转换的宿主函数, 直接接收参数列表与调用处的求值环境
+    pub fn register_builtin<F>(&self, name: &str, f: F)
+    where
+        F: Fn(Vec<Value>, Rc<EvalEnv>) -> Result<Value, ErrorEval> + 'static,
+    {
+        self.define_global(name, Value::ProcedureValue(Box::new(Rc::new(f))));
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -8,4 +8,15 @@ pub mod error;
 pub mod test_machine;
 pub mod reader_file;
 pub mod reader_interact;
-pub mod command_line;
\ No newline at end of file
+pub mod command_line;
+pub mod alloc_counter;
+pub mod documentation;
+pub mod interpreter;
+pub mod sandbox;
+pub mod call_stack;
+pub mod debugger;
+pub mod stream;
+pub mod list;
+pub mod conformance;
+pub mod unit_test;
+pub mod output;
\ No newline at end of file
@@ -0,0 +1,450 @@
+/// 定义了列表库, 参照 SRFI-1
+/// 接受过程参数的内置过程都经过 apply_procedure 调用, 因此内置过程与lambda表达式都可以使用
+use std::rc::Rc;
+use crate::builtins::{list, eq_q, equal_q, procedure_param, MultipleValues};
+use crate::error::ErrorEval;
+use crate::eval_env::EvalEnv;
+use crate::value::{Value, BuiltinFn, is_integer};
+
+/// 两级与三级的 c[ad]r 内置过程
+pub const CXR_NAMES: [&str; 12] = [
+    "caar", "cadr", "cdar", "cddr",
+    "caaar", "caadr", "cadar", "caddr", "cdaar", "cdadr", "cddar", "cdddr",
+];
+
+/// 读取列表参数
+pub fn list_param(procedure: &str, param: &Value) -> Result<Vec<Value>, ErrorEval> {
+    match param {
+        Value::NilValue => Ok(Vec::new()),
+        Value::PairValue(_, _) => param.to_vector().map_err(|_| ErrorEval::type_error(procedure, "list", param)),
+        _ => Err(ErrorEval::type_error(procedure, "list", param)),
+    }
+}
+
+/// 读取非负整数参数
+fn index_param(procedure: &str, param: &Value) -> Result<usize, ErrorEval> {
+    match param {
+        Value::NumericValue(n) if is_integer(n) && *n >= 0.0 => Ok(*n as usize),
+        _ => Err(ErrorEval::type_error(procedure, "non-negative integer", param)),
+    }
+}
+
+/// 读取若干个列表参数, 按位置组合为每次调用的参数, 以最短的列表为准
+pub fn columns(procedure: &str, params: &[Value]) -> Result<Vec<Vec<Value>>, ErrorEval> {
+    let lists: Vec<Vec<Value>> = params.iter().map(|param| list_param(procedure, param)).collect::<Result<_, _>>()?;
+    let size: usize = lists.iter().map(|list| list.len()).min().unwrap_or(0);
+    Ok((0..size).map(|i| lists.iter().map(|list| list[i].clone()).collect()).collect())
+}
+
+/// 值是否为真, 只有 #f 为假
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::BooleanValue(false))
+}
+
+/// 以compare比较两个值, 没有给出时使用默认的比较过程
+/// 查找与去重都在循环中比较, 每次比较在沙箱中计作一步
+fn same(compare: &Option<Value>, default: fn(Vec<Value>, Rc<EvalEnv>) -> Result<Value, ErrorEval>, a: &Value, b: &Value, env: &Rc<EvalEnv>) -> Result<bool, ErrorEval> {
+    let result: Value = match compare {
+        Some(compare) => env.apply_procedure(compare.clone(), vec![a.clone(), b.clone()])?,
+        None => {
+            env.tick()?;
+            default(vec![a.clone(), b.clone()], env.clone())?
+        },
+    };
+    Ok(truthy(&result))
+}
+
+/// 读取可选的比较过程参数
+fn compare_param(procedure: &str, param: Option<&Value>) -> Result<Option<Value>, ErrorEval> {
+    match param {
+        Some(compare) => Ok(Some(procedure_param(procedure, compare)?)),
+        None => Ok(None),
+    }
+}
+
+/// 从列表开头去掉count个对子, 返回剩下的部分
+fn tail(procedure: &str, list: &Value, count: usize) -> Result<Value, ErrorEval> {
+    let mut current: Value = list.clone();
+    for _ in 0..count {
+        current = match current {
+            Value::PairValue(_, cdr) => *cdr,
+            _ => return Err(ErrorEval::runtime(procedure, "Index out of range")),
+        };
+    }
+    Ok(current)
+}
+
+/// c[ad]r 系列内置过程, 按名字中从右向左的字母依次取car或cdr
+pub fn cxr(name: &'static str) -> BuiltinFn {
+    Rc::new(move |params: Vec<Value>, _env: Rc<EvalEnv>| -> Result<Value, ErrorEval> {
+        if params.len() != 1 {
+            return Err(ErrorEval::arity(name, params.len()));
+        }
+        let mut current: Value = params[0].clone();
+        for step in name[1..name.len() - 1].chars().rev() {
+            current = match (step, current) {
+                ('a', Value::PairValue(car, _)) => *car,
+                ('d', Value::PairValue(_, cdr)) => *cdr,
+                _ => return Err(ErrorEval::type_error(name, "pair", &params[0])),
+            };
+        }
+        Ok(current)
+    })
+}
+
+/// list-ref 内置过程
+/// (list-ref list k) 列表中下标为k的元素
+pub fn list_ref(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("list-ref", params.len()));
+    }
+    let index: usize = index_param("list-ref", &params[1])?;
+    match tail("list-ref", &params[0], index)? {
+        Value::PairValue(car, _) => Ok(*car),
+        _ => Err(ErrorEval::runtime("list-ref", "Index out of range")),
+    }
+}
+
+/// list-tail 内置过程
+/// (list-tail list k) 去掉前k个元素之后剩下的列表
+pub fn list_tail(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("list-tail", params.len()));
+    }
+    let index: usize = index_param("list-tail", &params[1])?;
+    tail("list-tail", &params[0], index)
+}
+
+/// reverse 内置过程
+pub fn reverse(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("reverse", params.len()));
+    }
+    let mut values: Vec<Value> = list_param("reverse", &params[0])?;
+    values.reverse();
+    list(values, env)
+}
+
+/// last 内置过程
+/// 非空列表的最后一个元素
+pub fn last(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("last", params.len()));
+    }
+    match list_param("last", &params[0])?.pop() {
+        Some(value) => Ok(value),
+        None => Err(ErrorEval::type_error("last", "non-empty list", &params[0])),
+    }
+}
+
+/// take 内置过程
+/// (take list k) 由前k个元素组成的列表
+pub fn take(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("take", params.len()));
+    }
+    let count: usize = index_param("take", &params[1])?;
+    let values: Vec<Value> = list_param("take", &params[0])?;
+    if count > values.len() {
+        return Err(ErrorEval::runtime("take", "Index out of range"));
+    }
+    list(values[..count].to_vec(), env)
+}
+
+/// drop 内置过程
+/// (drop list k) 去掉前k个元素之后剩下的列表
+pub fn drop(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("drop", params.len()));
+    }
+    let count: usize = index_param("drop", &params[1])?;
+    tail("drop", &params[0], count)
+}
+
+/// fold-left 内置过程
+/// (fold-left proc init list ...) 从左向右归约, 每一步以 (proc 累积值 元素 ...) 的值作为新的累积值
+pub fn fold_left(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() < 3 {
+        return Err(ErrorEval::arity("fold-left", params.len()));
+    }
+    let procedure: Value = procedure_param("fold-left", &params[0])?;
+    let mut acc: Value = params[1].clone();
+    for column in columns("fold-left", &params[2..])? {
+        let args: Vec<Value> = std::iter::once(acc).chain(column).collect();
+        acc = env.apply_procedure(procedure.clone(), args)?;
+    }
+    Ok(acc)
+}
+
+/// fold-right 内置过程
+/// (fold-right proc init list ...) 从右向左归约, 每一步以 (proc 元素 ... 累积值) 的值作为新的累积值
+pub fn fold_right(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() < 3 {
+        return Err(ErrorEval::arity("fold-right", params.len()));
+    }
+    let procedure: Value = procedure_param("fold-right", &params[0])?;
+    let mut acc: Value = params[1].clone();
+    for mut column in columns("fold-right", &params[2..])?.into_iter().rev() {
+        column.push(acc);
+        acc = env.apply_procedure(procedure.clone(), column)?;
+    }
+    Ok(acc)
+}
+
+/// 在关联列表中查找键为key的对子, 找不到时返回 #f
+fn find_association(procedure: &str, params: &[Value], default: fn(Vec<Value>, Rc<EvalEnv>) -> Result<Value, ErrorEval>, env: &Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    let compare: Option<Value> = compare_param(procedure, params.get(2))?;
+    for entry in list_param(procedure, &params[1])? {
+        match &entry {
+            Value::PairValue(key, _) => {
+                if same(&compare, default, &params[0], key, env)? {
+                    return Ok(entry);
+                }
+            },
+            _ => return Err(ErrorEval::type_error(procedure, "pair", &entry)),
+        }
+    }
+    Ok(Value::BooleanValue(false))
+}
+
+/// assoc 内置过程
+/// (assoc key alist [=]) 以 equal? 或给出的比较过程查找键为key的对子
+pub fn assoc(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 2 && params.len() != 3 {
+        return Err(ErrorEval::arity("assoc", params.len()));
+    }
+    find_association("assoc", &params, equal_q, &env)
+}
+
+/// assq 内置过程
+/// (assq key alist) 以 eq? 查找键为key的对子
+pub fn assq(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("assq", params.len()));
+    }
+    find_association("assq", &params, eq_q, &env)
+}
+
+/// assv 内置过程
+/// 数字与字符串按值比较, 与 assq 相同
+pub fn assv(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("assv", params.len()));
+    }
+    find_association("assv", &params, eq_q, &env)
+}
+
+/// 返回列表中从第一个等于x的元素开始的部分, 找不到时返回 #f
+fn find_member(procedure: &str, params: &[Value], default: fn(Vec<Value>, Rc<EvalEnv>) -> Result<Value, ErrorEval>, env: &Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    let compare: Option<Value> = compare_param(procedure, params.get(2))?;
+    let mut current: Value = params[1].clone();
+    loop {
+        match current {
+            Value::PairValue(ref car, ref cdr) => {
+                if same(&compare, default, &params[0], car, env)? {
+                    return Ok(current);
+                }
+                current = (**cdr).clone();
+            },
+            Value::NilValue => return Ok(Value::BooleanValue(false)),
+            _ => return Err(ErrorEval::type_error(procedure, "list", &params[1])),
+        }
+    }
+}
+
+/// member 内置过程
+/// (member x list [=]) 以 equal? 或给出的比较过程查找x, 返回从它开始的部分
+pub fn member(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 2 && params.len() != 3 {
+        return Err(ErrorEval::arity("member", params.len()));
+    }
+    find_member("member", &params, equal_q, &env)
+}
+
+/// memq 内置过程
+/// (memq x list) 以 eq? 查找x, 返回从它开始的部分
+pub fn memq(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("memq", params.len()));
+    }
+    find_member("memq", &params, eq_q, &env)
+}
+
+/// memv 内置过程
+/// 数字与字符串按值比较, 与 memq 相同
+pub fn memv(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("memv", params.len()));
+    }
+    find_member("memv", &params, eq_q, &env)
+}
+
+/// delete 内置过程
+/// (delete x list [=]) 去掉所有与x equal? 的元素
+pub fn delete(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 2 && params.len() != 3 {
+        return Err(ErrorEval::arity("delete", params.len()));
+    }
+    let compare: Option<Value> = compare_param("delete", params.get(2))?;
+    let mut results: Vec<Value> = Vec::new();
+    for value in list_param("delete", &params[1])? {
+        if !same(&compare, equal_q, &params[0], &value, &env)? {
+            results.push(value);
+        }
+    }
+    list(results, env)
+}
+
+/// remove 内置过程
+/// (remove pred list) 去掉使谓词不为 #f 的元素
+pub fn remove(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("remove", params.len()));
+    }
+    let predicate: Value = procedure_param("remove", &params[0])?;
+    let mut results: Vec<Value> = Vec::new();
+    for value in list_param("remove", &params[1])? {
+        if !truthy(&env.apply_procedure(predicate.clone(), vec![value.clone()])?) {
+            results.push(value);
+        }
+    }
+    list(results, env)
+}
+
+/// partition 内置过程
+/// (partition pred list) 返回两个值: 使谓词不为 #f 的元素与其余的元素
+pub fn partition(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("partition", params.len()));
+    }
+    let predicate: Value = procedure_param("partition", &params[0])?;
+    let mut accepted: Vec<Value> = Vec::new();
+    let mut rejected: Vec<Value> = Vec::new();
+    for value in list_param("partition", &params[1])? {
+        match truthy(&env.apply_procedure(predicate.clone(), vec![value.clone()])?) {
+            true => accepted.push(value),
+            false => rejected.push(value),
+        }
+    }
+    Ok(Value::foreign(MultipleValues(vec![list(accepted, env.clone())?, list(rejected, env)?])))
+}
+
+/// iota 内置过程
+/// (iota count [start [step]]) 从start开始每次增加step的count个数, start缺省为0, step缺省为1
+pub fn iota(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.is_empty() || params.len() > 3 {
+        return Err(ErrorEval::arity("iota", params.len()));
+    }
+    let count: usize = index_param("iota", &params[0])?;
+    let numbers: Vec<f64> = params[1..].iter().map(|param| match param {
+        Value::NumericValue(n) => Ok(*n),
+        _ => Err(ErrorEval::type_error("iota", "number", param)),
+    }).collect::<Result<_, _>>()?;
+    let start: f64 = numbers.first().copied().unwrap_or(0.0);
+    let step: f64 = numbers.get(1).copied().unwrap_or(1.0);
+    let mut values: Vec<Value> = Vec::new();
+    for i in 0..count {
+        env.tick()?;
+        values.push(Value::NumericValue(start + step * i as f64));
+    }
+    list(values, env)
+}
+
+/// any 内置过程
+/// (any pred list ...) 返回第一个不为 #f 的谓词值, 都为 #f 时返回 #f
+pub fn any(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() < 2 {
+        return Err(ErrorEval::arity("any", params.len()));
+    }
+    let predicate: Value = procedure_param("any", &params[0])?;
+    for column in columns("any", &params[1..])? {
+        let result: Value = env.apply_procedure(predicate.clone(), column)?;
+        if truthy(&result) {
+            return Ok(result);
+        }
+    }
+    Ok(Value::BooleanValue(false))
+}
+
+/// every 内置过程
+/// (every pred list ...) 遇到为 #f 的谓词值时返回 #f, 否则返回最后一个谓词值, 列表为空时返回 #t
+pub fn every(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() < 2 {
+        return Err(ErrorEval::arity("every", params.len()));
+    }
+    let predicate: Value = procedure_param("every", &params[0])?;
+    let mut result: Value = Value::BooleanValue(true);
+    for column in columns("every", &params[1..])? {
+        result = env.apply_procedure(predicate.clone(), column)?;
+        if !truthy(&result) {
+            return Ok(result);
+        }
+    }
+    Ok(result)
+}
+
+/// for-each 内置过程
+/// (for-each proc list ...) 依次将过程作用于各个列表中对应位置的元素, 只为了副作用
+pub fn for_each(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() < 2 {
+        return Err(ErrorEval::arity("for-each", params.len()));
+    }
+    let procedure: Value = procedure_param("for-each", &params[0])?;
+    for column in columns("for-each", &params[1..])? {
+        env.apply_procedure(procedure.clone(), column)?;
+    }
+    Ok(Value::NilValue)
+}
+
+/// append-map 内置过程
+/// (append-map proc list ...) 与 map 相同, 但是将各个结果列表拼接起来
+pub fn append_map(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() < 2 {
+        return Err(ErrorEval::arity("append-map", params.len()));
+    }
+    let procedure: Value = procedure_param("append-map", &params[0])?;
+    let mut results: Vec<Value> = Vec::new();
+    for column in columns("append-map", &params[1..])? {
+        let result: Value = env.apply_procedure(procedure.clone(), column)?;
+        results.extend(list_param("append-map", &result)?);
+    }
+    list(results, env)
+}
+
+/// delete-duplicates 内置过程
+/// (delete-duplicates list [=]) 去掉重复的元素, 保留第一次出现的位置
+pub fn delete_duplicates(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 1 && params.len() != 2 {
+        return Err(ErrorEval::arity("delete-duplicates", params.len()));
+    }
+    let compare: Option<Value> = compare_param("delete-duplicates", params.get(1))?;
+    let mut results: Vec<Value> = Vec::new();
+    for value in list_param("delete-duplicates", &params[0])? {
+        let mut duplicated: bool = false;
+        for kept in results.iter() {
+            if same(&compare, equal_q, kept, &value, &env)? {
+                duplicated = true;
+                break;
+            }
+        }
+        if !duplicated {
+            results.push(value);
+        }
+    }
+    list(results, env)
+}
+
+/// list-index 内置过程
+/// (list-index pred list ...) 第一个使谓词不为 #f 的位置, 找不到时返回 #f
+pub fn list_index(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() < 2 {
+        return Err(ErrorEval::arity("list-index", params.len()));
+    }
+    let predicate: Value = procedure_param("list-index", &params[0])?;
+    for (index, column) in columns("list-index", &params[1..])?.into_iter().enumerate() {
+        if truthy(&env.apply_procedure(predicate.clone(), column)?) {
+            return Ok(Value::NumericValue(index as f64));
+        }
+    }
+    Ok(Value::BooleanValue(false))
+}
@@ -9,6 +9,18 @@ mod error;
 mod reader_interact;
 mod reader_file;
 mod command_line;
+mod alloc_counter;
+mod documentation;
+mod sandbox;
+mod call_stack;
+mod debugger;
+mod stream;
+mod list;
+mod unit_test;
+mod output;
+
+#[global_allocator]
+static GLOBAL: alloc_counter::CountingAllocator = alloc_counter::CountingAllocator;
 
 fn main() {
     let config = command_line::Config::build(env::args()).unwrap_or_else(|err|{
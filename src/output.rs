@@ -0,0 +1,87 @@
+/// 定义了输出
+/// display, print, newline 等内置过程以及文件模式的顶层结果都写到这里, 求值环境及其派生环境共享同一份
+/// 平时直接写到标准输出, 捕获期间写入缓冲区, 供 --check 与嵌入解释器的程序取用
+use std::cell::RefCell;
+use std::io::{self, Write};
+
+/// 输出
+/// captured: 捕获期间的缓冲区, 不在捕获时为None
+#[derive(Default)]
+pub struct Output {
+    captured: RefCell<Option<String>>,
+}
+
+impl Output {
+    /// 新建输出, 写到标准输出
+    pub fn new() -> Self {
+        Self { captured: RefCell::new(None) }
+    }
+
+    /// 写入一段文本
+    pub fn write(&self, text: &str) {
+        match self.captured.borrow_mut().as_mut() {
+            Some(buffer) => buffer.push_str(text),
+            None => {
+                print!("{}", text);
+                let _ = io::stdout().flush();
+            },
+        }
+    }
+
+    /// 写入一行文本
+    pub fn writeln(&self, text: &str) {
+        self.write(&format!("{}\n", text));
+    }
+
+    /// 开始捕获, 之前捕获而未取走的内容被丢弃
+    pub fn capture(&self) {
+        *self.captured.borrow_mut() = Some(String::new());
+    }
+
+    /// 结束捕获, 返回捕获到的内容, 之后的输出重新写到标准输出
+    pub fn take(&self) -> String {
+        self.captured.borrow_mut().take().unwrap_or_default()
+    }
+}
+
+/// 逐行比较期望的输出与实际的输出, 相同时返回None
+/// 否则返回差异, 期望中多出的行以 - 开头, 实际中多出的行以 + 开头, 相同的行以空格开头
+pub fn diff(expected: &str, actual: &str) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+    let old: Vec<&str> = expected.lines().collect();
+    let new: Vec<&str> = actual.lines().collect();
+    // common[i][j]: old[i..] 与 new[j..] 的最长公共子序列长度
+    let mut common: Vec<Vec<usize>> = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = match old[i] == new[j] {
+                true => common[i + 1][j + 1] + 1,
+                false => common[i + 1][j].max(common[i][j + 1]),
+            };
+        }
+    }
+    let mut lines: Vec<String> = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(format!(" {}", old[i]));
+            i += 1;
+            j += 1;
+        }
+        else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(format!("-{}", old[i]));
+            i += 1;
+        }
+        else {
+            lines.push(format!("+{}", new[j]));
+            j += 1;
+        }
+    }
+    // 只有行尾的换行不同
+    if lines.iter().all(|line| line.starts_with(' ')) {
+        lines.push("\\ Trailing newline differs".to_string());
+    }
+    Some(lines.join("\n"))
+}
@@ -4,30 +4,35 @@ use std::fs::File;
 use std::io::Write;
 use crate::error::ErrorRead;
 use crate::eval_env::EvalEnv;
-use crate::tokenizer::Tokenizer;
+use crate::tokenizer::{Token, Tokenizer, IncrementalReader, Datum};
 use crate::parse::Parser;
-use crate::error::ErrorEval;
-use std::io::{BufReader, BufRead, BufWriter};
+use crate::value::Value;
+use crate::error::{ErrorEval, EXIT_IO_ERROR, EXIT_PARSE_ERROR, EXIT_RUNTIME_ERROR};
+use std::io::{BufReader, BufRead, BufWriter, Cursor};
 use std::rc::Rc;
 use std::io;
 
 /// 定义了文件模式自动机
+/// 输入可以是文件, 标准输入(文件名为"-")或者命令行中 -e 给出的表达式
 pub struct ReaderFile {
-    left_parenthesis_count: isize,
-    is_inside_quote: bool,
-    is_after_slash: bool,
-    is_inside_comment: bool,
-    templine: String,
-    line: String,
+    reader: IncrementalReader,
     env: Rc<EvalEnv>,
     have_output_file: bool,
     input_file_name: Option<String>,
     output_file_name: Option<String>,
+    expression: Option<String>,
+    failed: bool,
 }
 
 impl ReaderFile{
     /// 新建文件模式
+    #[allow(dead_code)]
     pub fn new(input_file_name: Option<String>, output_file_name: Option<String>) -> Self {
+        Self::with_env(input_file_name, output_file_name, Rc::new(EvalEnv::new()))
+    }
+
+    /// 新建文件模式, 在给定的求值环境中执行
+    pub fn with_env(input_file_name: Option<String>, output_file_name: Option<String>, env: Rc<EvalEnv>) -> Self {
         let have_output_file: bool;
         if output_file_name.is_none() {
             have_output_file = false;
@@ -36,24 +41,40 @@ impl ReaderFile{
             have_output_file = true;
         }
         Self {
-            left_parenthesis_count: 0,
-            is_inside_quote: false, 
-            is_after_slash: false,
-            is_inside_comment: false,
-            templine: String::new(),
-            line: String::new(),
-            env: Rc::new(EvalEnv::new()),
+            reader: IncrementalReader::new(),
+            env,
             have_output_file,
             input_file_name,
             output_file_name,
+            expression: None,
+            failed: false,
         }
     }
 
+    /// 新建文件模式, 执行命令行中给出的表达式
+    pub fn from_expression(expression: String, output_file_name: Option<String>, env: Rc<EvalEnv>) -> Self {
+        let mut reader_file: ReaderFile = Self::with_env(None, output_file_name, env);
+        reader_file.expression = Some(expression);
+        reader_file
+    }
+
+    /// 是否因为未捕获的运行时错误而停止
+    pub fn failed(&self) -> bool {
+        self.failed
+    }
+
     /// 打开输入文件
-    fn open_input_file(&self) -> Result<BufReader<File>, ErrorRead> {
-        let file = File::open(self.input_file_name.as_ref().unwrap()).map_err(|_| ErrorRead::FileOpenError)?;
+    fn open_input_file(&self) -> Result<Box<dyn BufRead>, ErrorRead> {
+        if let Some(expression) = &self.expression {
+            return Ok(Box::new(Cursor::new(expression.clone().into_bytes())));
+        }
+        let name: &String = self.input_file_name.as_ref().ok_or(ErrorRead::FileOpenError)?;
+        if name == "-" {
+            return Ok(Box::new(BufReader::new(io::stdin())));
+        }
+        let file = File::open(name).map_err(|_| ErrorRead::FileOpenError)?;
         let reader = BufReader::new(file);
-        return Ok(reader);
+        return Ok(Box::new(reader));
     }
 
     /// 打开输出文件
@@ -63,70 +84,8 @@ impl ReaderFile{
         return Ok(writer);
     }
     
-    /// 对读入的一行文本进行处理, 检查是否已经是一个完整的表达式
-    fn process_line(&mut self, mut templine: String) -> Result<(), ErrorRead> {
-        templine = templine.trim().to_string();
-        if templine.is_empty() {
-            return Err(ErrorRead::EmptyLine);
-        }
-        let mut lcount: isize = 0;
-        let mut rcount: isize = 0;
-        for ch in templine.chars() {
-            match ch {
-                '\n' => {
-                    if self.is_inside_comment { self.is_inside_comment = false; continue; }
-                },
-                '(' => {
-                    if self.is_inside_comment { continue; } // Added
-                    if self.is_after_slash { self.is_after_slash = false; }
-                    if self.is_inside_quote == false { lcount += 1; } // 检查bound
-                },
-                ')' => {
-                    if self.is_inside_comment { continue; } // Added
-                    if self.is_after_slash { self.is_after_slash = false; }
-                    if self.is_inside_quote == false { rcount += 1; } // 检查bound
-                },
-                ';' => {
-                    if self.is_inside_comment { continue; } // Added 
-                    if self.is_after_slash { self.is_after_slash = false; }
-                    if self.is_inside_quote == false { self.is_inside_comment = true; } // 检查bound
-                },
-                '"' => {
-                    if self.is_inside_comment { continue; } // Added
-                    if self.is_after_slash { self.is_after_slash = false; }
-                    else {
-                        if self.is_inside_quote { self.is_inside_quote = false; }
-                        else { self.is_inside_quote = true; }
-                    }
-                },
-                '\\' => {
-                    if self.is_inside_comment { continue; } // Added
-                    if self.is_inside_quote {
-                        if self.is_after_slash { self.is_after_slash = false; }
-                        else { self.is_after_slash = true; }
-                    }
-                },
-                'n' => {
-                    if self.is_inside_comment { continue; } // Added
-                    if self.is_inside_quote && self.is_after_slash { self.is_after_slash = false; }
-                },
-                _ => {
-                    if self.is_after_slash { self.is_after_slash = false; }
-                },
-            }
-        }
-        self.left_parenthesis_count += lcount - rcount;
-        if self.left_parenthesis_count < 0 {
-            return Err(ErrorRead::SyntaxFailure);
-        }
-        self.line += templine.as_str();
-
-        self.line.push('\n');
-        Ok(())
-    }
-
     /// 读入一行文本
-    fn readline(&mut self, reader: &mut BufReader<File>) -> Result<(), ErrorRead> {
+    fn readline(&mut self, reader: &mut Box<dyn BufRead>) -> Result<(), ErrorRead> {
         let mut buffer: String = String::new();
         let num_bytes = reader.read_line(&mut buffer);
   
@@ -136,7 +95,7 @@ impl ReaderFile{
                 return Err(ErrorRead::EOF);
             },
             Ok(_) => {
-                self.process_line(buffer)?
+                self.reader.feed(&buffer);
             },
             Err(_) => {
                 return Err(ErrorRead::Utf8ConversionError);
@@ -146,109 +105,125 @@ impl ReaderFile{
     }
 
     /// 在检测到一个完整表达式之后进行处理
-    fn process(&mut self) -> Result<String, ErrorEval> {
-        let mut tokenizer: Tokenizer = Tokenizer::new(self.line.clone());
-        let tokens = tokenizer.tokenize();
+    fn process(&mut self, tokens: Vec<Token>) -> Result<Value, ErrorEval> {
         let mut parser = Parser::new(tokens);
         let value = parser.parse();
-        let result = self.env.clone().eval(value)?;
-        Ok(result.to_string())
+        self.env.clone().eval(value)
     }
 
-    /// 处理输出
-    fn output(&self, result: String, writer: &mut Option<BufWriter<File>>) -> io::Result<()> {
-        if result == "()".to_string() {
+    /// 处理输出, 给出输出文件时写入文件, 否则与 display 等一样写到求值环境的输出
+    /// 空表同时也是 define, display 等没有值的表达式的结果, 因此不输出
+    fn output(&self, result: Value, writer: &mut Option<BufWriter<File>>) -> io::Result<()> {
+        if let Value::NilValue = result {
             return Ok(());
         }
-        println!("{}",self.have_output_file);
+        let result: String = result.to_string();
         if self.have_output_file {
             
             let writer: &mut BufWriter<File> = writer.as_mut().unwrap();
             
-            write!(writer, "{result}")?;
+            writeln!(writer, "{result}")?;
             writer.flush()?;
             return Ok(());
         }
         else {
-            println!("{}", result);
+            self.env.output.writeln(&result);
         }
         return Ok(())
     }
     
     /// 清空文件模式自动机的状态
-    fn flush(&mut self) -> () {
-        self.line.clear();
-        self.templine.clear();
-        self.is_inside_quote = false;
-        self.is_after_slash = false;
-        self.is_inside_comment = false;
-        self.left_parenthesis_count = 0;
+    fn flush(&mut self) {
+        self.reader.clear();
     }
 
     /// 调用文件模式
-    pub fn call(&mut self) -> () {
-        let open_input_result = self.open_input_file();
-        let mut reader: BufReader<File>;
-        match open_input_result {
+    /// 返回进程应当使用的退出码: 正常结束为0, (exit n) 为n, 出错时为对应的错误退出码
+    /// 是否真正结束进程由调用方决定
+    pub fn call(&mut self) -> i32 {
+        let mut reader: Box<dyn BufRead> = match self.open_input_file() {
             Err(e) => {
                 eprintln!("{:?}", e);
                 self.flush();
-                std::process::exit(127);
+                return EXIT_IO_ERROR;
             },
-            Ok(r) => {
-                reader = r;
-            }
-        }
+            Ok(r) => r,
+        };
         let mut writer: Option<BufWriter<File>> = None;
         if self.have_output_file {
-            let open_output_result = self.open_output_file();
-            match open_output_result {
+            match self.open_output_file() {
                 Err(e) => {
                     eprintln!("{:?}", e);
                     self.flush();
-                    std::process::exit(127);
-                }
-                Ok(w) => {
-                    writer = Some(w);
-                }
+                    return EXIT_IO_ERROR;
+                },
+                Ok(w) => writer = Some(w),
             }
         }
         
         loop {
             let read_status = self.readline(&mut reader);
-            match read_status {
+            // 到达文件末尾后仍需处理缓冲区中剩余的表达式
+            let at_eof: bool = match read_status {
                 Err(ErrorRead::EOF) => {
-                    return;
+                    self.reader.finish();
+                    true
                 },
-                Err(ErrorRead::EmptyLine) => {
-                    continue;
-                }
                 Err(e) => {
                     eprintln!("{:?}", e);
                     self.flush();
-                    std::process::exit(127);
+                    return EXIT_IO_ERROR;
                 },
-                Ok(()) => {              
-                    if self.left_parenthesis_count == 0 {
-                        let result = self.process();
-                        match result {
-                            Err(e) => {
-                                eprintln!("{}", e);
-                                self.flush();
-                                std::process::exit(127);
-                            },
-                            Ok(s) => {
-                                self.output(s, &mut writer).unwrap_or_else(|e|{
-                                    eprintln!("{}", e);
-                                    self.flush();
-                                    std::process::exit(127);
-                                });
-                            },
-                        }
+                Ok(()) => false,
+            };
+            loop {
+                let datum: Datum = match self.reader.next_datum() {
+                    Ok(Some(datum)) => datum,
+                    Ok(None) if at_eof && self.reader.is_pending() => {
+                        eprintln!("{:?}: Unexpected end of file", ErrorRead::SyntaxFailure);
                         self.flush();
-                    }
-                },
+                        return EXIT_PARSE_ERROR;
+                    },
+                    Ok(None) if at_eof => return 0,
+                    Ok(None) => break,
+                    Err(e) => {
+                        eprintln!("{:?}", e);
+                        self.flush();
+                        return EXIT_PARSE_ERROR;
+                    },
+                };
+                let (line, column) = (datum.line, datum.column);
+                let tokens: Vec<Token> = match Tokenizer::new(datum.text).tokenize() {
+                    Ok(tokens) => tokens,
+                    Err(e) => {
+                        eprintln!("{}:{}: {:?}", line, column, e);
+                        self.flush();
+                        return EXIT_PARSE_ERROR;
+                    },
+                };
+                let result = self.process(tokens);
+                match result {
+                    Err(e) => {
+                        self.flush();
+                        if let Some(code) = e.exit_code() {
+                            if let Some(reason) = e.exit_reason() {
+                                eprintln!("{}:{}: {}", line, column, reason);
+                            }
+                            return code;
+                        }
+                        eprintln!("{}:{}: {}", line, column, e);
+                        self.failed = true;
+                        return EXIT_RUNTIME_ERROR;
+                    },
+                    Ok(s) => {
+                        if let Err(e) = self.output(s, &mut writer) {
+                            eprintln!("{}", e);
+                            self.flush();
+                            return EXIT_IO_ERROR;
+                        }
+                    },
+                }
             }
         }
     }
-}
\ No newline at end of file
+}
@@ -1,197 +1,374 @@
 /// 定义了交互模式, 是在命令行解析之后, 用户与解释器内核进行互动的工具之一
 /// 允许类似Python IDLE的自动缩进
 /// 具备良好的错误处理性能
-
+/// 支持行编辑, 历史记录(Ctrl-R 反向搜索)与Tab补全
+/// 以逗号开头的行被视为交互模式的元命令, 如 ,help
 use crate::error::ErrorRead;
-use std::io;
-use crate::tokenizer::Tokenizer;
+use crate::tokenizer::{Tokenizer, IncrementalReader, Datum};
 use crate::parse::Parser;
 use crate::eval_env::EvalEnv;
-use crate::error::ErrorEval;
-use std::io::Write;
+use crate::error::{ErrorEval, ErrorKind};
+use crate::value::Value;
+use crate::alloc_counter;
+use crate::documentation::describe_name;
+use std::path::PathBuf;
 use std::rc::Rc;
+use std::time::Instant;
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+
+/// 历史记录文件名, 保存在用户主目录下
+const HISTORY_FILE: &str = ".minilisp_history";
+
+/// 元命令说明
+const META_HELP: &str = ",help            打开该说明文档
+,env             列出当前环境中用户定义的绑定
+,time <expr>     对表达式求值, 并给出耗时与内存分配情况
+,load <file>     在当前环境中载入并执行文件
+,reset           丢弃所有用户定义, 重新开始
+,describe <sym>  给出符号的调用形式, 参数个数与文档 (同 ,doc)
+,backtrace [n]   查看或设置出错时打印的调用栈深度, 为0时不打印
+,quit            退出交互模式";
+
+/// 补全时视为单词边界的字符
+const WORD_BREAKS: [char; 9] = [' ', '\t', '\n', '(', ')', '\'', '`', ',', '"'];
+
+/// 行编辑器的辅助结构, 负责Tab补全
+/// 补全候选来自求值环境中的特殊形式, 内置过程与用户定义的符号
+pub struct ReplHelper {
+    env: Rc<EvalEnv>,
+}
+
+impl ReplHelper {
+    /// 收集当前求值环境中所有可见的名字
+    fn candidates(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        names.extend(self.env.special_forms.keys().cloned());
+        names.extend(self.env.builtin_procs.keys().cloned());
+        let mut env: Option<Rc<EvalEnv>> = Some(self.env.clone());
+        while let Some(current) = env {
+            names.extend(current.symbol_map.borrow().keys().cloned());
+            env = current.parent.clone();
+        }
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let start: usize = line[..pos].rfind(|c| WORD_BREAKS.contains(&c)).map(|i| i + 1).unwrap_or(0);
+        let word: &str = &line[start..pos];
+        let matches: Vec<Pair> = self.candidates().into_iter()
+            .filter(|name| name.starts_with(word))
+            .map(|name| Pair { display: name.clone(), replacement: name })
+            .collect();
+        Ok((start, matches))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+/// 若为退出请求, 打印附带的原因并返回退出码
+fn exit_request(error: &ErrorEval) -> Option<i32> {
+    if let Some(reason) = error.exit_reason() {
+        eprintln!("Error: {}", reason);
+    }
+    error.exit_code()
+}
 
 /// 定义了交互模式自动机
 pub struct ReaderInteract {
-    space_buffer: Vec<usize>,
-    buffer_modify_pos: isize,
-    is_inside_quote: bool,
-    is_after_slash: bool,
-    is_inside_comment: bool,
+    reader: IncrementalReader,
+    editor: Editor<ReplHelper, DefaultHistory>,
+    history_path: Option<PathBuf>,
     templine: String,
-    line: String,
     env: Rc<EvalEnv>,
 }
 
 impl ReaderInteract {
     /// 新建交互模式
     pub fn new() -> Self {
+        Self::with_env(Rc::new(EvalEnv::new()))
+    }
+
+    /// 新建交互模式, 在给定的求值环境中执行
+    pub fn with_env(env: Rc<EvalEnv>) -> Self {
+        let mut editor: Editor<ReplHelper, DefaultHistory> = Editor::new().expect("Fail to initialize the line editor");
+        editor.set_helper(Some(ReplHelper { env: env.clone() }));
+        let history_path: Option<PathBuf> = std::env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE));
+        if let Some(path) = &history_path {
+            // 第一次使用时历史记录文件尚不存在, 忽略即可
+            _ = editor.load_history(path);
+        }
         Self {
-            space_buffer: Vec::new(), 
-            buffer_modify_pos: -1, 
-            is_inside_quote: false, 
-            is_after_slash: false,
-            is_inside_comment: false,
+            reader: IncrementalReader::new(),
+            editor,
+            history_path,
             templine: String::new(),
-            line: String::new(),
-            env: Rc::new(EvalEnv::new()),
+            env,
         }
     }
 
-    /// 读入一行文本, 检测是否已经是一个完整的表达式, 并且处理自动缩进
+    /// 读入一行文本, 交给增量读取机
+    /// 行提示符'>>>'与'...'由行编辑器打印, 续行时预先填入正确数量的空格完成缩进
     fn readline(&mut self) -> Result<(), ErrorRead> {
-        self.templine.clear();
-        let code = io::stdin().read_line(&mut self.templine);
-        // println!("LINE: {}", self.templine);
-        match code {
-            Ok(n) if n == 0 => std::process::exit(0),
-            Ok(_) => (),
+        let result = if !self.reader.is_pending() {
+            self.editor.readline(">>> ")
+        }
+        else {
+            let spaces: String = " ".repeat(self.reader.indent());
+            self.editor.readline_with_initial("... ", (spaces.as_str(), ""))
+        };
+        match result {
+            Ok(line) => self.templine = line,
+            Err(ReadlineError::Interrupted) => return Err(ErrorRead::KeyboardInterrupt),
+            Err(ReadlineError::Eof) => return Err(ErrorRead::EOF),
             Err(_) => return Err(ErrorRead::StreamFailure),
         }
-        if self.templine.len() == 1 && self.templine.clone().pop().unwrap() == '\n' {
-            return Err(ErrorRead::KeyboardInterrupt);
+        if !self.templine.trim().is_empty() {
+            _ = self.editor.add_history_entry(self.templine.as_str());
         }
-        for ch in self.templine.chars() {
-            match ch {
-                '\n' => {
-                    if self.is_inside_comment { self.is_inside_comment = false; continue; }
-                },
-                '(' => {
-                    if self.is_inside_comment { continue; } // Added
-                    if self.is_after_slash { self.is_after_slash = false; }
-                    if self.is_inside_quote { self.space_buffer[self.buffer_modify_pos as usize] += 1; } // 检查bound
-                    else { 
-                        self.buffer_modify_pos += 1; self.space_buffer.push(1); 
-                    }
-                },
-                ')' => {
-                    if self.is_inside_comment { continue; } // Added
-                    if self.is_after_slash { self.is_after_slash = false; }
-                    if self.is_inside_quote { self.space_buffer[self.buffer_modify_pos as usize] += 1; } // 检查bound
-                    else {
-                        if self.space_buffer.is_empty() {return Err(ErrorRead::SyntaxFailure)}
-                        self.buffer_modify_pos -= 1;
-                        self.space_buffer.pop();
-                    }
-                },
-                ';' => {
-                    if self.is_inside_comment { continue; } // Added 
-                    else { self.is_inside_comment = true; } // Added
-                    if self.is_after_slash { self.is_after_slash = false; }
-                    if self.is_inside_quote { self.space_buffer[self.buffer_modify_pos as usize] += 1; } // 检查bound
-                    else {
-                        self.is_inside_comment = true;
-                    }
-                },
-                '"' => {
-                    if self.is_inside_comment { continue; } // Added
-                    if self.is_after_slash { self.is_after_slash = false; }
-                    else {
-                        if self.is_inside_quote { self.is_inside_quote = false; }
-                        else { self.is_inside_quote = true; }
-                    }
-                    self.space_buffer[self.buffer_modify_pos as usize] += 1; // 检查bound
-                },
-                '\\' => {
-                    if self.is_inside_comment { continue; } // Added
-                    if self.is_inside_quote {
-                        if self.is_after_slash { self.is_after_slash = false; }
-                        else { self.is_after_slash = true; }
+        // 元命令不交给增量读取机
+        if self.is_meta_command() {
+            return Ok(());
+        }
+        self.templine.push('\n');
+        self.reader.feed(&self.templine);
+        return Ok(());
+    }
+
+    /// 刚读入的一行是否为元命令
+    /// 元命令只在没有未完成输入时识别, 否则逗号是 unquote
+    fn is_meta_command(&self) -> bool {
+        self.templine.trim_start().starts_with(',') && !self.reader.is_pending()
+    }
+
+    /// 执行元命令, 返回退出码表示需要退出交互模式
+    fn meta_command(&mut self, line: &str) -> Option<i32> {
+        let (command, argument) = match line.split_once(char::is_whitespace) {
+            Some((command, argument)) => (command, argument.trim()),
+            None => (line, ""),
+        };
+        match command {
+            ",help" => self.env.output.writeln(META_HELP),
+            ",quit" | ",q" => return Some(0),
+            ",env" => self.list_env(),
+            ",reset" => {
+                self.env = Rc::new(self.env.fresh());
+                if let Some(helper) = self.editor.helper_mut() {
+                    helper.env = self.env.clone();
+                }
+                self.env.output.writeln("Environment reset.");
+            },
+            ",backtrace" => self.backtrace(argument),
+            ",describe" | ",doc" if !argument.is_empty() => self.env.output.writeln(&describe_name(argument, self.env.clone())),
+            ",time" if !argument.is_empty() => return self.time(argument),
+            ",load" if !argument.is_empty() => {
+                if let Err(e) = self.load(argument) {
+                    if e.exit_code().is_some() {
+                        return exit_request(&e);
                     }
-                    self.space_buffer[self.buffer_modify_pos as usize] += 1; // 检查bound
-                },
-                'n' => {
-                    if self.is_inside_comment { continue; } // Added
-                    if self.is_inside_quote && self.is_after_slash { self.is_after_slash = false; }
-                    self.space_buffer[self.buffer_modify_pos as usize] += 1; // 检查bound
-                },
-                _ => {
-                    if self.is_inside_comment { continue; } // Added
-                    if self.buffer_modify_pos == -1 { break; }
-                    self.space_buffer[self.buffer_modify_pos as usize] += 1; // 检查bound
-                },
+                    eprintln!("Error:\n{}", e);
+                }
+            },
+            ",describe" | ",doc" | ",time" | ",load" => eprintln!("Error: {} needs an argument. Type ,help for usage.", command),
+            _ => eprintln!("Error: Unknown command {}. Type ,help for the list of commands.", command),
+        }
+        None
+    }
+
+    /// ,env 列出用户定义的绑定
+    fn list_env(&self) {
+        let bindings = self.env.symbol_map.borrow();
+        let mut names: Vec<&String> = bindings.keys().collect();
+        names.sort();
+        for name in names {
+            match &bindings[name] {
+                Value::LambdaValue(params, _, _, _) => self.env.output.writeln(&format!("{} = #<lambda {}>", name, params)),
+                value => self.env.output.writeln(&format!("{} = {}", name, value.to_string())),
             }
         }
-        self.line.push_str(&self.templine);
-        self.line.push('\n');
-        return Ok(());
     }
 
-    /// 用于打印交互模式中所需要的行提示符'>>>'与'...'并打印正确数量的空格完成缩进
-    fn printline(&mut self) -> () {
-        let mut result: usize = 0;
-        for s in &self.space_buffer {
-            result += s;
+    /// ,backtrace 查看或设置调用栈深度
+    fn backtrace(&self, argument: &str) {
+        if argument.is_empty() {
+            self.env.output.writeln(&format!("Backtrace depth: {}", self.env.call_stack.depth()));
+            return;
         }
-        if result == 0 {
-            print!(">>> ");
-            io::stdout().flush().unwrap();
+        match argument.parse::<usize>() {
+            Ok(depth) => {
+                self.env.call_stack.set_depth(depth);
+                self.env.output.writeln(&format!("Backtrace depth set to {}.", depth));
+            },
+            Err(_) => eprintln!("Error: ,backtrace needs a non-negative integer."),
         }
-        else {
-            print!("... ");
-            let spaces: String = std::iter::repeat(' ').take(result).collect();
-            print!("{}", spaces);
-            io::stdout().flush().unwrap();
+    }
+
+    /// ,time 对表达式求值并统计耗时与内存分配
+    /// 表达式请求退出时返回退出码
+    fn time(&self, source: &str) -> Option<i32> {
+        let mut reader: IncrementalReader = IncrementalReader::new();
+        reader.feed(source);
+        reader.finish();
+        let datum: Datum = match reader.next_datum() {
+            Ok(Some(datum)) => datum,
+            Ok(None) => { eprintln!("Error: {:?}: Incomplete expression", ErrorRead::SyntaxFailure); return None; },
+            Err(e) => { eprintln!("Error: {:?}", e); return None; },
+        };
+        let (allocations_before, bytes_before) = alloc_counter::snapshot();
+        let start: Instant = Instant::now();
+        let result = self.process(datum);
+        let elapsed = start.elapsed();
+        let (allocations_after, bytes_after) = alloc_counter::snapshot();
+        match result {
+            Ok(s) => self.output(s),
+            Err(e) if e.exit_code().is_some() => return exit_request(&e),
+            Err(e) => eprintln!("Error:\n{}", e),
+        }
+        self.env.output.writeln(&format!("; time: {:?}, allocations: {} ({} bytes)", elapsed, allocations_after - allocations_before, bytes_after - bytes_before));
+        None
+    }
+
+    /// ,load 在当前环境中执行文件, 遇到错误时停止
+    fn load(&self, path: &str) -> Result<(), ErrorEval> {
+        let content: String = std::fs::read_to_string(path).map_err(|e| {
+            ErrorEval::new(ErrorKind::Runtime(format!("{:?}: {}: {}", ErrorRead::FileOpenError, path, e)))
+        })?;
+        let mut reader: IncrementalReader = IncrementalReader::new();
+        reader.feed(&content);
+        reader.finish();
+        loop {
+            match reader.next_datum() {
+                Ok(Some(datum)) => {
+                    let (line, column) = (datum.line, datum.column);
+                    self.process(datum).map_err(|error| error.at(format!("{}:{}:{}", path, line, column)))?;
+                },
+                Ok(None) if reader.is_pending() => {
+                    return Err(ErrorEval::new(ErrorKind::BadSyntax(format!("{:?}: Unexpected end of file", ErrorRead::SyntaxFailure))).at(path.to_string()));
+                },
+                Ok(None) => return Ok(()),
+                Err(e) => return Err(ErrorEval::new(ErrorKind::BadSyntax(format!("{:?}", e))).at(path.to_string())),
+            }
         }
     }
 
     /// 检测到一个完整表达式之后进行处理
-    fn process(&self) -> Result<String, ErrorEval> {
-        let mut tokenizer: Tokenizer = Tokenizer::new(self.line.clone());
-        let tokens = tokenizer.tokenize();
+    fn process(&self, datum: Datum) -> Result<Value, ErrorEval> {
+        let mut tokenizer: Tokenizer = Tokenizer::new(datum.text);
+        let tokens = tokenizer.tokenize()?;
         let mut parser = Parser::new(tokens);
         let value = parser.parse();
-        let result = self.env.clone().eval(value)?;
-        Ok(result.to_string())
+        self.env.clone().eval(value)
     }
 
     /// 处理输出
-    fn output(&self, result: String) -> () {
-        if result == "()".to_string() {
+    /// 空表同时也是 define, display 等没有值的表达式的结果, 因此不输出
+    fn output(&self, result: Value) {
+        if let Value::NilValue = result {
             return;
         }
-        println!("{}", result);
+        self.env.output.writeln(&result.to_string());
     }
 
     /// 清空交互模式自动机的状态
-    fn flush(&mut self) -> () {
-        self.line.clear();
+    fn flush(&mut self) {
         self.templine.clear();
-        self.is_inside_quote = false;
-        self.is_after_slash = false;
-        self.is_inside_comment = false;
-        self.space_buffer.clear();
-        self.buffer_modify_pos = -1;
+        self.reader.clear();
+    }
+
+    /// 保存历史记录
+    fn save_history(&mut self) {
+        if let Some(path) = &self.history_path {
+            if let Err(e) = self.editor.save_history(path) {
+                eprintln!("Fail to save history: {}", e);
+            }
+        }
     }
 
     /// 调用交互模式
-    pub fn call(&mut self) -> () {
+    /// 在空行上按下Ctrl-D时退出, Ctrl-C放弃当前输入
+    /// 返回进程应当使用的退出码, (exit n) 时为n
+    pub fn call(&mut self) -> i32 {
         loop {
-            self.printline();
             let read_status = self.readline();
-            if read_status.is_ok() {
-                if self.space_buffer.is_empty() {
-                    let result = self.process();
-                    if result.is_ok() {
-                        self.output(result.ok().unwrap());
-                        self.flush();
-                    }
-                    else {
-                        eprintln!("Error:\n{}", result.err().unwrap());
-                        self.flush();
-                    }
-                }
-            }
-            else {
+            if read_status.is_err() {
                 let err: ErrorRead = read_status.err().unwrap();
                 if err == ErrorRead::KeyboardInterrupt {
-                    println!("Input interrupted with a pure return.");
+                    self.env.output.writeln("KeyboardInterrupt");
                     self.flush();
                     continue;
                 }
+                if err == ErrorRead::EOF {
+                    if self.reader.is_pending() {
+                        self.env.output.writeln("Unfinished input discarded.");
+                        self.flush();
+                        continue;
+                    }
+                    self.save_history();
+                    return 0;
+                }
                 eprintln!("Error: {:?}", err);
                 self.flush();
+                continue;
+            }
+            if self.is_meta_command() {
+                let line: String = self.templine.trim().to_string();
+                self.flush();
+                if let Some(code) = self.meta_command(&line) {
+                    self.save_history();
+                    return code;
+                }
+                continue;
+            }
+            loop {
+                match self.reader.next_datum() {
+                    Ok(None) => break,
+                    Ok(Some(datum)) => {
+                        let result = self.process(datum);
+                        if result.is_ok() {
+                            self.output(result.ok().unwrap());
+                        }
+                        else if let Some(code) = exit_request(result.as_ref().err().unwrap()) {
+                            self.save_history();
+                            return code;
+                        }
+                        else {
+                            eprintln!("Error:\n{}", result.err().unwrap());
+                            self.flush();
+                            break;
+                        }
+                    },
+                    Err(err) => {
+                        eprintln!("Error: {:?}", err);
+                        self.flush();
+                        break;
+                    },
+                }
             }
         }
     }
-}
\ No newline at end of file
+}
+
+impl Default for ReaderInteract {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,109 @@
+/// 定义了沙箱
+/// 沙箱环境只安装允许列表中的内置过程与特殊形式, 并在求值过程中检查资源限制
+/// 超出限制时求值以错误结束, 沙箱内的 exit 也只会得到错误, 不会结束宿主进程
+use std::cell::Cell;
+use std::time::Instant;
+use crate::alloc_counter;
+use crate::error::{ErrorEval, ErrorKind};
+
+/// 不会产生副作用, 适合用于求值不可信代码的内置过程
+pub const SAFE_BUILTINS: &[&str] = &[
+    "apply", "error",
+    "atom?", "boolean?", "integer?", "list?", "number?", "null?", "pair?", "procedure?", "string?", "symbol?",
+    "append", "push", "car", "cdr", "cons", "length", "list", "map", "filter", "reduce",
+    "caar", "cadr", "cdar", "cddr", "caaar", "caadr", "cadar", "caddr", "cdaar", "cdadr", "cddar", "cdddr",
+    "list-ref", "list-tail", "reverse", "last", "take", "drop", "fold-left", "fold-right",
+    "assoc", "assq", "assv", "member", "memq", "memv", "delete", "remove", "partition", "iota",
+    "any", "every", "for-each", "append-map", "delete-duplicates", "list-index",
+    "values", "call-with-values", "force", "make-promise", "promise?", "make-parameter",
+    "stream-null?", "stream-pair?", "stream-car", "stream-cdr", "stream-take", "stream-map", "stream-filter",
+    "stream-fold", "stream->list", "list->stream", "stream-range",
+    "+", "-", "*", "/", "abs", "expt", "quotient", "modulo", "remainder",
+    "eq?", "equal?", "not", "=", "<", ">", "<=", ">=", "even?", "odd?", "zero?", "sort",
+];
+
+/// 适合用于求值不可信代码的特殊形式
+pub const SAFE_SPECIAL_FORMS: &[&str] = &[
+    "quote", "if", "and", "or", "lambda", "case-lambda", "cond", "case", "when", "unless", "do", "begin",
+    "let", "let*", "letrec", "letrec*", "let-values", "let*-values", "define-values", "quasiquote", "unquote",
+    "delay", "delay-force", "parameterize", "stream-cons",
+];
+
+/// 资源限制, 为None的项不做限制
+/// max_steps: 最多求值多少次表达式
+/// max_depth: 求值的最大嵌套深度
+/// max_cells: 求值过程中最多分配多少个单元(堆上的分配次数), 需要安装 alloc_counter::CountingAllocator
+/// deadline: 求值必须在该时刻之前结束
+#[derive(Debug, Clone, Default)]
+pub struct Limits {
+    pub max_steps: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub max_cells: Option<usize>,
+    pub deadline: Option<Instant>,
+}
+
+/// 沙箱的运行状态, 由沙箱环境及其派生出的所有环境共享
+pub struct Sandbox {
+    limits: Limits,
+    steps: Cell<usize>,
+    depth: Cell<usize>,
+    cells_start: Cell<usize>,
+}
+
+/// 一次求值的深度记录, 离开求值时自动减少嵌套深度
+pub struct DepthGuard<'a> {
+    sandbox: &'a Sandbox,
+}
+
+impl Drop for DepthGuard<'_> {
+    fn drop(&mut self) {
+        self.sandbox.depth.set(self.sandbox.depth.get() - 1);
+    }
+}
+
+impl Sandbox {
+    /// 新建沙箱, 分配单元数从此刻开始统计
+    pub fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            steps: Cell::new(0),
+            depth: Cell::new(0),
+            cells_start: Cell::new(alloc_counter::thread_allocations()),
+        }
+    }
+
+    /// 计作一步, 超出步数, 分配单元数或期限时返回错误
+    /// 求值每次进入时调用, 在Rust中循环的内置过程每一轮也调用一次
+    pub fn tick(&self) -> Result<(), ErrorEval> {
+        let steps: usize = self.steps.get() + 1;
+        self.steps.set(steps);
+        if let Some(max_steps) = self.limits.max_steps {
+            if steps > max_steps {
+                return Err(ErrorEval::new(ErrorKind::ResourceLimit(format!("Step limit of {} exceeded", max_steps))));
+            }
+        }
+        if let Some(max_cells) = self.limits.max_cells {
+            if alloc_counter::thread_allocations() - self.cells_start.get() > max_cells {
+                return Err(ErrorEval::new(ErrorKind::ResourceLimit(format!("Allocation limit of {} cells exceeded", max_cells))));
+            }
+        }
+        if let Some(deadline) = self.limits.deadline {
+            if Instant::now() >= deadline {
+                return Err(ErrorEval::new(ErrorKind::ResourceLimit("Deadline exceeded".to_string())));
+            }
+        }
+        Ok(())
+    }
+
+    /// 进入一次求值, 超出任何一项限制时返回错误
+    pub fn enter(&self) -> Result<DepthGuard<'_>, ErrorEval> {
+        self.tick()?;
+        if let Some(max_depth) = self.limits.max_depth {
+            if self.depth.get() >= max_depth {
+                return Err(ErrorEval::new(ErrorKind::ResourceLimit(format!("Recursion depth limit of {} exceeded", max_depth))));
+            }
+        }
+        self.depth.set(self.depth.get() + 1);
+        Ok(DepthGuard { sandbox: self })
+    }
+}
@@ -1,10 +1,12 @@
 /// 定义特殊形式
 
-use crate::builtins::list;
-use crate::value::Value;
+use crate::builtins::{list, eq_q, spread_values, Promise, ParameterUpdate};
+use crate::value::{Value, Params};
 use crate::eval_env::EvalEnv;
 use std::rc::Rc;
-use crate::error::ErrorEval;
+use crate::error::{ErrorEval, ErrorKind};
+use crate::value::BuiltinFn;
+use crate::debugger::Traced;
 pub type SpecialForm = fn(Vec<Value>, Rc<EvalEnv>) -> Result<Value, ErrorEval>;
 
 /// define 特殊形式.
@@ -16,10 +18,7 @@ pub type SpecialForm = fn(Vec<Value>, Rc<EvalEnv>) -> Result<Value, ErrorEval>;
 /// ```
 pub fn define_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
     if args.len() < 2 {
-        return Err(ErrorEval {
-            message: format!("{}: Special Form <define>: Missing parameter", 0),
-            index: 0
-        });
+        return Err(ErrorEval::arity("define", args.len()));
     }
     match args[0].clone() {
         Value::SymbolValue(s) => {
@@ -38,20 +37,14 @@ pub fn define_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEva
                     _ = ref_of_map.insert(s, value_to_be_inserted);
                 }
                 else {
-                    let value_to_be_inserted = env.clone().eval(args[1].clone()).map_err(|error| ErrorEval{
-                        message: format!("{}: Special Form <define>: Fail to evaluate a value\n{}", error.index + 1 ,error.message),
-                        index: error.index + 1
-                    })?;
+                    let value_to_be_inserted = env.clone().eval(args[1].clone()).map_err(|error| error.context("define", "Fail to evaluate a value"))?;
                     std::mem::drop(borrow);
                     let mut ref_of_map = env.symbol_map.borrow_mut();
                     _ = ref_of_map.insert(s, value_to_be_inserted);
                 }
             }
             else {
-                let value_to_be_inserted = env.clone().eval(args[1].clone()).map_err(|error| ErrorEval{
-                    message: format!("{}: Special Form <define>: Fail to evaluate a value\n{}", error.index + 1 ,error.message),
-                    index: error.index + 1
-                })?;
+                let value_to_be_inserted = env.clone().eval(args[1].clone()).map_err(|error| error.context("define", "Fail to evaluate a value"))?;
                 let mut ref_of_map = env.symbol_map.borrow_mut();
                 _ = ref_of_map.insert(s, value_to_be_inserted);
             }
@@ -64,10 +57,10 @@ pub fn define_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEva
                     let temp_env = env.clone();
                     _ = env.symbol_map.borrow_mut().insert(s, lambda_form(lambda_args, temp_env)?);
                 },
-                _ => return Err(ErrorEval { message: format!("{}: Special Form <define>: Malformed define", 0), index: 0 }),
+                _ => return Err(ErrorEval::bad_syntax("define", "Malformed define")),
             }
         },
-        _ => return Err(ErrorEval { message: format!("{}: Special Form <define>: Malformed define", 0), index: 0 })
+        _ => return Err(ErrorEval::bad_syntax("define", "Malformed define"))
     }
     Ok(Value::NilValue)
 }
@@ -79,7 +72,7 @@ pub fn define_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEva
 /// (print '(+ 1 2)) 输出结果: (+ 1 2)
 pub fn quote_form(args: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
     if args.len() < 1 {
-        Err(ErrorEval{message: format!("{}: Special Form <quote>: Missing parameter", 0), index: 0})
+        Err(ErrorEval::arity("quote", args.len()))
     }
     else {
         Ok(args[0].clone())
@@ -87,72 +80,42 @@ pub fn quote_form(args: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEva
 }
 
 /// if 特殊形式
-/// (if (条件) (真分支) (假分支))
+/// (if (条件) (真分支) [(假分支)])
+/// 没有假分支且条件为 #f 时返回空表
 pub fn if_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    let result = env.clone().eval(args[0].clone());
-    if result.is_ok() {
-        match result.unwrap() {
-            Value::BooleanValue(false) => return env.eval(args[2].clone()).map_err(|error|ErrorEval {
-                message: format!("{}: Special Form <if>: Fail to evaluate the false branch\n{}", error.index + 1, error.message),
-                index: error.index + 1
-            }),
-            Value::BooleanValue(true) => return env.eval(args[1].clone()).map_err(|error| ErrorEval { 
-                message: format!("{}: Special Form <if>: Fail to evaluate the true branch\n{}", error.index + 1, error.message),
-                index: error.index + 1 
-            }),
-            _ => return env.clone().eval(args[1].clone()).map_err(|error| ErrorEval {
-                message: format!("{}: Special Form <if>: Fail to evaluate the true branch\n{}", error.index + 1, error.message),
-                index: error.index + 1
-            }),
-        }
+    if args.len() != 2 && args.len() != 3 {
+        return Err(ErrorEval::arity("if", args.len()));
     }
-    else {
-        env.eval(args[1].clone()).map_err(|error| ErrorEval {
-            message: format!("{}: Special Form <if>: Fail to evaluate the condition\n{}", error.index + 1, error.message),
-            index: error.index + 1
-        })
+    let flag: Value = env.clone().eval(args[0].clone()).map_err(|error| error.context("if", "Fail to evaluate the condition"))?;
+    match (flag, args.get(2)) {
+        (Value::BooleanValue(false), Some(alternative)) => env.eval(alternative.clone()).map_err(|error| error.context("if", "Fail to evaluate the false branch")),
+        (Value::BooleanValue(false), None) => Ok(Value::NilValue),
+        _ => env.eval(args[1].clone()).map_err(|error| error.context("if", "Fail to evaluate the true branch")),
     }
 }
 
 /// and 特殊形式
 /// (and <expr 1> <expr 2> <expr 3>)
+/// 从左向右求值, 遇到 #f 时返回 #f, 否则返回最后一个表达式的值; 每个表达式只求值一次, 出错时立即结束
 pub fn and_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if args.is_empty() {
-        return Ok(Value::BooleanValue(true));
-    }
-    for arg in args.clone() {
-        let result = env.clone().eval(arg.clone());
-        if result.is_ok() {
-            match result.unwrap() {
-                Value::BooleanValue(false) => return Ok(Value::BooleanValue(false)),
-                Value::BooleanValue(true) => continue,
-                _ => continue,
-            }
-        }
-        else {
-            continue;
+    let mut last: Value = Value::BooleanValue(true);
+    for arg in args.iter() {
+        match env.clone().eval(arg.clone()).map_err(|error| error.context("and", "Fail to evaluate an operand"))? {
+            Value::BooleanValue(false) => return Ok(Value::BooleanValue(false)),
+            value => last = value,
         }
     }
-    env.eval(args[args.len() - 1].clone()).or(Ok(Value::NilValue))
+    Ok(last)
 }
 
 /// or 特殊形式
-/// (and <expr 1> <expr 2> <expr 3>)
+/// (or <expr 1> <expr 2> <expr 3>)
+/// 从左向右求值, 返回第一个不为 #f 的值, 都为 #f 时返回 #f; 出错时立即结束
 pub fn or_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if args.is_empty() {
-        return Ok(Value::BooleanValue(false));
-    }
-    for arg in args.clone() {
-        let result = env.clone().eval(arg.clone());
-        if result.is_ok() {
-            match result.unwrap() {
-                Value::BooleanValue(false) => continue,
-                v @ Value::BooleanValue(true) => return Ok(v),
-                v @ _ => return Ok(v),
-            }
-        }
-        else {
-            return Ok(Value::NilValue);
+    for arg in args.iter() {
+        match env.clone().eval(arg.clone()).map_err(|error| error.context("or", "Fail to evaluate an operand"))? {
+            Value::BooleanValue(false) => continue,
+            value => return Ok(value),
         }
     }
     Ok(Value::BooleanValue(false))
@@ -160,87 +123,276 @@ pub fn or_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
 
 /// lambda 特殊形式
 /// (define foobar (lambda (x) (print x))))
+/// 参数表可以带剩余参数 (a b . rest) 或者就是一个符号 args, 也可以用 #!optional 与 #:key 声明可选参数与关键字参数
+/// 函数体以字符串开头且之后还有表达式时, 该字符串作为文档字符串保存
+/// (define (double x) "将x加倍" (+ x x))
 pub fn lambda_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
     if args.len() < 2{
-        return Err(ErrorEval{message: format!("{}: Special Form <lambda>: Missing part of lambda expression", 0), index: 0});
-    }
-    let vec: Vec<Value> = args[0].to_vector().map_err(|error| ErrorEval {
-        message: format!("{}: Special Form <lambda>: Fail to convert value to vector\n{}", error.index + 1, error.message),
-        index: error.index + 1
-    })?;
-    let mut params: Vec<String> = Vec::new();
-    let temp_arg = args[0].clone();
-    vec.iter().for_each(|value| params.push(value.to_string()));
-    let body: Vec<Value> = args.into_iter().skip(1).filter(|bodyv| 
+        return Err(ErrorEval::bad_syntax("lambda", "Missing part of lambda expression"));
+    }
+    let params: Params = Params::parse(&args[0])?;
+    let mut body: Vec<Value> = args.into_iter().skip(1).filter(|bodyv| 
         match bodyv {
             Value::NilValue => false,
             _ => true,
         }
     ).collect();
-    match temp_arg {
-        Value::PairValue(_, _) => return Ok(Value::LambdaValue(Box::new(params), Box::new(body), Rc::clone(&env))),
-        _ => return Ok(Value::LambdaValue(Box::new(Vec::<String>::new()), Box::new(body), Rc::clone(&env))),
+    // 函数体开头的字符串字面量作为文档字符串, 但函数体只有它一个表达式时它是返回值
+    let mut doc: Option<String> = None;
+    if body.len() > 1 {
+        if let Value::StringValue(s) = &body[0] {
+            doc = Some(s.clone());
+            body.remove(0);
+        }
+    }
+    Ok(Value::LambdaValue(Box::new(params), Box::new(body), Rc::clone(&env), doc))
+}
+
+/// case-lambda 特殊形式
+/// (case-lambda (params body ...) ...)
+/// 调用时选择第一个能接受这么多实参的子句, 没有这样的子句时报告参数个数不符
+pub fn case_lambda_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    let mut clauses: Vec<(Params, Value)> = Vec::new();
+    for clause in args {
+        let clause_vec: Vec<Value> = match clause {
+            Value::PairValue(_, _) => clause.to_vector().map_err(|error| error.context("case-lambda", "Fail to convert value to vector"))?,
+            _ => return Err(ErrorEval::bad_syntax("case-lambda", "Each clause should be a list (params body ...)")),
+        };
+        let params: Params = Params::parse(&clause_vec[0])?;
+        clauses.push((params, lambda_form(clause_vec, env.clone())?));
     }
+    let case_lambda = move |args: Vec<Value>, env: Rc<EvalEnv>| -> Result<Value, ErrorEval> {
+        match clauses.iter().find(|(params, _)| params.accepts(args.len())) {
+            Some((_, lambda)) => env.apply_procedure(lambda.clone(), args),
+            None => {
+                let expected: Vec<String> = clauses.iter().map(|(params, _)| params.arity()).collect();
+                Err(ErrorEval::new(ErrorKind::ArityMismatch { procedure: "case-lambda".to_string(), expected: expected.join(" or "), got: args.len() }))
+            },
+        }
+    };
+    Ok(Value::ProcedureValue(Box::new(Rc::new(case_lambda) as BuiltinFn)))
 }
 
 /// cond 特殊形式
 /// (cond (条件1 值1) (条件2 值2) (条件3 值3) ... )
 /// 逐个条件求值, 除非求得为布尔字面量否, 否则都认为是真
 /// 一旦遇到真, 则返回该条件对应的值.
+/// 子句写作 (条件 => proc) 时, 以条件的值调用proc; 子句只有条件时返回条件的值
 /// 未遇到真, 则不返回
 pub fn cond_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
     for (index, arg) in args.iter().enumerate() {
         match arg {
             Value::PairValue(_, _) => {
-                let arg_vec: Vec<Value> = arg.to_vector().map_err(|error| ErrorEval {
-                    message: format!("{}: Special Form <cond>: Fail to convert value to vector\n{}", error.index + 1, error.message),
-                    index: error.index + 1
-                })?;
-                let flag = env.clone().eval(arg_vec[0].clone()).map_err(|error| ErrorEval {
-                    message: format!("{}: Special Form <cond>: Fail to evaluate condition\n{}", error.index + 1, error.message),
-                    index: error.index + 1
-                })?;
+                let arg_vec: Vec<Value> = arg.to_vector().map_err(|error| error.context("cond", "Fail to convert value to vector"))?;
+                let flag = env.clone().eval(arg_vec[0].clone()).map_err(|error| error.context("cond", "Fail to evaluate condition"))?;
                 match flag {
                     Value::BooleanValue(false) => continue,
-                    Value::SymbolValue(s) if s == "else".to_string() => {
-                        if index == args.len() - 1 {
-                            let mut result_vec:Vec<Value> = Vec::new(); 
-                            arg_vec.iter().for_each(|arg_v| 
-                                result_vec.push(env.clone().eval(arg_v.clone()).expect("Corruption when evaluating a value in form <cond>."))
-                            );
-                            return result_vec.pop().ok_or(ErrorEval{
-                                message: format!("{}: Special Form <cond>: Fail to pop a value", 0),
-                                index: 0
-                            });
-                        }
-                        else {
-                            return Err(ErrorEval{
-                                message: format!("{}: Special Form <cond>: \"else\" must be at the condition position in the last clause", 0),
-                                index: 0,
-                            });
-                        }
-                    },
-                    _ => {
-                        let mut result_vec:Vec<Value> = Vec::new(); 
-                        arg_vec.iter().for_each(|arg_v| 
-                            result_vec.push(env.clone().eval(arg_v.clone()).expect("Corruption when evaluating a value in form <cond>."))
-                        );
-                        return result_vec.pop().ok_or(ErrorEval{
-                            message: format!("{}: Special Form <cond>: Missing executing part of a clause", 0),
-                            index: 0
-                        });
+                    Value::SymbolValue(s) if s == "else".to_string() && index != args.len() - 1 => {
+                        return Err(ErrorEval::bad_syntax("cond", "\"else\" must be at the condition position in the last clause"));
                     },
+                    flag => return clause_result("cond", flag, &arg_vec[1..], env),
+                }
+            },
+            _ => return Err(ErrorEval::arity("cond", args.len())),
+        }
+    }
+    Ok(Value::NilValue)
+}
+
+/// cond 与 case 中选中的子句的值
+/// (=> proc) 以flag调用proc, 没有表达式时返回flag, 否则依次求值返回最后一个表达式的值
+fn clause_result(form: &str, flag: Value, exprs: &[Value], env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    match exprs {
+        [] => Ok(flag),
+        [Value::SymbolValue(arrow), receiver] if arrow == "=>" => {
+            let receiver: Value = env.clone().eval(receiver.clone()).map_err(|error| error.context(form, "Fail to evaluate the receiver of =>"))?;
+            env.apply_procedure(receiver, vec![flag])
+        },
+        _ => eval_body(exprs, env),
+    }
+}
+
+/// case 特殊形式
+/// (case key ((datum ...) expr ...) ... (else expr ...))
+/// 对key求值后, 选择第一个包含与其 eq? 的datum的子句; 子句同样可以写作 ((datum ...) => proc)
+pub fn case_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if args.is_empty() {
+        return Err(ErrorEval::arity("case", args.len()));
+    }
+    let key: Value = env.clone().eval(args[0].clone()).map_err(|error| error.context("case", "Fail to evaluate the key"))?;
+    for (index, clause) in args[1..].iter().enumerate() {
+        let clause_vec: Vec<Value> = match clause {
+            Value::PairValue(_, _) => clause.to_vector().map_err(|error| error.context("case", "Fail to convert value to vector"))?,
+            _ => return Err(ErrorEval::bad_syntax("case", "Each clause should be a list ((datum ...) expr ...)")),
+        };
+        let matched: bool = match &clause_vec[0] {
+            Value::SymbolValue(s) if s == "else" && index == args.len() - 2 => true,
+            Value::SymbolValue(s) if s == "else" => return Err(ErrorEval::bad_syntax("case", "\"else\" must be in the last clause")),
+            Value::NilValue => false,
+            data @ Value::PairValue(_, _) => {
+                let mut matched: bool = false;
+                for datum in data.to_vector().map_err(|error| error.context("case", "Fail to convert value to vector"))? {
+                    if let Value::BooleanValue(true) = eq_q(vec![key.clone(), datum], env.clone())? {
+                        matched = true;
+                        break;
+                    }
                 }
+                matched
             },
-            _ => return Err(ErrorEval {
-                message: format!("{}: Special Form <cond>: Missing parameter", 0),
-                index: 0
-            }),
+            _ => return Err(ErrorEval::bad_syntax("case", "Each clause should start with a list of data or else")),
+        };
+        if matched {
+            return clause_result("case", key, &clause_vec[1..], env);
         }
     }
     Ok(Value::NilValue)
 }
 
+/// when 特殊形式
+/// (when condition body ...) 条件不为 #f 时依次对函数体求值
+pub fn when_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if args.is_empty() {
+        return Err(ErrorEval::arity("when", args.len()));
+    }
+    match env.clone().eval(args[0].clone()).map_err(|error| error.context("when", "Fail to evaluate condition"))? {
+        Value::BooleanValue(false) => Ok(Value::NilValue),
+        _ => eval_body(&args[1..], env),
+    }
+}
+
+/// unless 特殊形式
+/// (unless condition body ...) 条件为 #f 时依次对函数体求值
+pub fn unless_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if args.is_empty() {
+        return Err(ErrorEval::arity("unless", args.len()));
+    }
+    match env.clone().eval(args[0].clone()).map_err(|error| error.context("unless", "Fail to evaluate condition"))? {
+        Value::BooleanValue(false) => eval_body(&args[1..], env),
+        _ => Ok(Value::NilValue),
+    }
+}
+
+/// do 特殊形式
+/// (do ((var init step) ...) (test expr ...) body ...)
+/// 每一轮在新的环境中绑定变量, test为真时对expr求值并返回最后一个的值, 否则对函数体求值, 再以step的值进入下一轮
+/// 没有step的变量保持原来的值
+pub fn do_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if args.len() < 2 {
+        return Err(ErrorEval::arity("do", args.len()));
+    }
+    let mut names: Vec<String> = Vec::new();
+    let mut steps: Vec<Option<Value>> = Vec::new();
+    let mut values: Vec<Value> = Vec::new();
+    let specs: Vec<Value> = match &args[0] {
+        Value::NilValue => Vec::new(),
+        specs => specs.to_vector().map_err(|error| error.context("do", "Fail to convert value to vector"))?,
+    };
+    for spec in specs {
+        let spec_vec: Vec<Value> = match spec {
+            Value::PairValue(_, _) => spec.to_vector().map_err(|error| error.context("do", "Fail to convert value to vector"))?,
+            _ => Vec::new(),
+        };
+        match spec_vec.as_slice() {
+            [Value::SymbolValue(name), init, step @ ..] if step.len() <= 1 => {
+                names.push(name.clone());
+                values.push(env.clone().eval(init.clone()).map_err(|error| error.context("do", "Fail to evaluate a value"))?);
+                steps.push(step.first().cloned());
+            },
+            _ => return Err(ErrorEval::bad_syntax("do", "variable should be written as (var init [step])")),
+        }
+    }
+    let test_vec: Vec<Value> = match &args[1] {
+        test @ Value::PairValue(_, _) => test.to_vector().map_err(|error| error.context("do", "Fail to convert value to vector"))?,
+        _ => return Err(ErrorEval::bad_syntax("do", "Missing the test clause (test expr ...)")),
+    };
+    loop {
+        let env_derived: Rc<EvalEnv> = env.clone().derive(names.clone(), values)?.into();
+        let done: Value = env_derived.clone().eval(test_vec[0].clone()).map_err(|error| error.context("do", "Fail to evaluate the test"))?;
+        if !matches!(done, Value::BooleanValue(false)) {
+            return eval_body(&test_vec[1..], env_derived);
+        }
+        eval_body(&args[2..], env_derived.clone())?;
+        values = Vec::new();
+        for (name, step) in names.iter().zip(steps.iter()) {
+            values.push(match step {
+                Some(step) => env_derived.clone().eval(step.clone()).map_err(|error| error.context("do", "Fail to evaluate a step"))?,
+                None => env_derived.symbol_map.borrow()[name].clone(),
+            });
+        }
+    }
+}
+
+/// delay 特殊形式
+/// (delay expr) 返回一个promise, 第一次 force 时才对表达式求值, 之后直接返回记下的值
+pub fn delay_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if args.len() != 1 {
+        return Err(ErrorEval::arity("delay", args.len()));
+    }
+    Ok(Promise::delayed(args[0].clone(), env, false))
+}
+
+/// delay-force 特殊形式
+/// (delay-force expr) 与 delay 类似, 但表达式的值应当是另一个promise
+/// force 时迭代地求值, 用于写出不会加深调用的惰性循环
+pub fn delay_force_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if args.len() != 1 {
+        return Err(ErrorEval::arity("delay-force", args.len()));
+    }
+    Ok(Promise::delayed(args[0].clone(), env, true))
+}
+
+/// parameterize 特殊形式
+/// (parameterize ((param value) ...) body ...)
+/// 对函数体求值期间将参数对象param的值设为value (经过转换过程), 结束或出错时恢复原来的值
+pub fn parameterize_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if args.is_empty() {
+        return Err(ErrorEval::arity("parameterize", args.len()));
+    }
+    let bindings: Vec<Value> = match &args[0] {
+        Value::NilValue => Vec::new(),
+        bindings => bindings.to_vector().map_err(|error| error.context("parameterize", "Fail to convert value to vector"))?,
+    };
+    let mut updates: Vec<(Value, Value)> = Vec::new();
+    for binding in bindings {
+        let binding_vec: Vec<Value> = match binding {
+            Value::PairValue(_, _) => binding.to_vector().map_err(|error| error.context("parameterize", "Fail to convert value to vector"))?,
+            _ => Vec::new(),
+        };
+        if binding_vec.len() != 2 {
+            return Err(ErrorEval::bad_syntax("parameterize", "binding should be a 2-element list (param value)"));
+        }
+        let parameter: Value = env.clone().eval(binding_vec[0].clone()).map_err(|error| error.context("parameterize", "Fail to evaluate a parameter"))?;
+        if !matches!(parameter, Value::ProcedureValue(_)) {
+            return Err(ErrorEval::type_error("parameterize", "parameter", &parameter));
+        }
+        let value: Value = env.clone().eval(binding_vec[1].clone()).map_err(|error| error.context("parameterize", "Fail to evaluate a value"))?;
+        updates.push((parameter, value));
+    }
+    let mut installed: Vec<(Value, Value)> = Vec::new();
+    let mut result: Result<Value, ErrorEval> = Ok(Value::NilValue);
+    for (parameter, value) in updates {
+        match env.apply_procedure(parameter.clone(), vec![Value::foreign(ParameterUpdate { convert: true }), value]) {
+            Ok(old) => installed.push((parameter, old)),
+            Err(error) => {
+                result = Err(error);
+                break;
+            },
+        }
+    }
+    if result.is_ok() {
+        result = eval_body(&args[1..], env.clone());
+    }
+    // 直接调用参数对象而不经过 apply_procedure, 超出沙箱的限制之后也能恢复
+    for (parameter, old) in installed.into_iter().rev() {
+        let restored: Result<Value, ErrorEval> = match &parameter {
+            Value::ProcedureValue(f) => f(vec![Value::foreign(ParameterUpdate { convert: false }), old], env.clone()),
+            _ => Err(ErrorEval::type_error("parameterize", "parameter", &parameter)),
+        };
+        result = result.and_then(|value| restored.map(|_| value));
+    }
+    result
+}
+
 /// begin 特殊形式
 /// (begin <expr1> <expr2> ... <expr n>)
 /// 每个子句逐个执行
@@ -258,123 +410,413 @@ pub fn cond_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval>
 /// "pos 2"
 pub fn begin_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
     if args.is_empty() {
-        return Err(ErrorEval {
-            message: format!("{}: Special Form <begin>: Missing parameter", 0),
-            index: 0
-        });
+        return Err(ErrorEval::arity("begin", args.len()));
     }
     let mut result: Value = Value::NilValue;
     for arg in args {
-        result = env.clone().eval(arg).map_err(|error| ErrorEval{
-            message: format!("{}: Special Form <begin>: Missing parameter\n{}", error.index + 1, error.message),
-            index: error.index + 1
-        })?
+        result = env.clone().eval(arg).map_err(|error| error.context("begin", "Missing parameter"))?
     }
     Ok(result)
 }
 
+/// 解析 let 家族的绑定表 ((name expr) ...)
+fn let_bindings(form: &str, bindings: &Value) -> Result<Vec<(String, Value)>, ErrorEval> {
+    match bindings {
+        Value::NilValue => return Ok(Vec::new()),
+        Value::PairValue(_, _) => {},
+        _ => return Err(ErrorEval::bad_syntax(form, "temporary bindings without parentheses: \n (let ((#<binding>)(...)) (#<procedure>)(..) \n      ^                 ^")),
+    }
+    let mut result: Vec<(String, Value)> = Vec::new();
+    for binding in bindings.to_vector().map_err(|error| error.context(form, "Fail to convert value to vector"))? {
+        match binding {
+            Value::PairValue(_, _) => {
+                let mut binding_vec: Vec<Value> = binding.to_vector().map_err(|error| error.context(form, "Fail to convert value to vector"))?;
+                match (binding_vec.len(), &binding_vec[0]) {
+                    (2, Value::SymbolValue(name)) => {
+                        let name: String = name.clone();
+                        result.push((name, binding_vec.remove(1)));
+                    },
+                    _ => return Err(ErrorEval::bad_syntax(form, "temporary binding should be a 2-element list")),
+                }
+            },
+            _ => return Err(ErrorEval::bad_syntax(form, "temporary binding should be a 2-element list")),
+        }
+    }
+    Ok(result)
+}
+
+/// 在求值环境env中依次对函数体求值, 返回最后一个表达式的值
+fn eval_body(body: &[Value], env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    body.iter().try_fold(Value::NilValue, |_, expr| env.clone().eval(expr.clone()))
+}
+
 /// let 特殊形式
 /// 在当前求值环境中绑定一些临时变量
+/// (let name ((var init) ...) body ...) 为命名 let, 在函数体中可以用name以新的值重新进入循环
 pub fn let_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if args.is_empty() {
+        return Err(ErrorEval::arity("let", args.len()));
+    }
+    if let Value::SymbolValue(name) = &args[0] {
+        return named_let(name, &args[1..], env);
+    }
     let mut params1: Vec<String> = Vec::new();
     let mut params2: Vec<Value> = Vec::new();
-    let bindings: Vec<Value>;
-    match args[0] {
-        Value::PairValue(_, _) => bindings = args[0].to_vector().map_err(|error| ErrorEval {
-            message: format!("{}: Special Form <let>: Fail to convert value to vector\n{}", error.index + 1, error.message),
-            index: error.index + 1
-        })?,
-        _ => return Err(ErrorEval{
-            message: format!("temporary bindings without parentheses: \n (let ((#<binding>)(...)) (#<procedure>)(..) \n      ^                 ^"),
-            index: 0
-        }),
+    for (name, init) in let_bindings("let", &args[0])? {
+        params1.push(name);
+        params2.push(env.clone().eval(init).map_err(|error| error.context("let", "Fail to evaluate a value"))?);
     }
-    for binding in bindings {
-        match binding {
-            Value::PairValue(_, _) => {
-                let binding_vec: Vec<Value> = binding.to_vector().map_err(|error| ErrorEval {
-                    message: format!("{}: Special Form <let>: Fail to convert value to vector\n{}", error.index + 1, error.message),
-                    index: error.index + 1
-                })?;
-                if binding_vec.len() == 2 {
-                    params1.push(binding_vec[0].to_string());
-                    params2.push(env.clone().eval(binding_vec[1].clone()).map_err(|error| ErrorEval {
-                        message: format!("{}: Special Form <let>: Fail to evaluate a value\n{}", error.index + 1, error.message),
-                        index: error.index + 1
-                    })?);
-                }
-                else {
-                    return Err(ErrorEval{
-                        message: format!("{}: Special Form <let>: temporary binding should be a 2-element list", 0),
-                        index: 0
-                    });
-                }
+    let env_derived: Rc<EvalEnv> = env.derive(params1, params2)?.into();
+    eval_body(&args[1..], env_derived)
+}
+
+/// 命名 let 的函数体在尾部位置上的求值结果
+/// Done: 求得的值, Loop: 对循环过程的尾调用, 附带求得的实参
+enum LoopStep {
+    Done(Value),
+    Loop(Vec<Value>),
+}
+
+/// 命名 let
+/// 初始值在外层环境中求值, name绑定到一个只对函数体可见的过程上
+/// 函数体尾部位置上对name的调用不加深调用, 而是以新的实参重新绑定变量后再次求值函数体, 其余位置上的调用仍是普通的递归调用
+fn named_let(name: &str, args: &[Value], env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if args.is_empty() {
+        return Err(ErrorEval::bad_syntax("let", "Missing bindings of named let"));
+    }
+    let mut params: Params = Params::default();
+    let mut inits: Vec<Value> = Vec::new();
+    for (var, init) in let_bindings("let", &args[0])? {
+        params.required.push(var);
+        inits.push(env.clone().eval(init).map_err(|error| error.context("let", "Fail to evaluate a value"))?);
+    }
+    let body: &[Value] = &args[1..];
+    let env_loop: Rc<EvalEnv> = env.derive(Vec::new(), Vec::new())?.into();
+    let procedure: Value = Value::LambdaValue(Box::new(params.clone()), Box::new(body.to_vec()), env_loop.clone(), None);
+    env_loop.symbol_map.borrow_mut().insert(name.to_string(), procedure);
+    let mut args: Vec<Value> = inits;
+    loop {
+        env_loop.tick()?;
+        let env_body: Rc<EvalEnv> = env_loop.clone().bind(&params, args)?;
+        args = match loop_body(body, &env_body, name, &env_loop)? {
+            LoopStep::Done(value) => return Ok(value),
+            LoopStep::Loop(next) => next,
+        };
+    }
+}
+
+/// 依次对命名 let 的函数体求值, 最后一个表达式位于尾部位置
+fn loop_body(body: &[Value], env: &Rc<EvalEnv>, name: &str, env_loop: &Rc<EvalEnv>) -> Result<LoopStep, ErrorEval> {
+    match body.split_last() {
+        None => Ok(LoopStep::Done(Value::NilValue)),
+        Some((last, init)) => {
+            for expr in init {
+                env.clone().eval(expr.clone())?;
+            }
+            loop_tail(last, env, name, env_loop)
+        },
+    }
+}
+
+/// 对命名 let 中尾部位置上的表达式求值
+/// 对循环过程的调用只对实参求值, if, cond, when, unless 与 begin 选中的分支仍在尾部位置, 其余的表达式照常求值
+fn loop_tail(expr: &Value, env: &Rc<EvalEnv>, name: &str, env_loop: &Rc<EvalEnv>) -> Result<LoopStep, ErrorEval> {
+    let done = || env.clone().eval(expr.clone()).map(LoopStep::Done);
+    let items: Vec<Value> = match expr {
+        Value::PairValue(_, _) => match expr.to_vector() {
+            Ok(items) => items,
+            Err(_) => return done(),
+        },
+        _ => return done(),
+    };
+    let head: &String = match &items[0] {
+        Value::SymbolValue(head) => head,
+        _ => return done(),
+    };
+    // 函数体中重新绑定的名字不再指向循环过程, 也不再是特殊形式
+    match env.clone().find_binding(head) {
+        Some(Value::LambdaValue(_, _, closure, _)) if head == name && Rc::ptr_eq(&closure, env_loop) => {
+            let args: Vec<Value> = items[1..].iter().map(|arg| env.clone().eval(arg.clone())).collect::<Result<_, _>>()?;
+            return Ok(LoopStep::Loop(args));
+        },
+        Some(_) => return done(),
+        None if !env.special_forms.contains_key(head) => return done(),
+        None => {},
+    }
+    match (head.as_str(), &items[1..]) {
+        ("if", [condition, consequent, alternative @ ..]) if alternative.len() <= 1 => {
+            let flag: Value = env.clone().eval(condition.clone()).map_err(|error| error.context("if", "Fail to evaluate the condition"))?;
+            match (flag, alternative) {
+                (Value::BooleanValue(false), [alternative]) => loop_tail(alternative, env, name, env_loop),
+                (Value::BooleanValue(false), _) => Ok(LoopStep::Done(Value::NilValue)),
+                _ => loop_tail(consequent, env, name, env_loop),
+            }
+        },
+        ("when", [condition, body @ ..]) => match env.clone().eval(condition.clone()).map_err(|error| error.context("when", "Fail to evaluate condition"))? {
+            Value::BooleanValue(false) => Ok(LoopStep::Done(Value::NilValue)),
+            _ => loop_body(body, env, name, env_loop),
+        },
+        ("unless", [condition, body @ ..]) => match env.clone().eval(condition.clone()).map_err(|error| error.context("unless", "Fail to evaluate condition"))? {
+            Value::BooleanValue(false) => loop_body(body, env, name, env_loop),
+            _ => Ok(LoopStep::Done(Value::NilValue)),
+        },
+        ("begin", body) if !body.is_empty() => loop_body(body, env, name, env_loop),
+        ("cond", clauses) => loop_cond(clauses, env, name, env_loop),
+        _ => done(),
+    }
+}
+
+/// 命名 let 尾部位置上的 cond, 与 cond_form 相同, 但选中的子句中的表达式位于尾部位置
+fn loop_cond(clauses: &[Value], env: &Rc<EvalEnv>, name: &str, env_loop: &Rc<EvalEnv>) -> Result<LoopStep, ErrorEval> {
+    for (index, clause) in clauses.iter().enumerate() {
+        let clause_vec: Vec<Value> = match clause {
+            Value::PairValue(_, _) => clause.to_vector().map_err(|error| error.context("cond", "Fail to convert value to vector"))?,
+            _ => return Err(ErrorEval::arity("cond", clauses.len())),
+        };
+        let flag: Value = env.clone().eval(clause_vec[0].clone()).map_err(|error| error.context("cond", "Fail to evaluate condition"))?;
+        match flag {
+            Value::BooleanValue(false) => continue,
+            Value::SymbolValue(s) if s == "else" && index != clauses.len() - 1 => {
+                return Err(ErrorEval::bad_syntax("cond", "\"else\" must be at the condition position in the last clause"));
+            },
+            flag => return match &clause_vec[1..] {
+                [] => Ok(LoopStep::Done(flag)),
+                exprs @ [Value::SymbolValue(arrow), _] if arrow == "=>" => clause_result("cond", flag, exprs, env.clone()).map(LoopStep::Done),
+                body => loop_body(body, env, name, env_loop),
             },
-            _ => return Err(ErrorEval{
-                message: format!("{}: Special Form <let>: temporary binding should be a 2-element list", 0),
-                index: 0
-            }),
         }
     }
-    let mut results: Vec<Value> = Vec::new();
-    let env_derived: Rc<EvalEnv> = env.derive(params1, params2).into();
-    args[1..].iter().for_each(|arg| results.push(env_derived.clone().eval(arg.clone()).expect("Corruption when evaluating a value in form <let>")));
-    Ok(results.pop().unwrap_or(Value::NilValue))
+    Ok(LoopStep::Done(Value::NilValue))
+}
+
+/// let* 特殊形式
+/// 与 let 类似, 但是依次绑定, 后面的初始值可以引用前面的变量
+pub fn let_star_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if args.is_empty() {
+        return Err(ErrorEval::arity("let*", args.len()));
+    }
+    let mut env_derived: Rc<EvalEnv> = env.derive(Vec::new(), Vec::new())?.into();
+    for (name, init) in let_bindings("let*", &args[0])? {
+        let value: Value = env_derived.clone().eval(init).map_err(|error| error.context("let*", "Fail to evaluate a value"))?;
+        env_derived = env_derived.derive(vec![name], vec![value])?.into();
+    }
+    eval_body(&args[1..], env_derived)
+}
+
+/// letrec 特殊形式
+/// 初始值在已经包含所有变量的新环境中求值, 用于定义相互递归的局部过程
+/// 所有初始值求值之后才进行绑定
+pub fn letrec_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if args.is_empty() {
+        return Err(ErrorEval::arity("letrec", args.len()));
+    }
+    let env_derived: Rc<EvalEnv> = env.derive(Vec::new(), Vec::new())?.into();
+    let mut values: Vec<(String, Value)> = Vec::new();
+    for (name, init) in let_bindings("letrec", &args[0])? {
+        values.push((name, env_derived.clone().eval(init).map_err(|error| error.context("letrec", "Fail to evaluate a value"))?));
+    }
+    env_derived.symbol_map.borrow_mut().extend(values);
+    eval_body(&args[1..], env_derived)
+}
+
+/// letrec* 特殊形式
+/// 与 letrec 类似, 但是每个初始值求值之后立即绑定, 后面的初始值可以使用前面的变量
+/// lambda表达式与 let 的函数体中的内部 define 也是这样的作用域
+pub fn letrec_star_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if args.is_empty() {
+        return Err(ErrorEval::arity("letrec*", args.len()));
+    }
+    let env_derived: Rc<EvalEnv> = env.derive(Vec::new(), Vec::new())?.into();
+    for (name, init) in let_bindings("letrec*", &args[0])? {
+        let value: Value = env_derived.clone().eval(init).map_err(|error| error.context("letrec*", "Fail to evaluate a value"))?;
+        env_derived.symbol_map.borrow_mut().insert(name, value);
+    }
+    eval_body(&args[1..], env_derived)
+}
+
+/// 解析 let-values 的绑定表 ((formals expr) ...), formals 的写法与lambda表达式的参数表相同
+fn values_bindings(form: &str, bindings: &Value) -> Result<Vec<(Params, Value)>, ErrorEval> {
+    let mut result: Vec<(Params, Value)> = Vec::new();
+    for binding in bindings.to_vector().map_err(|error| error.context(form, "Fail to convert value to vector"))? {
+        let binding_vec: Vec<Value> = match binding {
+            Value::PairValue(_, _) => binding.to_vector().map_err(|error| error.context(form, "Fail to convert value to vector"))?,
+            _ => Vec::new(),
+        };
+        if binding_vec.len() != 2 {
+            return Err(ErrorEval::bad_syntax(form, "binding should be a 2-element list (formals expr)"));
+        }
+        result.push((Params::parse(&binding_vec[0])?, binding_vec[1].clone()));
+    }
+    Ok(result)
+}
+
+/// let-values 特殊形式
+/// (let-values (((a b) (values 1 2)) ((c . rest) expr)) body ...)
+/// 所有表达式在外层环境中求值, 其返回的各个值按 formals 绑定
+pub fn let_values_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if args.is_empty() {
+        return Err(ErrorEval::arity("let-values", args.len()));
+    }
+    let mut produced: Vec<(Params, Vec<Value>)> = Vec::new();
+    for (params, expr) in values_bindings("let-values", &args[0])? {
+        let value: Value = env.clone().eval(expr).map_err(|error| error.context("let-values", "Fail to evaluate a value"))?;
+        produced.push((params, spread_values(value)));
+    }
+    let env_derived: Rc<EvalEnv> = env.derive(Vec::new(), Vec::new())?.into();
+    for (params, values) in produced {
+        let frame: Rc<EvalEnv> = env_derived.clone().bind(&params, values).map_err(|error| error.frame("let-values"))?;
+        env_derived.symbol_map.borrow_mut().extend(frame.symbol_map.take());
+    }
+    eval_body(&args[1..], env_derived)
+}
+
+/// let*-values 特殊形式
+/// 与 let-values 类似, 但是依次绑定, 后面的表达式可以引用前面的变量
+pub fn let_star_values_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if args.is_empty() {
+        return Err(ErrorEval::arity("let*-values", args.len()));
+    }
+    let mut env_derived: Rc<EvalEnv> = env.derive(Vec::new(), Vec::new())?.into();
+    for (params, expr) in values_bindings("let*-values", &args[0])? {
+        let value: Value = env_derived.clone().eval(expr).map_err(|error| error.context("let*-values", "Fail to evaluate a value"))?;
+        env_derived = env_derived.bind(&params, spread_values(value)).map_err(|error| error.frame("let*-values"))?;
+    }
+    eval_body(&args[1..], env_derived)
+}
+
+/// define-values 特殊形式
+/// (define-values (a b . rest) expr) 将表达式返回的各个值绑定到当前求值环境
+pub fn define_values_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if args.len() != 2 {
+        return Err(ErrorEval::arity("define-values", args.len()));
+    }
+    let params: Params = Params::parse(&args[0])?;
+    let value: Value = env.clone().eval(args[1].clone()).map_err(|error| error.context("define-values", "Fail to evaluate a value"))?;
+    let frame: Rc<EvalEnv> = env.clone().bind(&params, spread_values(value)).map_err(|error| error.frame("define-values"))?;
+    env.symbol_map.borrow_mut().extend(frame.symbol_map.take());
+    Ok(Value::NilValue)
 }
 
 /// quasiquote 特殊形式
 /// 与quote类似, 不过由,逗号表达式(unquote)引导的表达式会被求值
 pub fn quasiquote_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
     let mut results: Vec<Value> = Vec::new();
-    let arg_vec: Vec<Value> = args[0].to_vector().map_err(|error| ErrorEval{
-        message: format!("{}: Special Form <quasiquote>: Fail to evaluate a value\n{}", error.index + 1, error.message),
-        index: error.index + 1
-    })?;
+    let arg_vec: Vec<Value> = args[0].to_vector().map_err(|error| error.context("quasiquote", "Fail to evaluate a value"))?;
     for arg in arg_vec {
         match arg.clone() {
             Value::PairValue(car, cdr) => {
                 match *car {
                     Value::SymbolValue(s) if s == "unquote".to_string() => {
-                        results.push(unquote_form(cdr.to_vector().map_err(|error| ErrorEval{
-                            message: format!("{}: Special Form <quasiquote>: Fail to convert a value to vector\n{}", error.index + 1, error.message),
-                            index: error.index + 1
-                        })?, env.clone())?);
+                        results.push(unquote_form(cdr.to_vector().map_err(|error| error.context("quasiquote", "Fail to convert a value to vector"))?, env.clone())?);
                     },
-                    Value::SymbolValue(s) if s == "quasiquote".to_string() => return Err(ErrorEval {
-                        message: format!("{}: Special Form <quasiquote>: Calling quasiquote inside quasiquote is an undefined behavior", 0),
-                        index: 0
-                    }),
+                    Value::SymbolValue(s) if s == "quasiquote".to_string() => return Err(ErrorEval::bad_syntax("quasiquote", "Calling quasiquote inside quasiquote is an undefined behavior")),
                     _ => results.push(arg), 
                 }
             },
             _ => results.push(arg),
         }
     }
-    list(results, env).map_err(|error| ErrorEval {
-        message: format!("{}: Special Form <quasiquote>: Fail to pack the result\n{}", error.index + 1, error.message),
-        index: error.index + 1
-    })
+    list(results, env).map_err(|error| error.context("quasiquote", "Fail to pack the result"))
 }
 
 /// unquote特殊形式
 /// 用于在quasiquote中豁免表达式的
 pub fn unquote_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
-    if args.len() < 1 {
-        return Err(ErrorEval{
-            message: format!("{}: Special Form <unquote>: Missing argument", 0),
-            index: 0
-        });
-    }
-    else if args.len() > 1{
-        return Err(ErrorEval {
-            message: format!("{}: Special Form <unquote>: Too many argument", 0),
-            index: 0
-        });
+    if args.len() != 1 {
+        return Err(ErrorEval::arity("unquote", args.len()));
     }
     else {
-        env.eval(args[0].clone()).map_err(|error| ErrorEval {
-            message: format!("{}: Special Form <let>: Fail to convert value to vector\n{}", error.index + 1, error.message),
-            index: error.index + 1
-        })
+        env.eval(args[0].clone()).map_err(|error| error.context("unquote", "Fail to evaluate a value"))
     }
-}
\ No newline at end of file
+}
+/// trace特殊形式
+/// (trace f ...)
+/// 把名为f的过程换成包装过程, 每次调用时按调用深度缩进, 打印参数与返回值
+/// 包装过程仍是普通的过程值, 无论由求值器还是 map 等高阶内置过程调用都会打印
+pub fn trace_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if args.is_empty() {
+        return Err(ErrorEval::arity("trace", args.len()));
+    }
+    for arg in args.iter() {
+        let name: &String = match arg {
+            Value::SymbolValue(name) => name,
+            _ => return Err(ErrorEval::type_error("trace", "symbol", arg)),
+        };
+        if env.debugger.is_traced(name) {
+            continue;
+        }
+        let original: Value = env.clone().eval(arg.clone()).map_err(|error| error.context("trace", "Fail to evaluate a value"))?;
+        if !original.is_procedure() {
+            return Err(ErrorEval::type_error("trace", "procedure", &original));
+        }
+        let wrapper: BuiltinFn = {
+            let name: String = name.clone();
+            let original: Value = original.clone();
+            Rc::new(move |args: Vec<Value>, env: Rc<EvalEnv>| -> Result<Value, ErrorEval> {
+                env.debugger.trace_enter(&name, &args);
+                let result: Result<Value, ErrorEval> = env.apply_procedure(original.clone(), args);
+                env.debugger.trace_leave(&result);
+                result
+            })
+        };
+        // 用户定义的过程在原来的帧中替换, 内置过程在全局环境中遮盖
+        let (frame, restore): (Rc<EvalEnv>, Option<Value>) = match binding_frame(&env, name) {
+            Some(frame) => (frame, Some(original)),
+            None => (global_frame(&env), None),
+        };
+        frame.symbol_map.borrow_mut().insert(name.clone(), Value::ProcedureValue(Box::new(wrapper.clone())));
+        env.debugger.add_traced(name, Traced { wrapper, restore });
+    }
+    Ok(Value::NilValue)
+}
+
+/// untrace特殊形式
+/// (untrace f ...)
+/// 恢复被 trace 的过程, 之后重新定义过的名字保持新的定义
+pub fn untrace_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if args.is_empty() {
+        return Err(ErrorEval::arity("untrace", args.len()));
+    }
+    for arg in args.iter() {
+        let name: &String = match arg {
+            Value::SymbolValue(name) => name,
+            _ => return Err(ErrorEval::type_error("untrace", "symbol", arg)),
+        };
+        let traced: Traced = match env.debugger.remove_traced(name) {
+            Some(traced) => traced,
+            None => continue,
+        };
+        let frame: Rc<EvalEnv> = match binding_frame(&env, name) {
+            Some(frame) => frame,
+            None => continue,
+        };
+        let still_wrapped: bool = matches!(frame.symbol_map.borrow().get(name), Some(Value::ProcedureValue(f)) if Rc::ptr_eq(f, &traced.wrapper));
+        if still_wrapped {
+            match traced.restore {
+                Some(original) => { frame.symbol_map.borrow_mut().insert(name.clone(), original); },
+                None => { frame.symbol_map.borrow_mut().remove(name); },
+            }
+        }
+    }
+    Ok(Value::NilValue)
+}
+
+/// 找到绑定了name的帧
+fn binding_frame(env: &Rc<EvalEnv>, name: &String) -> Option<Rc<EvalEnv>> {
+    let mut current: Rc<EvalEnv> = env.clone();
+    loop {
+        if current.symbol_map.borrow().contains_key(name) {
+            return Some(current);
+        }
+        current = current.parent.clone()?;
+    }
+}
+
+/// 全局环境, 即最外层的帧
+fn global_frame(env: &Rc<EvalEnv>) -> Rc<EvalEnv> {
+    let mut current: Rc<EvalEnv> = env.clone();
+    while let Some(parent) = current.parent.clone() {
+        current = parent;
+    }
+    current
+}
@@ -0,0 +1,274 @@
+/// 定义了惰性流, 接口参照 SRFI-41
+/// 流是空表 () 或者一个promise, 后者求值得到空表或者对子 (car的promise . 剩下的流)
+/// 流的元素只在需要时求值并且只求值一次, 线性遍历时已经走过的部分可以被回收, 因此可以表示很长甚至无穷的序列
+use std::cell::RefCell;
+use std::rc::Rc;
+use crate::builtins::{list, procedure_param, Promise, Thunk};
+use crate::error::ErrorEval;
+use crate::eval_env::EvalEnv;
+use crate::value::{Value, is_integer};
+
+/// 对流求值一步
+/// 空流返回None, 否则返回car的promise与剩下的流
+fn force_stream(procedure: &str, stream: &Value) -> Result<Option<(Value, Value)>, ErrorEval> {
+    let forced: Value = match stream {
+        Value::NilValue => return Ok(None),
+        _ => match stream.downcast_ref::<Promise>() {
+            Some(promise) => promise.force()?,
+            None => return Err(ErrorEval::type_error(procedure, "stream", stream)),
+        },
+    };
+    match forced {
+        Value::NilValue => Ok(None),
+        Value::PairValue(car, cdr) => Ok(Some((*car, *cdr))),
+        _ => Err(ErrorEval::type_error(procedure, "stream", stream)),
+    }
+}
+
+/// 对car的promise求值
+fn force_car(car: &Value) -> Result<Value, ErrorEval> {
+    match car.downcast_ref::<Promise>() {
+        Some(promise) => promise.force(),
+        None => Err(ErrorEval::type_error("stream-car", "promise", car)),
+    }
+}
+
+/// 由已经求得的car与剩下的流构造流的对子
+fn stream_pair(car: Value, cdr: Value) -> Value {
+    Value::PairValue(Box::new(Promise::done(car)), Box::new(cdr))
+}
+
+/// 第一次求值时才调用thunk得到流的下一个对子
+fn lazy_stream(thunk: impl Fn() -> Result<Value, ErrorEval> + 'static) -> Value {
+    Promise::lazy(Rc::new(thunk) as Thunk)
+}
+
+/// 读取非负整数参数
+fn count_param(procedure: &str, param: &Value) -> Result<usize, ErrorEval> {
+    match param {
+        Value::NumericValue(n) if is_integer(n) && *n >= 0.0 => Ok(*n as usize),
+        _ => Err(ErrorEval::type_error(procedure, "non-negative integer", param)),
+    }
+}
+
+/// stream-cons 特殊形式
+/// (stream-cons a b) 构造一个流, a与b都在需要时才求值, b的值应当是一个流
+pub fn stream_cons_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if args.len() != 2 {
+        return Err(ErrorEval::arity("stream-cons", args.len()));
+    }
+    let car: Value = Promise::delayed(args[0].clone(), env.clone(), false);
+    let cdr: Value = Promise::delayed(args[1].clone(), env, true);
+    Ok(Promise::done(Value::PairValue(Box::new(car), Box::new(cdr))))
+}
+
+/// stream-null? 内置过程
+pub fn stream_null_q(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("stream-null?", params.len()));
+    }
+    Ok(Value::BooleanValue(force_stream("stream-null?", &params[0])?.is_none()))
+}
+
+/// stream-pair? 内置过程
+pub fn stream_pair_q(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("stream-pair?", params.len()));
+    }
+    match params[0] {
+        Value::NilValue => Ok(Value::BooleanValue(false)),
+        _ if params[0].downcast_ref::<Promise>().is_none() => Ok(Value::BooleanValue(false)),
+        _ => Ok(Value::BooleanValue(force_stream("stream-pair?", &params[0])?.is_some())),
+    }
+}
+
+/// stream-car 内置过程
+pub fn stream_car(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("stream-car", params.len()));
+    }
+    match force_stream("stream-car", &params[0])? {
+        Some((car, _)) => force_car(&car),
+        None => Err(ErrorEval::type_error("stream-car", "non-empty stream", &params[0])),
+    }
+}
+
+/// stream-cdr 内置过程
+pub fn stream_cdr(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("stream-cdr", params.len()));
+    }
+    match force_stream("stream-cdr", &params[0])? {
+        Some((_, cdr)) => Ok(cdr),
+        None => Err(ErrorEval::type_error("stream-cdr", "non-empty stream", &params[0])),
+    }
+}
+
+/// (stream-take n stream) 由流的前n个元素组成的流
+fn take(count: usize, stream: Value) -> Value {
+    lazy_stream(move || {
+        if count == 0 {
+            return Ok(Value::NilValue);
+        }
+        match force_stream("stream-take", &stream)? {
+            Some((car, cdr)) => Ok(Value::PairValue(Box::new(car), Box::new(take(count - 1, cdr)))),
+            None => Ok(Value::NilValue),
+        }
+    })
+}
+
+/// stream-take 内置过程
+pub fn stream_take(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("stream-take", params.len()));
+    }
+    let count: usize = count_param("stream-take", &params[0])?;
+    Ok(take(count, params[1].clone()))
+}
+
+/// (stream-map proc stream ...) 将过程作用于各个流中对应位置的元素, 任一流结束时结束
+fn map(procedure: Value, streams: Vec<Value>, env: Rc<EvalEnv>) -> Value {
+    lazy_stream(move || {
+        let mut cars: Vec<Value> = Vec::new();
+        let mut cdrs: Vec<Value> = Vec::new();
+        for stream in streams.iter() {
+            match force_stream("stream-map", stream)? {
+                Some((car, cdr)) => {
+                    cars.push(car);
+                    cdrs.push(cdr);
+                },
+                None => return Ok(Value::NilValue),
+            }
+        }
+        let (procedure_car, env_car) = (procedure.clone(), env.clone());
+        let car: Value = Promise::lazy(Rc::new(move || {
+            let args: Vec<Value> = cars.iter().map(force_car).collect::<Result<_, _>>()?;
+            env_car.apply_procedure(procedure_car.clone(), args)
+        }) as Thunk);
+        Ok(Value::PairValue(Box::new(car), Box::new(map(procedure.clone(), cdrs, env.clone()))))
+    })
+}
+
+/// stream-map 内置过程
+pub fn stream_map(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() < 2 {
+        return Err(ErrorEval::arity("stream-map", params.len()));
+    }
+    let procedure: Value = procedure_param("stream-map", &params[0])?;
+    Ok(map(procedure, params[1..].to_vec(), env))
+}
+
+/// (stream-filter pred stream) 由使谓词不为 #f 的元素组成的流
+/// 第一次求值时取走流的开头, 跳过的元素随即可以被回收, 出错时保留已经走到的位置
+fn filter(predicate: Value, stream: Value, env: Rc<EvalEnv>) -> Value {
+    let stream: RefCell<Option<Value>> = RefCell::new(Some(stream));
+    lazy_stream(move || {
+        let mut current: Value = match stream.take() {
+            Some(current) => current,
+            None => return Err(ErrorEval::runtime("stream-filter", "Stream forced again while being filtered")),
+        };
+        filter_from(&predicate, &mut current, &env).inspect_err(|_| *stream.borrow_mut() = Some(current))
+    })
+}
+
+/// 从current开始找到第一个使谓词不为 #f 的元素, 返回流的下一个对子
+/// 跳过不满足谓词的元素时就地前移current, 不加深调用
+fn filter_from(predicate: &Value, current: &mut Value, env: &Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    loop {
+        let (car, cdr) = match force_stream("stream-filter", current)? {
+            Some(pair) => pair,
+            None => return Ok(Value::NilValue),
+        };
+        let value: Value = force_car(&car)?;
+        match env.apply_procedure(predicate.clone(), vec![value])? {
+            Value::BooleanValue(false) => *current = cdr,
+            _ => return Ok(Value::PairValue(Box::new(car), Box::new(filter(predicate.clone(), cdr, env.clone())))),
+        }
+    }
+}
+
+/// stream-filter 内置过程
+pub fn stream_filter(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 2 {
+        return Err(ErrorEval::arity("stream-filter", params.len()));
+    }
+    let predicate: Value = procedure_param("stream-filter", &params[0])?;
+    Ok(filter(predicate, params[1].clone(), env))
+}
+
+/// stream-fold 内置过程
+/// (stream-fold proc base stream) 从左向右归约, 每一步以 (proc 累积值 元素) 的值作为新的累积值
+pub fn stream_fold(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 3 {
+        return Err(ErrorEval::arity("stream-fold", params.len()));
+    }
+    let mut params = params.into_iter();
+    let procedure: Value = procedure_param("stream-fold", &params.next().unwrap())?;
+    let mut acc: Value = params.next().unwrap();
+    let mut current: Value = params.next().unwrap();
+    while let Some((car, cdr)) = force_stream("stream-fold", &current)? {
+        acc = env.apply_procedure(procedure.clone(), vec![acc, force_car(&car)?])?;
+        current = cdr;
+    }
+    Ok(acc)
+}
+
+/// stream->list 内置过程
+/// (stream->list [n] stream) 将流(的前n个元素)转化为列表
+pub fn stream_to_list(params: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    let (limit, mut current) = match params.as_slice() {
+        [stream] => (None, stream.clone()),
+        [count, stream] => (Some(count_param("stream->list", count)?), stream.clone()),
+        _ => return Err(ErrorEval::arity("stream->list", params.len())),
+    };
+    let mut values: Vec<Value> = Vec::new();
+    while limit.is_none_or(|limit| values.len() < limit) {
+        env.tick()?;
+        match force_stream("stream->list", &current)? {
+            Some((car, cdr)) => {
+                values.push(force_car(&car)?);
+                current = cdr;
+            },
+            None => break,
+        }
+    }
+    list(values, env)
+}
+
+/// list->stream 内置过程
+pub fn list_to_stream(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() != 1 {
+        return Err(ErrorEval::arity("list->stream", params.len()));
+    }
+    let values: Vec<Value> = params[0].to_vector().map_err(|_| ErrorEval::type_error("list->stream", "list", &params[0]))?;
+    Ok(values.into_iter().rev().fold(Value::NilValue, |rest, value| Promise::done(stream_pair(value, rest))))
+}
+
+/// (stream-range first past step) 从first开始每次增加step, 到达past之前结束
+fn range(first: f64, past: f64, step: f64) -> Value {
+    lazy_stream(move || {
+        if (step > 0.0 && first >= past) || (step < 0.0 && first <= past) {
+            return Ok(Value::NilValue);
+        }
+        Ok(stream_pair(Value::NumericValue(first), range(first + step, past, step)))
+    })
+}
+
+/// stream-range 内置过程
+/// (stream-range first past [step]) 步长缺省时为1, first大于past时为-1
+pub fn stream_range(params: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if params.len() < 2 || params.len() > 3 {
+        return Err(ErrorEval::arity("stream-range", params.len()));
+    }
+    let numbers: Vec<f64> = params.iter().map(|param| match param {
+        Value::NumericValue(n) => Ok(*n),
+        _ => Err(ErrorEval::type_error("stream-range", "number", param)),
+    }).collect::<Result<_, _>>()?;
+    let step: f64 = match numbers.get(2) {
+        Some(step) if *step == 0.0 => return Err(ErrorEval::runtime("stream-range", "Step should not be zero")),
+        Some(step) => *step,
+        None if numbers[0] > numbers[1] => -1.0,
+        None => 1.0,
+    };
+    Ok(range(numbers[0], numbers[1], step))
+}
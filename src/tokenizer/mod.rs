@@ -1,4 +1,6 @@
 pub mod token;
 pub mod tokenize;
+pub mod reader;
 pub use self::tokenize::Tokenizer;
-pub use self::token::Token;
\ No newline at end of file
+pub use self::token::Token;
+pub use self::reader::{IncrementalReader, Datum};
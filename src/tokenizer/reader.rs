@@ -0,0 +1,263 @@
+/// 定义了增量读取机
+/// 文件模式与交互模式共用, 负责从逐块输入的文本中切分出完整的表达式
+use crate::error::ErrorRead;
+
+/// 一个完整的顶层表达式, 附带其在输入中的起始位置(从1开始计数)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Datum {
+    pub text: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// 扫描结果
+/// Done: 完整表达式在该下标之前结束
+/// More: 需要更多输入
+/// Fail: 出现了多余的右括号, 下标为该右括号所在位置
+enum Scan {
+    Done(usize),
+    More,
+    Fail(usize),
+}
+
+/// 增量读取机
+/// buffer: 尚未被切分出去的输入
+/// line, column: buffer开头在整个输入中的位置
+/// open_parens: 最近一次扫描中尚未闭合的左括号下标
+/// consumed_any: 是否已经读取过输入, 用于识别第一行的 #!
+/// finished: 输入是否已经结束, 结束后位于末尾的原子也视为完整
+#[derive(Debug)]
+pub struct IncrementalReader {
+    buffer: Vec<char>,
+    line: usize,
+    column: usize,
+    open_parens: Vec<usize>,
+    consumed_any: bool,
+    finished: bool,
+}
+
+/// 原子的结束符
+const DELIMITERS: [char; 11] = ['(', ')', '"', ';', '\'', '`', ',', ' ', '\n', '\r', '\t'];
+
+impl IncrementalReader {
+    /// 新建增量读取机
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            line: 1,
+            column: 1,
+            open_parens: Vec::new(),
+            consumed_any: false,
+            finished: false,
+        }
+    }
+
+    /// 送入一段输入
+    pub fn feed(&mut self, chunk: &str) {
+        self.buffer.extend(chunk.chars());
+    }
+
+    /// 标记输入已经结束
+    /// 之后位于末尾、没有分隔符的原子与行注释也会被正常处理
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    /// 取出下一个完整的表达式
+    /// 输入不足以构成完整表达式时返回 Ok(None), 已读入的部分会被保留
+    pub fn next_datum(&mut self) -> Result<Option<Datum>, ErrorRead> {
+        self.open_parens.clear();
+        let start: usize = match self.skip_atmosphere(0) {
+            Scan::Done(i) => i,
+            Scan::More => return Ok(None),
+            Scan::Fail(i) => { self.consume(i + 1); return Err(ErrorRead::SyntaxFailure); },
+        };
+        // 注释与空白已经确定不会再被用到, 直接丢弃
+        self.consume(start);
+        if self.buffer.is_empty() {
+            return Ok(None);
+        }
+        match self.scan_datum(0) {
+            Scan::Done(end) => {
+                let line: usize = self.line;
+                let column: usize = self.column;
+                let text: String = self.buffer[..end].iter().collect();
+                self.consume(end);
+                Ok(Some(Datum { text, line, column }))
+            },
+            Scan::More => Ok(None),
+            Scan::Fail(i) => { self.consume(i + 1); Err(ErrorRead::SyntaxFailure) },
+        }
+    }
+
+    /// 是否存在尚未构成完整表达式的输入
+    pub fn is_pending(&self) -> bool {
+        !self.buffer.iter().all(|c| c.is_whitespace())
+    }
+
+    /// 最内层未闭合的左括号之后一格所在的列, 用于交互模式的自动缩进
+    pub fn indent(&self) -> usize {
+        match self.open_parens.last() {
+            None => 0,
+            Some(&index) => {
+                let line_start: usize = self.buffer[..index].iter().rposition(|&c| c == '\n').map(|i| i + 1).unwrap_or(0);
+                let offset: usize = if line_start == 0 { self.column - 1 } else { 0 };
+                offset + index - line_start + 1
+            },
+        }
+    }
+
+    /// 清空读取机中尚未处理的输入
+    pub fn clear(&mut self) {
+        let rest: usize = self.buffer.len();
+        self.consume(rest);
+        self.open_parens.clear();
+    }
+
+    /// 从buffer开头移除n个字符, 同时更新位置信息
+    fn consume(&mut self, n: usize) {
+        for c in self.buffer.drain(..n) {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            }
+            else {
+                self.column += 1;
+            }
+        }
+        if n > 0 {
+            self.consumed_any = true;
+        }
+    }
+
+    /// 跳过空白与注释, 返回下一个有意义字符的下标
+    fn skip_atmosphere(&mut self, mut i: usize) -> Scan {
+        loop {
+            if i >= self.buffer.len() {
+                return Scan::Done(i);
+            }
+            let c: char = self.buffer[i];
+            let next: Option<char> = self.buffer.get(i + 1).copied();
+            match (c, next) {
+                (' ' | '\n' | '\r' | '\t', _) => i += 1,
+                (';', _) => {
+                    match self.buffer[i..].iter().position(|&c| c == '\n') {
+                        Some(offset) => i += offset + 1,
+                        None if self.finished => i = self.buffer.len(),
+                        None => return Scan::More,
+                    }
+                },
+                ('#', None) => return Scan::More,
+                ('#', Some('|')) => {
+                    match self.skip_block_comment(i + 2) {
+                        Scan::Done(end) => i = end,
+                        other => return other,
+                    }
+                },
+                ('#', Some(';')) => {
+                    let start: usize = match self.skip_atmosphere(i + 2) {
+                        Scan::Done(start) => start,
+                        other => return other,
+                    };
+                    if start >= self.buffer.len() {
+                        return Scan::More;
+                    }
+                    match self.scan_datum(start) {
+                        Scan::Done(end) => i = end,
+                        other => return other,
+                    }
+                },
+                ('#', Some('!')) if i == 0 && !self.consumed_any && matches!(self.buffer.get(2), Some('/') | Some(' ')) => {
+                    match self.buffer.iter().position(|&c| c == '\n') {
+                        Some(offset) => i = offset + 1,
+                        None if self.finished => i = self.buffer.len(),
+                        None => return Scan::More,
+                    }
+                },
+                _ => return Scan::Done(i),
+            }
+        }
+    }
+
+    /// 跳过块注释, i 位于开头的 #| 之后
+    fn skip_block_comment(&self, mut i: usize) -> Scan {
+        let mut depth: usize = 1;
+        while i < self.buffer.len() {
+            match (self.buffer[i], self.buffer.get(i + 1).copied()) {
+                ('#', Some('|')) => { depth += 1; i += 2; },
+                ('|', Some('#')) => {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        return Scan::Done(i);
+                    }
+                },
+                _ => i += 1,
+            }
+        }
+        Scan::More
+    }
+
+    /// 扫描一个完整的表达式, i 位于表达式的第一个字符
+    fn scan_datum(&mut self, i: usize) -> Scan {
+        match self.buffer[i] {
+            ')' => Scan::Fail(i),
+            '(' => {
+                self.open_parens.push(i);
+                let mut j: usize = i + 1;
+                loop {
+                    j = match self.skip_atmosphere(j) {
+                        Scan::Done(j) => j,
+                        other => return other,
+                    };
+                    if j >= self.buffer.len() {
+                        return Scan::More;
+                    }
+                    if self.buffer[j] == ')' {
+                        self.open_parens.pop();
+                        return Scan::Done(j + 1);
+                    }
+                    j = match self.scan_datum(j) {
+                        Scan::Done(j) => j,
+                        other => return other,
+                    };
+                }
+            },
+            '\'' | '`' | ',' => {
+                let start: usize = match self.skip_atmosphere(i + 1) {
+                    Scan::Done(start) => start,
+                    other => return other,
+                };
+                if start >= self.buffer.len() {
+                    return Scan::More;
+                }
+                self.scan_datum(start)
+            },
+            '"' => {
+                let mut j: usize = i + 1;
+                while j < self.buffer.len() {
+                    match self.buffer[j] {
+                        '"' => return Scan::Done(j + 1),
+                        '\\' => j += 2,
+                        _ => j += 1,
+                    }
+                }
+                Scan::More
+            },
+            _ => {
+                match self.buffer[i..].iter().position(|c| DELIMITERS.contains(c)) {
+                    Some(offset) => Scan::Done(i + offset),
+                    None if self.finished => Scan::Done(self.buffer.len()),
+                    // 原子可能在下一段输入中继续
+                    None => Scan::More,
+                }
+            },
+        }
+    }
+}
+
+impl Default for IncrementalReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,9 +1,10 @@
 /// 定义了Tokenize机以及Tokenize的过程
 
 use crate::tokenizer::token::Token;
+use crate::error::ErrorRead;
 
 /// Token结束符
-pub const TOKEN_END: [char; 6] = ['(', ')', '\'', '`', ',', '"'];
+pub const TOKEN_END: [char; 7] = ['(', ')', '\'', '`', ',', '"', ';'];
 
 /// 空字符
 pub const TOKEN_SPACE: [char; 4] = [' ','\n','\r','\t'];
@@ -21,8 +22,8 @@ impl Tokenizer {
         Self { content_vec, pos: 0}
     }
 
-    /// 获取下一个token
-    fn next_token(&mut self) -> Option<Token> {
+    /// 获取下一个token, 文本不完整或格式不对时返回 SyntaxFailure
+    fn next_token(&mut self) -> Result<Option<Token>, ErrorRead> {
         while self.pos < self.content_vec.len() {
             let c = self.content_vec[self.pos];
             match c {
@@ -32,16 +33,28 @@ impl Tokenizer {
                     }
                 },
                 ' '|'\n'|'\r'|'\t' => {self.pos += 1;}
-                '(' => { self.pos += 1; return Some(Token::ParL); }
-                ')' => { self.pos += 1; return Some(Token::ParR); }
-                '\'' => { self.pos += 1; return Some(Token::Quote);}
-                '`' => { self.pos += 1; return Some(Token::QuasiQuote);}
+                '(' => { self.pos += 1; return Ok(Some(Token::ParL)); }
+                ')' => { self.pos += 1; return Ok(Some(Token::ParR)); }
+                '\'' => { self.pos += 1; return Ok(Some(Token::Quote));}
+                '`' => { self.pos += 1; return Ok(Some(Token::QuasiQuote));}
+                ',' => { self.pos += 1; return Ok(Some(Token::Unquote));}
                 '#' => {
-                    self.pos += 1;
-                    match self.content_vec[self.pos] {
-                        't' => { self.pos += 1; return Some(Token::Boolean(true)) },
-                        'f' => { self.pos += 1; return Some(Token::Boolean(false))},
-                        _ => panic!("Unexpected end of literal string"),
+                    if self.pos + 1 >= self.content_vec.len() {
+                        return Err(ErrorRead::SyntaxFailure);
+                    }
+                    match self.content_vec[self.pos + 1] {
+                        't' => { self.pos += 2; return Ok(Some(Token::Boolean(true))) },
+                        'f' => { self.pos += 2; return Ok(Some(Token::Boolean(false)))},
+                        '|' => { self.pos += 2; self.skip_block_comment()?; },
+                        ';' => { self.pos += 2; self.skip_datum()?; },
+                        '!' if self.pos == 0 && matches!(self.content_vec.get(2), Some('/') | Some(' ')) => {
+                            while self.pos < self.content_vec.len() && self.content_vec[self.pos] != '\n' {
+                                self.pos += 1;
+                            }
+                        },
+                        // #!optional 之类的参数表标记与 #:name 关键字都读作标识符
+                        '!' | ':' => return Ok(Some(self.read_atom())),
+                        _ => return Err(ErrorRead::SyntaxFailure),
                     }
                 },
                 '"' => {
@@ -51,11 +64,11 @@ impl Tokenizer {
                         match self.content_vec[self.pos] {
                             '"' => {
                                 self.pos += 1;
-                                return Some(Token::String(string));
+                                return Ok(Some(Token::String(string)));
                             },
                             '\\' => {
                                 if self.pos + 1 >= self.content_vec.len() {
-                                    panic!("Unexpected end of string literal.");
+                                    return Err(ErrorRead::SyntaxFailure);
                                 }
                                 let c: char = self.content_vec[self.pos + 1];
                                 if c == 'n' {
@@ -72,42 +85,85 @@ impl Tokenizer {
                             },
                         }
                     }
-                    panic!("Unexpected end of string literal.");
+                    return Err(ErrorRead::SyntaxFailure);
                 },
-                _ => {
-                    let mut text: String = String::new();
-                    let first_char:char = self.content_vec[self.pos];
-                    while self.pos < self.content_vec.len() && !TOKEN_SPACE.contains(&self.content_vec[self.pos]) && !TOKEN_END.contains(&self.content_vec[self.pos]) {
-                        text.push(self.content_vec[self.pos]);
-                        self.pos += 1;
-                    }
-                    if text == String::from('.') {
-                        return Some(Token::Dot);
+                _ => return Ok(Some(self.read_atom())),
+            }
+        }
+        Ok(None)
+    }
+
+    /// 读取一个数字或标识符, 直到空字符或Token结束符
+    fn read_atom(&mut self) -> Token {
+        let mut text: String = String::new();
+        let first_char:char = self.content_vec[self.pos];
+        while self.pos < self.content_vec.len() && !TOKEN_SPACE.contains(&self.content_vec[self.pos]) && !TOKEN_END.contains(&self.content_vec[self.pos]) {
+            text.push(self.content_vec[self.pos]);
+            self.pos += 1;
+        }
+        if text == String::from('.') {
+            return Token::Dot;
+        }
+        if first_char.is_digit(10) || first_char == '+' || first_char == '-' || first_char == '.' {
+            match text.parse::<f64>() {
+                Ok(n) => return Token::Numeric(n),
+                Err(_e) => {},
+            }
+        }
+        Token::Identifier(text)
+    }
+
+    /// 跳过块注释 #| ... |#
+    /// 块注释允许嵌套, 调用时已经越过了开头的 #|
+    fn skip_block_comment(&mut self) -> Result<(), ErrorRead> {
+        let mut depth: usize = 1;
+        while self.pos < self.content_vec.len() {
+            let c = self.content_vec[self.pos];
+            let next = self.content_vec.get(self.pos + 1).copied();
+            match (c, next) {
+                ('#', Some('|')) => { depth += 1; self.pos += 2; },
+                ('|', Some('#')) => {
+                    depth -= 1;
+                    self.pos += 2;
+                    if depth == 0 {
+                        return Ok(());
                     }
-                    if first_char.is_digit(10) || first_char == '+' || first_char == '-' || first_char == '.' {
-                        match text.parse::<f64>() {
-                            Ok(n) => return Some(Token::Numeric(n)),
-                            Err(_e) => {},
-                        }
+                },
+                _ => { self.pos += 1; },
+            }
+        }
+        Err(ErrorRead::SyntaxFailure)
+    }
+
+    /// 跳过数据注释 #; 之后的一个完整表达式, 后面没有表达式时返回 SyntaxFailure
+    fn skip_datum(&mut self) -> Result<(), ErrorRead> {
+        let mut depth: usize = 0;
+        loop {
+            match self.next_token()? {
+                None => return Err(ErrorRead::SyntaxFailure),
+                Some(Token::ParL) => depth += 1,
+                Some(Token::ParR) => {
+                    if depth == 0 {
+                        return Err(ErrorRead::SyntaxFailure);
                     }
-                    return Some(Token::Identifier(text));
+                    depth -= 1;
                 },
+                // 引号后面还跟着一个表达式, 需要一并跳过
+                Some(Token::Quote) | Some(Token::QuasiQuote) | Some(Token::Unquote) => continue,
+                Some(_) => {},
+            }
+            if depth == 0 {
+                return Ok(());
             }
         }
-        None
     }
 
     /// 将整个传入的文本进行Tokenize
-    pub fn tokenize(&mut self) -> Vec<Token> {
+    pub fn tokenize(&mut self) -> Result<Vec<Token>, ErrorRead> {
         let mut v: Vec<Token> = Vec::new();
-        loop {
-            let b = self.next_token();
-            if b.is_none() {
-                break;
-            } else {
-                v.push(b.unwrap());
-            }
+        while let Some(token) = self.next_token()? {
+            v.push(token);
         }
-        v
+        Ok(v)
     }
 }
\ No newline at end of file
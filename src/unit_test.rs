@@ -0,0 +1,227 @@
+/// 定义了单元测试库, 接口参照 SRFI-64
+/// 测试结果由测试运行器记录, 测试运行器由求值环境及其派生出的所有环境共享
+/// 不通过的测试立即打印, 每个 (test-begin name) 对应的 (test-end) 打印该组的汇总
+/// minilisp test dir 在dir下查找 *-test.scm 逐个运行, 见 command_line
+use std::cell::{Cell, RefCell};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use crate::builtins::equal_q;
+use crate::error::ErrorEval;
+use crate::eval_env::EvalEnv;
+use crate::output::Output;
+use crate::value::Value;
+
+/// 测试运行器
+/// groups: 尚未结束的测试组, 依次为组名以及进入该组时的通过数与失败数
+/// passed: 通过的测试数, failed: 不通过的测试数
+#[derive(Default)]
+pub struct TestRunner {
+    groups: RefCell<Vec<(String, usize, usize)>>,
+    passed: Cell<usize>,
+    failed: Cell<usize>,
+}
+
+impl TestRunner {
+    /// 新建测试运行器
+    pub fn new() -> Self {
+        Self { groups: RefCell::new(Vec::new()), passed: Cell::new(0), failed: Cell::new(0) }
+    }
+
+    /// 通过的测试数
+    pub fn passed(&self) -> usize {
+        self.passed.get()
+    }
+
+    /// 不通过的测试数
+    pub fn failed(&self) -> usize {
+        self.failed.get()
+    }
+
+    /// 尚未结束的测试组
+    pub fn open_groups(&self) -> Vec<String> {
+        self.groups.borrow().iter().map(|(name, _, _)| name.clone()).collect()
+    }
+
+    /// 记录一个测试的结果, 不通过时向output打印测试名与原因
+    fn report(&self, output: &Output, name: &str, result: Result<(), String>) {
+        match result {
+            Ok(()) => self.passed.set(self.passed.get() + 1),
+            Err(reason) => {
+                self.failed.set(self.failed.get() + 1);
+                let group: String = self.open_groups().join(" / ");
+                match group.is_empty() {
+                    true => output.writeln(&format!("FAIL {}: {}", name, reason)),
+                    false => output.writeln(&format!("FAIL {} / {}: {}", group, name, reason)),
+                }
+            },
+        }
+    }
+}
+
+/// 测试名参数, 必须是字符串
+fn name_param(procedure: &str, expr: &Value, env: Rc<EvalEnv>) -> Result<String, ErrorEval> {
+    match env.eval(expr.clone())? {
+        Value::StringValue(name) => Ok(name),
+        other => Err(ErrorEval::type_error(procedure, "string", &other)),
+    }
+}
+
+/// 分出可选的测试名与其余参数, 没有给出测试名时以被测表达式的外部表示作为测试名
+fn split_name<'a>(procedure: &str, args: &'a [Value], count: usize, env: Rc<EvalEnv>) -> Result<(String, &'a [Value]), ErrorEval> {
+    if args.len() == count + 1 {
+        Ok((name_param(procedure, &args[0], env)?, &args[1..]))
+    }
+    else if args.len() == count {
+        Ok((args[count - 1].to_string(), args))
+    }
+    else {
+        Err(ErrorEval::arity(procedure, args.len()))
+    }
+}
+
+/// 测试不通过的原因, 或者必须原样传回的退出请求与调试器的中止请求
+enum Failure {
+    Reason(String),
+    Unwinding(ErrorEval),
+}
+
+impl From<String> for Failure {
+    fn from(reason: String) -> Self {
+        Failure::Reason(reason)
+    }
+}
+
+/// 对被测表达式求值, 出错时以错误信息作为不通过的原因
+fn eval_tested(expr: &Value, env: Rc<EvalEnv>) -> Result<Value, Failure> {
+    env.eval(expr.clone()).map_err(|error| match error.is_unwinding() {
+        true => Failure::Unwinding(error),
+        false => Failure::Reason(format!("unexpected error: {}", error.kind)),
+    })
+}
+
+/// 记录测试的结果, 退出请求与调试器的中止请求不计入结果而是继续传回
+fn finish(env: &Rc<EvalEnv>, name: &str, result: Result<(), Failure>) -> Result<Value, ErrorEval> {
+    let result: Result<(), String> = match result {
+        Ok(()) => Ok(()),
+        Err(Failure::Reason(reason)) => Err(reason),
+        Err(Failure::Unwinding(error)) => return Err(error),
+    };
+    env.test_runner.report(&env.output, name, result);
+    Ok(Value::NilValue)
+}
+
+/// test-begin 特殊形式
+/// (test-begin name) 开始一个测试组
+pub fn test_begin_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if args.len() != 1 {
+        return Err(ErrorEval::arity("test-begin", args.len()));
+    }
+    let name: String = name_param("test-begin", &args[0], env.clone())?;
+    let runner: &TestRunner = &env.test_runner;
+    runner.groups.borrow_mut().push((name, runner.passed(), runner.failed()));
+    Ok(Value::NilValue)
+}
+
+/// test-end 特殊形式
+/// (test-end [name]) 结束当前测试组并打印该组的汇总, 给出的组名与当前测试组不符时报错
+pub fn test_end_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    if args.len() > 1 {
+        return Err(ErrorEval::arity("test-end", args.len()));
+    }
+    let expected: Option<String> = match args.first() {
+        Some(expr) => Some(name_param("test-end", expr, env.clone())?),
+        None => None,
+    };
+    let runner: &TestRunner = &env.test_runner;
+    let (name, passed, failed) = match runner.groups.borrow().last() {
+        Some(group) => group.clone(),
+        None => return Err(ErrorEval::runtime("test-end", "No test group to end")),
+    };
+    if expected.as_ref().is_some_and(|expected| *expected != name) {
+        return Err(ErrorEval::runtime("test-end", &format!("Expected to end {}, got {}", name, expected.unwrap())));
+    }
+    runner.groups.borrow_mut().pop();
+    env.output.writeln(&format!("{}: {} passed, {} failed", name, runner.passed() - passed, runner.failed() - failed));
+    Ok(Value::NilValue)
+}
+
+/// test-equal 特殊形式
+/// (test-equal [name] expected expr) 两者的值满足 equal? 时通过
+pub fn test_equal_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    let (name, args) = split_name("test-equal", &args, 2, env.clone())?;
+    let result = (|| {
+        let expected: Value = eval_tested(&args[0], env.clone())?;
+        let got: Value = eval_tested(&args[1], env.clone())?;
+        match equal_q(vec![expected.clone(), got.clone()], env.clone()) {
+            Ok(Value::BooleanValue(true)) => Ok(()),
+            _ => Err(format!("expected {}, got {}", expected.to_string(), got.to_string()).into()),
+        }
+    })();
+    finish(&env, &name, result)
+}
+
+/// test-assert 特殊形式
+/// (test-assert [name] expr) 值不为 #f 时通过
+pub fn test_assert_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    let (name, args) = split_name("test-assert", &args, 1, env.clone())?;
+    let result = match eval_tested(&args[0], env.clone()) {
+        Ok(Value::BooleanValue(false)) => Err("expected a true value, got #f".to_string().into()),
+        Ok(_) => Ok(()),
+        Err(failure) => Err(failure),
+    };
+    finish(&env, &name, result)
+}
+
+/// test-error 特殊形式
+/// (test-error [[name] error-type] expr) 求值出错时通过, 不检查错误类型, 退出请求与调试器的中止请求不算出错
+pub fn test_error_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    let (name, args) = match args.len() {
+        1 => (args[0].to_string(), &args[..]),
+        2 => (args[1].to_string(), &args[1..]),
+        3 => (name_param("test-error", &args[0], env.clone())?, &args[2..]),
+        _ => return Err(ErrorEval::arity("test-error", args.len())),
+    };
+    let result = match env.clone().eval(args[0].clone()) {
+        Ok(value) => Err(format!("expected an error, got {}", value.to_string()).into()),
+        Err(error) if error.is_unwinding() => Err(Failure::Unwinding(error)),
+        Err(_) => Ok(()),
+    };
+    finish(&env, &name, result)
+}
+
+/// test-approximate 特殊形式
+/// (test-approximate [name] expected expr error) 两者相差不超过error时通过
+pub fn test_approximate_form(args: Vec<Value>, env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+    let (name, args) = match args.len() {
+        3 => (args[1].to_string(), &args[..]),
+        4 => (name_param("test-approximate", &args[0], env.clone())?, &args[1..]),
+        _ => return Err(ErrorEval::arity("test-approximate", args.len())),
+    };
+    let result = (|| {
+        let values: Vec<Value> = args.iter().map(|arg| eval_tested(arg, env.clone())).collect::<Result<_, _>>()?;
+        match values.as_slice() {
+            [Value::NumericValue(expected), Value::NumericValue(got), Value::NumericValue(error)] => match (expected - got).abs() <= *error {
+                true => Ok(()),
+                false => Err(format!("expected {} ± {}, got {}", values[0].to_string(), values[2].to_string(), values[1].to_string()).into()),
+            },
+            _ => Err(format!("expected numbers, got {}", values.iter().map(Value::to_string).collect::<Vec<String>>().join(" ")).into()),
+        }
+    })();
+    finish(&env, &name, result)
+}
+
+/// 在目录dir及其子目录中查找 *-test.scm, 按路径排序
+pub fn discover(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut found: Vec<PathBuf> = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path: PathBuf = entry?.path();
+        if path.is_dir() {
+            found.extend(discover(&path)?);
+        }
+        else if path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.ends_with("-test.scm")) {
+            found.push(path);
+        }
+    }
+    found.sort();
+    Ok(found)
+}
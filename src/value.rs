@@ -1,17 +1,60 @@
 /// 定义解释器内"值"的概念
 /// 解释器执行的时候内部所有表达都是以"值"的形式进行传递的
 
+use std::any::Any;
 use std::hash::{Hash,Hasher};
 use std::fmt::Debug;
 use std::rc::Rc;
 use crate::error::ErrorEval;
 use crate::eval_env::EvalEnv;
-pub type BuiltinFn = fn(Vec<Value>, Rc<EvalEnv>) -> Result<Value, ErrorEval>;
+/// 内置过程类型
+/// 使用Rc<dyn Fn>而非函数指针, 使得宿主程序注册的闭包也可以捕获状态
+pub type BuiltinFn = Rc<dyn Fn(Vec<Value>, Rc<EvalEnv>) -> Result<Value, ErrorEval>>;
+
+/// 宿主对象
+/// 嵌入解释器的程序可以把任意Rust值包装成宿主对象, 在Lisp代码中传递后再取回
+/// 只需给出类型名, 打印, 相等与哈希均有默认实现: 按 #<类型名> 打印, 只有同一个对象才相等
+pub trait Foreign: Any {
+    /// 类型名, 用于打印与错误信息
+    fn type_name(&self) -> &str;
+
+    /// 外部表示
+    fn display(&self) -> String {
+        format!("#<{}>", self.type_name())
+    }
+
+    /// 与另一个宿主对象是否相等, 供 equal? 使用
+    /// 同一个对象总是相等, 不会调用该方法
+    fn equals(&self, _other: &dyn Foreign) -> bool {
+        false
+    }
+
+    /// 哈希值, 返回None时按对象地址哈希
+    /// 重写了 equals 的类型应同时重写该方法
+    fn hash_code(&self) -> Option<u64> {
+        None
+    }
+
+    /// 是否可以像过程一样调用, 例如续延
+    /// 可调用的类型应同时重写 call
+    fn callable(&self) -> bool {
+        false
+    }
+
+    /// 以求值后的参数args调用该对象
+    fn call(&self, _args: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+        Err(ErrorEval::runtime("apply", &format!("{} is not applicable", self.display())))
+    }
+}
 
 /// 值类型
 /// 布尔字面量, 数字字面量, 字符串字面量
 /// 空字面量, 符号, 对子
 /// 过程(内置过程与特殊形式), lambda表达式(外部定义)
+/// lambda表达式依次保存参数表, 函数体, 定义时的求值环境与文档字符串
+/// 宿主对象(由嵌入解释器的程序提供)
+/// 所有变体都沿用 XxxValue 的命名
+#[allow(clippy::enum_variant_names)]
 #[derive(Clone)]
 pub enum Value {
     BooleanValue(bool),
@@ -21,8 +64,150 @@ pub enum Value {
     SymbolValue(String),
     PairValue(Box<Value>, Box::<Value>),
     ProcedureValue(Box<BuiltinFn>),
-    LambdaValue(Box<Vec<String>>, Box<Vec<Value>>, Rc<EvalEnv>),
+    LambdaValue(Box<Params>, Box<Vec<Value>>, Rc<EvalEnv>, Option<String>),
+    ForeignValue(Rc<dyn Foreign>),
+}
+
+/// lambda表达式的参数表
+/// required: 必需参数
+/// optional: #!optional 之后的可选参数, 写作 (name default) 时给出默认值, 否则缺省为 #f
+/// rest: 剩余参数, 写作 (a b . rest), 单独一个符号 args 或者 #!rest rest
+/// keys: #:key 之后的关键字参数, 调用时写作 #:name value, 默认值的写法与可选参数相同
+#[derive(Clone, Default)]
+pub struct Params {
+    pub required: Vec<String>,
+    pub optional: Vec<(String, Option<Value>)>,
+    pub rest: Option<String>,
+    pub keys: Vec<(String, Option<Value>)>,
 }
+
+/// 参数表中正在读取的部分
+#[derive(Clone, Copy, PartialEq)]
+enum Section {
+    Required,
+    Optional,
+    Rest,
+    Key,
+}
+
+impl Params {
+    /// 从lambda表达式的参数表构造
+    pub fn parse(list: &Value) -> Result<Params, ErrorEval> {
+        let mut params: Params = Params::default();
+        let mut section: Section = Section::Required;
+        let mut current: &Value = list;
+        loop {
+            match current {
+                Value::NilValue => break,
+                Value::SymbolValue(name) if params.rest.is_none() => {
+                    params.rest = Some(name.clone());
+                    break;
+                },
+                Value::PairValue(car, cdr) => {
+                    match (car.as_ref(), section) {
+                        (Value::SymbolValue(s), _) if s == "#!optional" => section = Section::Optional,
+                        (Value::SymbolValue(s), _) if s == "#!rest" => section = Section::Rest,
+                        (Value::SymbolValue(s), _) if s == "#:key" => section = Section::Key,
+                        (Value::SymbolValue(name), Section::Required) => params.required.push(name.clone()),
+                        (Value::SymbolValue(name), Section::Optional) => params.optional.push((name.clone(), None)),
+                        (Value::SymbolValue(name), Section::Rest) if params.rest.is_none() => params.rest = Some(name.clone()),
+                        (Value::SymbolValue(name), Section::Key) => params.keys.push((name.clone(), None)),
+                        (Value::PairValue(_, _), Section::Optional | Section::Key) => {
+                            let (name, default) = match car.to_vector()?.as_slice() {
+                                [Value::SymbolValue(name), default] => (name.clone(), Some(default.clone())),
+                                _ => return Err(ErrorEval::bad_syntax("lambda", &format!("Invalid parameter {}", car.to_string()))),
+                            };
+                            match section {
+                                Section::Optional => params.optional.push((name, default)),
+                                _ => params.keys.push((name, default)),
+                            }
+                        },
+                        _ => return Err(ErrorEval::bad_syntax("lambda", &format!("Invalid parameter {}", car.to_string()))),
+                    }
+                    current = cdr;
+                },
+                _ => return Err(ErrorEval::bad_syntax("lambda", &format!("Invalid parameter list {}", list.to_string()))),
+            }
+        }
+        let names: Vec<String> = params.names();
+        if let Some(name) = names.iter().enumerate().find(|(i, name)| names[..*i].contains(name)).map(|(_, name)| name) {
+            return Err(ErrorEval::bad_syntax("lambda", &format!("Duplicate parameter {}", name)));
+        }
+        Ok(params)
+    }
+
+    /// 按顺序列出所有参数名
+    pub fn names(&self) -> Vec<String> {
+        self.required.iter()
+            .chain(self.optional.iter().map(|(name, _)| name))
+            .chain(self.keys.iter().map(|(name, _)| name))
+            .chain(self.rest.iter())
+            .cloned().collect()
+    }
+
+    /// 能否接受count个实参
+    /// 有关键字参数时, 必需参数之后的个数不做限制, 由绑定时检查
+    pub fn accepts(&self, count: usize) -> bool {
+        count >= self.required.len() && (self.rest.is_some() || !self.keys.is_empty() || count <= self.required.len() + self.optional.len())
+    }
+
+    /// 参数个数说明, 用于错误信息与文档
+    pub fn arity(&self) -> String {
+        let min: usize = self.required.len();
+        let max: usize = min + self.optional.len();
+        if self.rest.is_some() || !self.keys.is_empty() {
+            format!("at least {}", min)
+        }
+        else if max > min {
+            format!("{} to {}", min, max)
+        }
+        else {
+            min.to_string()
+        }
+    }
+
+    /// 除剩余参数外, 参数表中的各项
+    fn items(&self) -> Vec<String> {
+        fn item((name, default): &(String, Option<Value>)) -> String {
+            match default {
+                Some(default) => format!("({} {})", name, default.to_string()),
+                None => name.clone(),
+            }
+        }
+        let mut items: Vec<String> = self.required.clone();
+        if !self.optional.is_empty() {
+            items.push("#!optional".to_string());
+            items.extend(self.optional.iter().map(item));
+        }
+        if !self.keys.is_empty() {
+            items.push("#:key".to_string());
+            items.extend(self.keys.iter().map(item));
+        }
+        items
+    }
+
+    /// 以head开头的调用形式, 如 (head a b . rest)
+    pub fn signature(&self, head: &str) -> String {
+        let items: Vec<String> = std::iter::once(head.to_string()).chain(self.items()).collect();
+        match &self.rest {
+            Some(rest) => format!("({} . {})", items.join(" "), rest),
+            None => format!("({})", items.join(" ")),
+        }
+    }
+}
+
+/// 参数表的外部表示, 与lambda表达式中的写法相同
+impl std::fmt::Display for Params {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let items: Vec<String> = self.items();
+        match &self.rest {
+            Some(rest) if items.is_empty() => write!(f, "{}", rest),
+            Some(rest) => write!(f, "({} . {})", items.join(" "), rest),
+            None => write!(f, "({})", items.join(" ")),
+        }
+    }
+}
+
 pub fn is_integer(num: &f64) -> bool {
     num.abs() < std::f64::EPSILON ||
     (num - num.floor()).abs() < std::f64::EPSILON ||
@@ -38,7 +223,8 @@ impl Debug for Value {
             Self::SymbolValue(s) => write!(f, "SymbolValue {s}"),
             Self::PairValue(_, _) => write!(f, "PairValue {}", self.to_string()),
             Self::ProcedureValue(_) => write!(f, "ProcedureValue"),
-            Self::LambdaValue(_, _, _) => write!(f, "LambdaValue"),
+            Self::LambdaValue(_, _, _, _) => write!(f, "LambdaValue"),
+            Self::ForeignValue(o) => write!(f, "ForeignValue {}", o.type_name()),
         }
     }
 }
@@ -69,12 +255,13 @@ impl ToString for Value {
             Value::ProcedureValue(_f) => {
                 format!("#<procedure>")
             },
-            Value::LambdaValue(params, body, env) => {
+            Value::ForeignValue(o) => o.display(),
+            Value::LambdaValue(params, body, env, _) => {
                 let mut params_string: String = String::new();
                 let mut body_string: String = String::new();
                 let mut env_string: String = String::new();
-                for param in &**params {
-                    params_string += param.clone().as_str();
+                for param in params.names() {
+                    params_string += param.as_str();
                     params_string.push(' ');
                 }
                 for bodyv in &**body {
@@ -84,7 +271,7 @@ impl ToString for Value {
                 for bind in env.symbol_map.borrow().clone() {
                     match bind.1 {
                         Value::ProcedureValue(_) => continue,
-                        Value::LambdaValue(_, _, _) => continue,
+                        Value::LambdaValue(_, _, _, _) => continue,
                         _ => {
                             env_string += format!("({}, {})", bind.0, bind.1.to_string()).as_str();
                             env_string.push('\n');
@@ -96,24 +283,10 @@ impl ToString for Value {
             Value::PairValue(box_car, box_cdr) => {
                 let mut s: String = format!("({} ", box_car.to_string());
                 match &**box_cdr {
-                    v @ Value::BooleanValue(_) => {
+                    v @ (Value::BooleanValue(_) | Value::NumericValue(_) | Value::StringValue(_) | Value::SymbolValue(_)
+                        | Value::ProcedureValue(_) | Value::LambdaValue(_, _, _, _) | Value::ForeignValue(_)) => {
                         format!("{}. {})", s, v.to_string())
                     },
-                    v @ Value::NumericValue(_) => {
-                        format!("{}. {}", s, v.to_string())
-                    }
-                    v @ Value::StringValue(_) => {
-                        format!("{}. {}", s, v.to_string())
-                    }
-                    v @ Value::SymbolValue(_) => {
-                        format!("{}. {}", s, v.to_string())
-                    }
-                    v @ Value::ProcedureValue(_) => {
-                        format!("{}. {}", s, v.to_string())
-                    }
-                    v @ Value::LambdaValue(_, _, _) => {
-                        format!("{}. {}", s, v.to_string())
-                    }
                     v @ Value::PairValue(_, _) => {
                         let mut rs = v.to_string();
                         rs.remove(0);
@@ -143,14 +316,51 @@ impl Hash for Value {
             Value::StringValue(s) => s.hash(state),
             Value::SymbolValue(s) => s.hash(state),
             v @ Value::PairValue(_car, _cdr) => v.to_string().hash(state),
-            Value::ProcedureValue(f) => (**f as *const usize).hash(state),
-            v @ Value::LambdaValue(_, _, _) => v.to_string().hash(state),
+            Value::ProcedureValue(f) => (Rc::as_ptr(f) as *const () as usize).hash(state),
+            v @ Value::LambdaValue(_, _, _, _) => v.to_string().hash(state),
+            Value::ForeignValue(o) => match o.hash_code() {
+                Some(code) => code.hash(state),
+                None => (Rc::as_ptr(o) as *const () as usize).hash(state),
+            },
         }
     }
 }
 
 
 
+/// 可调用性与宿主对象相关的辅助方法
+impl Value {
+    /// 是否可以调用: 内置过程, lambda 以及可调用的宿主对象
+    pub fn is_procedure(&self) -> bool {
+        match self {
+            Value::ProcedureValue(_) | Value::LambdaValue(_, _, _, _) => true,
+            Value::ForeignValue(o) => o.callable(),
+            _ => false,
+        }
+    }
+
+    /// 包装一个宿主对象
+    pub fn foreign<T: Foreign>(object: T) -> Value {
+        Value::ForeignValue(Rc::new(object))
+    }
+
+    /// 若为类型T的宿主对象, 返回其引用
+    pub fn downcast_ref<T: Foreign>(&self) -> Option<&T> {
+        match self {
+            Value::ForeignValue(o) => (&**o as &dyn Any).downcast_ref::<T>(),
+            _ => None,
+        }
+    }
+
+    /// 若为类型T的宿主对象, 返回共享同一对象的Rc
+    pub fn downcast_rc<T: Foreign>(&self) -> Option<Rc<T>> {
+        match self {
+            Value::ForeignValue(o) => (o.clone() as Rc<dyn Any>).downcast::<T>().ok(),
+            _ => None,
+        }
+    }
+}
+
 impl Value {
     /// 将值转化为向量
     /// 其它值转化还是本身, 但是会将对子值展开.
@@ -162,13 +372,14 @@ impl Value {
                 Value::StringValue(_) => { vec.push(Rc::new(expr.clone())); Ok(()) },
                 Value::NilValue => Ok(()),
                 Value::SymbolValue(_) => { vec.push(Rc::new(expr.clone())); Ok(()) },
+                Value::ForeignValue(_) => { vec.push(Rc::new(expr.clone())); Ok(()) },
                 Value::PairValue(car, cdr) => {
                     vec.push(Rc::new(*(*car).clone()));
                     to_vector_recursive(cdr, vec)?;
                     Ok(())
                 }
                 // _ => panic!("Invalid format when converting pairvalue to vector."),
-                _ => Err(ErrorEval{message: format!("{}: [to_vector]: Invalid format when converting pairvalue to vector", 0), index: 0}),
+                _ => Err(ErrorEval::type_error("to_vector", "list", expr)),
             }
         }
         let mut vec: Vec<Rc<Value>> = Vec::new();
@@ -0,0 +1,5 @@
+use mini_lisp_interpreter::interpreter::Interpreter;
+/// 求值并返回结果的外部表示, 出错时测试失败
+pub fn eval(interpreter: &Interpreter, code: &str) -> String {
+    interpreter.eval_str(code).unwrap().to_string()
+}
@@ -0,0 +1,19 @@
+;; R7RS-small 6.10 控制特性
+
+(test #t (procedure? car))
+(test #f (procedure? 'car))
+(test #t (procedure? (lambda (x) (* x x))))
+(test #f (procedure? '(lambda (x) (* x x))))
+(test 7 (apply + (list 3 4)))
+(test '(b e h) (map cadr '((a b) (d e) (g h))))
+(test '(1 4 27 256 3125) (map (lambda (n) (expt n n)) '(1 2 3 4 5)))
+(test '(5 7 9) (map + '(1 2 3) '(4 5 6)))
+(test '(5 7) (map + '(1 2) '(4 5 6)))
+(test '(0 1 4 9 16)
+      (let ((v '()))
+        (for-each (lambda (i) (set! v (cons (* i i) v))) '(0 1 2 3 4))
+        (reverse v)))
+(test 5 (call-with-values (lambda () (values 4 5)) (lambda (a b) b)))
+(test -1 (call-with-values * -))
+(test 'ok (call-with-current-continuation (lambda (k) (k 'ok))))
+(test 3 (dynamic-wind (lambda () #f) (lambda () 3) (lambda () #f)))
@@ -0,0 +1,115 @@
+;; R7RS-small 6.1 等价谓词, 6.2 数, 6.3 布尔值, 6.4 对子与列表, 6.5 符号
+
+;; 6.1 等价谓词
+(test #t (eq? 'a 'a))
+(test #f (eq? (list 'a) (list 'a)))
+(test #t (eq? '() '()))
+(test #t (let ((x '(a))) (eq? x x)))
+(test #t (equal? 'a 'a))
+(test #t (equal? '(a) '(a)))
+(test #t (equal? '(a (b) c) '(a (b) c)))
+(test #t (equal? "abc" "abc"))
+(test #t (equal? 2 2))
+(test #f (equal? 2 3))
+
+;; 6.2 数
+(test #t (number? 3))
+(test #t (integer? 3))
+(test #f (integer? 3.5))
+(test #t (integer? 3.0))
+(test #t (= 1 1.0))
+(test #t (< 1 2 3))
+(test #f (< 1 3 2))
+(test #t (>= 3 3 2))
+(test #t (zero? 0))
+(test #t (odd? 3))
+(test #t (even? 0))
+(test 7 (+ 3 4))
+(test 3 (+ 3))
+(test 0 (+))
+(test 4 (* 4))
+(test 1 (*))
+(test -1 (- 3 4))
+(test -3 (- 3))
+(test 0.15 (/ 3 4 5))
+(test 2 (/ 6 3))
+(test 7 (abs -7))
+(test 2 (quotient 7 3))
+(test 1 (modulo 13 4))
+(test 3 (modulo -13 4))
+(test -1 (remainder -13 4))
+(test 1 (remainder 13 4))
+(test 8 (expt 2 3))
+(test 1 (expt 0 0))
+
+;; 6.3 布尔值
+(test #t #t)
+(test #f #f)
+(test #f '#f)
+(test #f (not #t))
+(test #f (not 3))
+(test #f (not (list 3)))
+(test #t (not #f))
+(test #f (not '()))
+(test #f (not 'nil))
+(test #t (boolean? #f))
+(test #f (boolean? 0))
+(test #f (boolean? '()))
+
+;; 6.4 对子与列表
+(define x (list 'a 'b 'c))
+(define y x)
+(test '(a b c) y)
+(test #t (list? y))
+(test #t (pair? '(a . b)))
+(test #t (pair? '(a b c)))
+(test #f (pair? '()))
+(test '(a) (cons 'a '()))
+(test '((a) b c d) (cons '(a) '(b c d)))
+(test '("a" b c) (cons "a" '(b c)))
+(test '(a . 3) (cons 'a 3))
+(test '((a b) . c) (cons '(a b) 'c))
+(test 'a (car '(a b c)))
+(test '(a) (car '((a) b c d)))
+(test 1 (car '(1 . 2)))
+(test-error (car '()))
+(test '(b c d) (cdr '((a) b c d)))
+(test 2 (cdr '(1 . 2)))
+(test-error (cdr '()))
+(test #t (null? '()))
+(test #t (list? '(a b c)))
+(test #t (list? '()))
+(test #f (list? '(a . b)))
+(test '(a 7 c) (list 'a (+ 3 4) 'c))
+(test '() (list))
+(test 3 (length '(a b c)))
+(test 3 (length '(a (b) (c d e))))
+(test 0 (length '()))
+(test '(x y) (append '(x) '(y)))
+(test '(a b c d) (append '(a) '(b c d)))
+(test '(a (b) (c)) (append '(a (b)) '((c))))
+(test '(a b c . d) (append '(a b) '(c . d)))
+(test 'a (append '() 'a))
+(test '(c b a) (reverse '(a b c)))
+(test '((e (f)) d (b c) a) (reverse '(a (b c) d (e (f)))))
+(test '(d e) (list-tail '(a b c d e) 3))
+(test 'c (list-ref '(a b c d) 2))
+(test '(a b c) (memq 'a '(a b c)))
+(test '(b c) (memq 'b '(a b c)))
+(test #f (memq 'a '(b c d)))
+(test #f (memq (list 'a) '(b (a) c)))
+(test '((a) c) (member (list 'a) '(b (a) c)))
+(test '(101 102) (memv 101 '(100 101 102)))
+(test '(a 1) (assq 'a '((a 1) (b 2) (c 3))))
+(test '(b 2) (assq 'b '((a 1) (b 2) (c 3))))
+(test #f (assq 'd '((a 1) (b 2) (c 3))))
+(test #f (assq (list 'a) '(((a)) ((b)) ((c)))))
+(test '((a)) (assoc (list 'a) '(((a)) ((b)) ((c)))))
+(test '(5 7) (assv 5 '((2 3) (5 7) (11 13))))
+
+;; 6.5 符号
+(test #t (symbol? 'foo))
+(test #t (symbol? (car '(a b))))
+(test #f (symbol? "bar"))
+(test #t (symbol? 'nil))
+(test #f (symbol? '()))
@@ -0,0 +1,118 @@
+;; R7RS-small 4.1 原始表达式类型 与 4.2 派生表达式类型
+
+;; 4.1.2 字面量表达式
+(test 'a (quote a))
+(test '(+ 1 2) (quote (+ 1 2)))
+(test '(quote a) ''a)
+(test "abc" '"abc")
+(test 145932 '145932)
+(test #t '#t)
+
+;; 4.1.3 过程调用
+(test 7 (+ 3 4))
+(test 12 ((if #f + *) 3 4))
+
+;; 4.1.4 过程
+(test 8 ((lambda (x) (+ x x)) 4))
+(define reverse-subtract
+  (lambda (x y) (- y x)))
+(test 3 (reverse-subtract 7 10))
+(define add4
+  (let ((x 4))
+    (lambda (y) (+ x y))))
+(test 10 (add4 6))
+(test '(3 4 5 6) ((lambda x x) 3 4 5 6))
+(test '(5 6) ((lambda (x y . z) z) 3 4 5 6))
+
+;; 4.1.5 条件表达式
+(test 'yes (if (> 3 2) 'yes 'no))
+(test 'no (if (> 2 3) 'yes 'no))
+(test 1 (if (> 3 2) (- 3 2) (+ 3 2)))
+
+;; 4.2.1 条件
+(test 'greater (cond ((> 3 2) 'greater) ((< 3 2) 'less)))
+(test 'equal (cond ((> 3 3) 'greater) ((< 3 3) 'less) (else 'equal)))
+(test 2 (cond ((assv 'b '((a 1) (b 2))) => cadr) (else #f)))
+(test 'composite (case (* 2 3) ((2 3 5 7) 'prime) ((1 4 6 8 9) 'composite)))
+(test 'c (case (car '(c d)) ((a e i o u) 'vowel) ((w y) 'semivowel) (else => (lambda (x) x))))
+(test #t (and (= 2 2) (> 2 1)))
+(test #f (and (= 2 2) (< 2 1)))
+(test '(f g) (and 1 2 'c '(f g)))
+(test #t (and))
+(test #t (or (= 2 2) (> 2 1)))
+(test #t (or (= 2 2) (< 2 1)))
+(test #f (or #f #f #f))
+(test '(b c) (or (memq 'b '(a b c)) (/ 3 0)))
+(test 'greater (when (> 1 0) 'greater))
+(test 'less (unless (> 0 1) 'less))
+
+;; 4.2.2 绑定结构
+(test 6 (let ((x 2) (y 3)) (* x y)))
+(test 35 (let ((x 2) (y 3)) (let ((x 7) (z (+ x y))) (* z x))))
+(test 70 (let ((x 2) (y 3)) (let* ((x 7) (z (+ x y))) (* z x))))
+(test #t (letrec ((even? (lambda (n) (if (zero? n) #t (odd? (- n 1)))))
+                  (odd? (lambda (n) (if (zero? n) #f (even? (- n 1))))))
+           (even? 88)))
+(test 5 (letrec* ((p (lambda (x) (+ 1 (q (- x 1)))))
+                  (q (lambda (y) (if (zero? y) 0 (+ 1 (p (- y 1))))))
+                  (x (p 5))
+                  (y x))
+          y))
+(test 35 (let-values (((root rem) (values 5 10))) (* root (+ rem -3))))
+(test '(x y x y) (let ((a 'a) (b 'b) (x 'x) (y 'y))
+                   (let*-values (((a b) (values x y))
+                                 ((x y) (values a b)))
+                     (list a b x y))))
+
+;; 4.2.3 顺序结构
+(test 6 (begin (+ 1 2) (* 2 3)))
+
+;; 4.2.4 迭代
+(test 25 (let ((x '(1 3 5 7 9)))
+           (do ((x x (cdr x))
+                (sum 0 (+ sum (car x))))
+               ((null? x) sum))))
+(test '((6 1 3) (-5 -2))
+      (let loop ((numbers '(3 -2 1 6 -5)) (nonneg '()) (neg '()))
+        (cond ((null? numbers) (list nonneg neg))
+              ((>= (car numbers) 0) (loop (cdr numbers) (cons (car numbers) nonneg) neg))
+              ((< (car numbers) 0) (loop (cdr numbers) nonneg (cons (car numbers) neg))))))
+
+;; 4.2.5 延迟求值
+(test 3 (force (delay (+ 1 2))))
+(test '(3 3) (let ((p (delay (+ 1 2)))) (list (force p) (force p))))
+(define integers
+  (letrec ((next (lambda (n) (delay (cons n (next (+ n 1)))))))
+    (next 0)))
+(define (head stream) (car (force stream)))
+(define (tail stream) (cdr (force stream)))
+(test 2 (head (tail (tail integers))))
+(define (stream-drop s k)
+  (delay-force (if (= k 0) s (stream-drop (tail s) (- k 1)))))
+(test 5 (head (stream-drop integers 5)))
+(test #t (promise? (delay 1)))
+(test 7 (force (make-promise 7)))
+(test 7 (force 7))
+
+;; 4.2.6 动态绑定
+(define radix (make-parameter 10 (lambda (x) (if (and (integer? x) (< 1 x) (< x 37)) x (error "invalid radix")))))
+(test 10 (radix))
+(test 2 (parameterize ((radix 2)) (radix)))
+(test 10 (radix))
+(test-error (parameterize ((radix 0)) (radix)))
+
+;; 4.2.8 准引用
+(test '(list 3 4) `(list ,(+ 1 2) 4))
+(test '(list a (quote a)) (let ((name 'a)) `(list ,name ',name)))
+
+;; 4.3 与 5.3 定义
+(define add3 (lambda (x) (+ x 3)))
+(test 6 (add3 3))
+(define first car)
+(test 1 (first '(1 2)))
+(test 45 (let ((x 5))
+           (define foo (lambda (y) (bar x y)))
+           (define bar (lambda (a b) (+ (* a b) a)))
+           (foo (+ x 3))))
+(define-values (q r) (values 7 2))
+(test '(7 2) (list q r))
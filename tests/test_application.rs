@@ -0,0 +1,46 @@
+mod common;
+use common::eval;
+use mini_lisp_interpreter::error::ErrorKind;
+use mini_lisp_interpreter::interpreter::Interpreter;
+#[test]
+fn computed_operator() {
+    let interpreter: Interpreter = Interpreter::new();
+    assert_eq!(eval(&interpreter, "((lambda (x) x) '(a b))"), "(a b)");
+    assert_eq!(eval(&interpreter, "((lambda (x) x) (list 'car ''(1 2)))"), "(car (quote (1 2)))");
+    assert_eq!(eval(&interpreter, "((if #t car cdr) '(1 2))"), "1");
+    assert_eq!(eval(&interpreter, "((if #f car cdr) '(1 2))"), "(2)");
+    assert_eq!(eval(&interpreter, "(((lambda (x) (lambda (y) (+ x y))) 1) 2)"), "3");
+    assert_eq!(eval(&interpreter, "((car (list car)) '(7 8))"), "7");
+    assert_eq!(eval(&interpreter, "((lambda args args))"), "()");
+    assert_eq!(eval(&interpreter, "((lambda (x) (+ x 1)) (* 2 3))"), "7");
+}
+#[test]
+fn procedure_as_operator() {
+    let interpreter: Interpreter = Interpreter::new();
+    assert_eq!(eval(&interpreter, "(eval (list car ''(1 2)))"), "1");
+    assert_eq!(eval(&interpreter, "(eval (list + '(* 2 3) 1))"), "7");
+    assert_eq!(eval(&interpreter, "(eval (list (lambda (x) (* x 2)) '(+ 1 2)))"), "6");
+    assert_eq!(eval(&interpreter, "(eval (list (lambda (x) x) ''(a b)))"), "(a b)");
+}
+#[test]
+fn operands_evaluated_once() {
+    let interpreter: Interpreter = Interpreter::new();
+    interpreter.eval_str("(define p (make-parameter 0))").unwrap();
+    interpreter.eval_str("(define (tick) (parameterize ((p (+ (p) 1))) (p)))").unwrap();
+    assert_eq!(eval(&interpreter, "((lambda (x) x) (tick))"), "1");
+    assert_eq!(eval(&interpreter, "((lambda (x) x) (quote (quote a)))"), "(quote a)");
+    assert_eq!(eval(&interpreter, "((lambda (x) x) ''''a)"), "(quote (quote (quote a)))");
+    interpreter.env().output.capture();
+    interpreter.eval_str("(and (display 1)) (and #t (display 2)) (or (display 3))").unwrap();
+    assert_eq!(interpreter.env().output.take(), "123");
+    assert_eq!(eval(&interpreter, "(and 1 '(a b))"), "(a b)");
+    assert_eq!(eval(&interpreter, "(list (not 3) (not '()) (not (list 3)) (not 'x) (not #f) (not ''a))"), "(#f #f #f #f #t #f)");
+}
+#[test]
+fn not_a_procedure() {
+    let interpreter: Interpreter = Interpreter::new();
+    assert!(matches!(interpreter.eval_str("((lambda (x) x) 1 2)").unwrap_err().kind, ErrorKind::ArityMismatch { .. }));
+    assert!(matches!(interpreter.eval_str("((car '(1)) 2)").unwrap_err().kind, ErrorKind::TypeError { .. }));
+    assert!(matches!(interpreter.eval_str("((if #t 1 2))").unwrap_err().kind, ErrorKind::TypeError { .. }));
+    assert!(interpreter.eval_str("(1 2)").is_err());
+}
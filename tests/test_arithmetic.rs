@@ -0,0 +1,22 @@
+use mini_lisp_interpreter::interpreter::Interpreter;
+#[test]
+fn variadic_division_and_comparisons() {
+    let interpreter: Interpreter = Interpreter::new();
+    let cases: [(&str, &str); 12] = [
+        ("(/ 6 3)", "2"),
+        ("(/ 60 2 3)", "10"),
+        ("(/ 4)", "0.25"),
+        ("(- 10 1 2 3)", "4"),
+        ("(- 5)", "-5"),
+        ("(< 1 2 3)", "#t"),
+        ("(< 1 3 2)", "#f"),
+        ("(>= 3 3 2)", "#t"),
+        ("(= 2 2 2.0)", "#t"),
+        ("(> 3 2 2)", "#f"),
+        ("(<= 1 1 2)", "#t"),
+        ("(expt 0 0)", "1"),
+    ];
+    for (code, expected) in cases {
+        assert_eq!(interpreter.eval_str(code).unwrap().to_string(), expected, "{}", code);
+    }
+}
@@ -0,0 +1,53 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Output, Stdio};
+/// 以给定的参数与标准输入运行解释器, 返回(标准输出, 退出码)
+/// 交互模式的历史记录写到临时目录中
+fn run(args: &[&str], stdin: &str) -> (String, Option<i32>) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mini_lisp_interpreter"))
+        .args(args)
+        .env("HOME", std::env::temp_dir())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    let output: Output = child.wait_with_output().unwrap();
+    (String::from_utf8(output.stdout).unwrap(), output.status.code())
+}
+#[test]
+fn eval_option() {
+    assert_eq!(run(&["-e", "(display 1)", "-e", "(+ 1 2)"], ""), ("13\n".to_string(), Some(0)));
+    assert_eq!(run(&["-e", "(define x 2)", "--eval", "(* x 3)"], ""), ("6\n".to_string(), Some(0)));
+    assert_eq!(run(&["-e", "'()", "-e", "(define x 1)", "-e", "\"()\"", "-e", "(list x)"], ""), ("\"()\"\n(1)\n".to_string(), Some(0)));
+    let program: String = format!("(\"{}\" \"a\" \"b\")", env!("CARGO_BIN_EXE_mini_lisp_interpreter"));
+    assert_eq!(run(&["-e", "(display (command-line))", "a", "b"], ""), (program, Some(0)));
+}
+#[test]
+fn stdin_and_script_args() {
+    assert_eq!(run(&["-"], "(display \"in\") (+ 2 3)"), ("in5\n".to_string(), Some(0)));
+    assert_eq!(run(&["-", "a", "b"], "(display (command-line))"), ("(\"-\" \"a\" \"b\")".to_string(), Some(0)));
+    let script: PathBuf = std::env::temp_dir().join(format!("minilisp-cli-{}.scm", std::process::id()));
+    std::fs::write(&script, "(display (cdr (command-line)))").unwrap();
+    let path: &str = script.to_str().unwrap();
+    let plain: (String, Option<i32>) = run(&[path, "-x", "--", "y"], "");
+    let separated: (String, Option<i32>) = run(&["--", path, "-e", "z"], "");
+    std::fs::remove_file(&script).unwrap();
+    assert_eq!(plain, ("(\"-x\" \"--\" \"y\")".to_string(), Some(0)));
+    assert_eq!(separated, ("(\"-e\" \"z\")".to_string(), Some(0)));
+}
+#[test]
+fn exit_codes() {
+    assert_eq!(run(&["-e", "(+ 1"], "").1, Some(65));
+    assert_eq!(run(&["-"], "(display 1) #| unterminated").1, Some(65));
+    assert_eq!(run(&["-e", "(display 1) (car 1) (display 2)"], ""), ("1".to_string(), Some(70)));
+    assert_eq!(run(&["-e", "(exit_force 1.5)"], "").1, Some(127));
+    assert_eq!(run(&["no-such-file.scm"], "").1, Some(127));
+    assert_eq!(run(&["-e", "(display 1) (exit 3) (display 2)"], ""), ("1".to_string(), Some(3)));
+    assert_eq!(run(&["-e", "(exit)"], "").1, Some(0));
+}
+#[test]
+fn interactive_results() {
+    assert_eq!(run(&["-i"], "'()\n(define x 1)\n\"()\"\n(list x)\n"), ("\"()\"\n(1)\n".to_string(), Some(0)));
+}
@@ -0,0 +1,21 @@
+use mini_lisp_interpreter::error::ErrorRead;
+use mini_lisp_interpreter::tokenizer::Tokenizer;
+use mini_lisp_interpreter::{test_machine::test_machine, eval_env::EvalEnv};
+use std::rc::Rc;
+#[test]
+fn comments() {
+    let eval_env: EvalEnv = EvalEnv::new();
+    test_machine(("#| block |# 42", "42"), Rc::new(eval_env.clone()));
+    test_machine(("#| outer #| inner |# still outer |# 42", "42"), Rc::new(eval_env.clone()));
+    test_machine(("#;(+ 1 2) 42", "42"), Rc::new(eval_env.clone()));
+    test_machine(("(+ 1 #;'(2 3) 4)", "5"), Rc::new(eval_env.clone()));
+    test_machine(("(+ 1 #;#;2 3 4)", "5"), Rc::new(eval_env.clone()));
+    test_machine(("#!/usr/bin/env minilisp\n42", "42"), Rc::new(eval_env.clone()));
+}
+#[test]
+fn unterminated_comments() {
+    for source in ["#| never closed", "#| outer #| inner |# 42", "42 #;", "#;", "(+ 1 #;)", "(+ 1 2 #; )", "#x"] {
+        assert_eq!(Tokenizer::new(source.to_string()).tokenize().unwrap_err(), ErrorRead::SyntaxFailure, "{}", source);
+    }
+    assert_eq!(Tokenizer::new("#| ok |# 42 #;(1 2)".to_string()).tokenize().unwrap().len(), 1);
+}
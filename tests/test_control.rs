@@ -0,0 +1,44 @@
+mod common;
+use common::eval;
+use mini_lisp_interpreter::error::ErrorKind;
+use mini_lisp_interpreter::interpreter::Interpreter;
+#[test]
+fn conditionals() {
+    let interpreter: Interpreter = Interpreter::new();
+    assert_eq!(eval(&interpreter, "(when (> 1 0) 1 2)"), "2");
+    assert_eq!(eval(&interpreter, "(unless (> 1 0) 1)"), "()");
+    assert_eq!(eval(&interpreter, "(case (* 2 3) ((2 3 5 7) 'prime) ((1 4 6 8 9) 'composite))"), "composite");
+    assert_eq!(eval(&interpreter, "(case 'x ((a) 1) (else => (lambda (k) (list k k))))"), "(x x)");
+    assert_eq!(eval(&interpreter, "(case 5 ((5) => (lambda (x) (* x 10))))"), "50");
+    assert_eq!(eval(&interpreter, "(cond ((+ 1 1) => (lambda (x) (* x 3))) (else 0))"), "6");
+    assert_eq!(eval(&interpreter, "(cond (#f 1) (5))"), "5");
+    assert!(matches!(interpreter.eval_str("(cond (#t (car 1)))").unwrap_err().kind, ErrorKind::TypeError { .. }));
+}
+#[test]
+fn do_loop() {
+    let interpreter: Interpreter = Interpreter::new();
+    assert_eq!(eval(&interpreter, "(do ((i 0 (+ i 1)) (acc '() (cons i acc))) ((= i 4) acc))"), "(3 2 1 0)");
+    assert_eq!(eval(&interpreter, "(do ((i 0 (+ i 1)) (n 7)) ((= i 2) n))"), "7");
+}
+#[test]
+fn promises() {
+    let interpreter: Interpreter = Interpreter::new();
+    interpreter.eval_str("(define count 0) (define p (delay (begin (define count (+ count 1)) count)))").unwrap();
+    assert_eq!(eval(&interpreter, "(list (force p) (force p))"), "(1 1)");
+    assert_eq!(eval(&interpreter, "(promise? p)"), "#t");
+    assert_eq!(eval(&interpreter, "(force (make-promise 3))"), "3");
+    assert_eq!(eval(&interpreter, "(force 4)"), "4");
+    interpreter.eval_str("(define (countdown n) (delay-force (if (= n 0) (delay 'done) (countdown (- n 1)))))").unwrap();
+    assert_eq!(eval(&interpreter, "(force (countdown 2000))"), "done");
+}
+#[test]
+fn parameters() {
+    let interpreter: Interpreter = Interpreter::new();
+    interpreter.eval_str("(define width (make-parameter 10 (lambda (x) (* x 2))))").unwrap();
+    assert_eq!(eval(&interpreter, "(width)"), "20");
+    assert_eq!(eval(&interpreter, "(parameterize ((width 3)) (width))"), "6");
+    assert_eq!(eval(&interpreter, "(width)"), "20");
+    assert!(interpreter.eval_str("(parameterize ((width 3)) (car 1))").is_err());
+    assert_eq!(eval(&interpreter, "(width)"), "20");
+    assert!(matches!(interpreter.eval_str("(width 1)").unwrap_err().kind, ErrorKind::ArityMismatch { .. }));
+}
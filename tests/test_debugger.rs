@@ -0,0 +1,86 @@
+use mini_lisp_interpreter::error::ErrorKind;
+use mini_lisp_interpreter::interpreter::Interpreter;
+use std::cell::RefCell;
+use std::io::{Cursor, Write};
+use std::rc::Rc;
+#[derive(Clone, Default)]
+struct Output(Rc<RefCell<Vec<u8>>>);
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+fn debug(commands: &str) -> (Interpreter, Output) {
+    let interpreter: Interpreter = Interpreter::new();
+    let output: Output = Output::default();
+    interpreter.env().debugger.set_io(Box::new(Cursor::new(commands.to_string().into_bytes())), Box::new(output.clone()));
+    (interpreter, output)
+}
+fn text(output: &Output) -> String {
+    String::from_utf8(output.0.borrow().clone()).unwrap()
+}
+#[test]
+fn break_and_inspect() {
+    let (interpreter, output) = debug("l\nw\np (+ c 10)\nc\n");
+    interpreter.eval_str("(define (f a b) (let ((c (+ a b))) (break) (* c c)))").unwrap();
+    assert_eq!(interpreter.eval_str("(f 1 2)").unwrap().to_string(), "9");
+    let output: String = text(&output);
+    assert!(output.contains("Paused at (break)"));
+    assert!(output.contains("[0] c = 3\n"));
+    assert!(output.contains("[1] a = 1, b = 2\n"));
+    assert!(output.contains("(f 1 2), in f\n"));
+    assert!(output.contains("debug> 13\n"));
+}
+#[test]
+fn breakpoint_and_quit() {
+    let (interpreter, output) = debug("l\nb car\nc\nc\nq\n");
+    interpreter.eval_str("(define (square x) (* x x))").unwrap();
+    interpreter.env().debugger.add_breakpoint("square");
+    assert_eq!(interpreter.eval_str("(+ (square 2) (car (list 1)))").unwrap().to_string(), "5");
+    let output: String = text(&output);
+    assert!(output.contains("Breakpoint: (square 2)\ndebug> [0] x = 2\n"));
+    assert!(output.contains("Breakpoint set on car.\ndebug> Breakpoint: (car (list 1))"));
+    assert!(interpreter.eval_str("(square 3)").unwrap_err().to_string().contains("aborted by the debugger"));
+}
+#[test]
+fn quit_inside_special_forms() {
+    let (interpreter, output) = debug("q\nq\nq\n");
+    interpreter.eval_str("(define (f) (break) 1)").unwrap();
+    interpreter.env().output.capture();
+    for code in ["(and (f) (display \"after\"))", "(or (not (f)) (display \"after\"))", "(if (f) (display \"after\") 2)"] {
+        let error = interpreter.eval_str(code).unwrap_err();
+        assert!(matches!(error.kind, ErrorKind::Aborted), "{}: {}", code, error);
+        assert!(error.traceback.is_none());
+    }
+    assert_eq!(interpreter.env().output.take(), "");
+    assert_eq!(text(&output).matches("Paused at (break)").count(), 3);
+}
+#[test]
+fn stepping() {
+    let (interpreter, output) = debug("s\nn\nc\n");
+    interpreter.eval_str("(define (g x) (+ x 1))").unwrap();
+    interpreter.env().debugger.step();
+    assert_eq!(interpreter.eval_str("(g (g 1))").unwrap().to_string(), "3");
+    let output: String = text(&output);
+    assert!(output.starts_with("-> (g (g 1))\ndebug> -> (g 1)\ndebug> "));
+    assert!(!output.contains("(+ x 1)"));
+}
+#[test]
+fn trace_and_untrace() {
+    let (interpreter, output) = debug("");
+    interpreter.eval_str("(define (fact n) (if (= n 0) 1 (* n (fact (- n 1))))) (trace fact car)").unwrap();
+    assert_eq!(interpreter.eval_str("(fact 2)").unwrap().to_string(), "2");
+    assert_eq!(interpreter.eval_str("(map car (list (list 1)))").unwrap().to_string(), "(1)");
+    assert_eq!(text(&output), "> (fact 2)\n  > (fact 1)\n    > (fact 0)\n    < 1\n  < 1\n< 2\n> (car (1))\n< 1\n");
+    interpreter.eval_str("(untrace fact car)").unwrap();
+    assert_eq!(interpreter.eval_str("(fact 3) (car (list 1))").unwrap().to_string(), "1");
+    assert_eq!(interpreter.eval_str("(fact 3)").unwrap().to_string(), "6");
+    assert!(!text(&output).contains("(fact 3)"));
+    assert!(interpreter.eval_str("(trace 1)").is_err());
+    interpreter.eval_str("(define x 1)").unwrap();
+    assert!(interpreter.eval_str("(trace x)").is_err());
+}
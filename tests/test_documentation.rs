@@ -0,0 +1,17 @@
+use mini_lisp_interpreter::{test_machine::test_machine, eval_env::EvalEnv, documentation::lookup};
+use std::rc::Rc;
+#[test]
+fn every_builtin_is_documented() {
+    let eval_env: EvalEnv = EvalEnv::new();
+    for name in eval_env.builtin_procs.keys().chain(eval_env.special_forms.keys()) {
+        assert!(lookup(name).is_some(), "{} has no documentation", name);
+    }
+}
+#[test]
+fn docstring() {
+    let eval_env: Rc<EvalEnv> = Rc::new(EvalEnv::new());
+    test_machine(("(define (f x) \"doc\" (+ x 1))", "()"), eval_env.clone());
+    test_machine(("(f 1)", "2"), eval_env.clone());
+    test_machine(("(define (g) \"only a value\")", "()"), eval_env.clone());
+    test_machine(("(g)", "\"only a value\""), eval_env.clone());
+}
@@ -0,0 +1,66 @@
+use mini_lisp_interpreter::error::ErrorKind;
+use mini_lisp_interpreter::interpreter::Interpreter;
+#[test]
+fn error_kinds() {
+    let interpreter: Interpreter = Interpreter::new();
+    let kind = |code: &str| interpreter.eval_str(code).unwrap_err().kind.clone();
+    assert!(matches!(kind("(car 1)"), ErrorKind::TypeError { .. }));
+    assert!(matches!(kind("(undefined-x)"), ErrorKind::UnboundVariable(name) if name == "undefined-x"));
+    assert!(matches!(kind("(car)"), ErrorKind::ArityMismatch { got: 0, .. }));
+    assert!(matches!(kind("(quotient 1 0)"), ErrorKind::DivisionByZero));
+    assert!(matches!(kind("(/ 6 3 0)"), ErrorKind::DivisionByZero));
+    assert!(matches!(kind("(< 1 'a 3)"), ErrorKind::TypeError { .. }));
+    assert!(matches!(kind("(< 1)"), ErrorKind::ArityMismatch { got: 1, .. }));
+    assert!(matches!(kind("(append 1 '(3))"), ErrorKind::TypeError { .. }));
+    assert!(matches!(kind("(error 'x)"), ErrorKind::UserRaised(_)));
+    assert!(matches!(kind("(exit 2)"), ErrorKind::Exit(2, None)));
+    assert!(matches!(kind("(exit 1.5)"), ErrorKind::TypeError { .. }));
+    assert!(matches!(kind("(exit \"x\")"), ErrorKind::TypeError { .. }));
+    assert!(matches!(kind("(exit_force 1.5)"), ErrorKind::Exit(127, Some(_))));
+    assert!(matches!(kind("(exit_force 1 2)"), ErrorKind::Exit(127, Some(_))));
+}
+#[test]
+fn errors_propagate_through_conditionals() {
+    let interpreter: Interpreter = Interpreter::new();
+    let kind = |code: &str| interpreter.eval_str(code).unwrap_err().kind.clone();
+    assert!(matches!(kind("(if (car 1) 1 2)"), ErrorKind::TypeError { .. }));
+    assert!(matches!(kind("(if #t (car 1) 2)"), ErrorKind::TypeError { .. }));
+    assert!(matches!(kind("(if #f 1 (car 1))"), ErrorKind::TypeError { .. }));
+    assert!(matches!(kind("(and (car 1))"), ErrorKind::TypeError { .. }));
+    assert!(matches!(kind("(and 1 (car 1) 2)"), ErrorKind::TypeError { .. }));
+    assert!(matches!(kind("(or (car 1) 7)"), ErrorKind::TypeError { .. }));
+    assert!(matches!(kind("(or #f (car 1))"), ErrorKind::TypeError { .. }));
+    assert!(matches!(kind("(cond ((car 1) 1) (else 2))"), ErrorKind::TypeError { .. }));
+    assert!(matches!(kind("(if 1)"), ErrorKind::ArityMismatch { .. }));
+    assert_eq!(interpreter.eval_str("(or #f 7 (car 1))").unwrap().to_string(), "7");
+    assert_eq!(interpreter.eval_str("(and #f (car 1))").unwrap().to_string(), "#f");
+}
+#[test]
+fn backtrace() {
+    let interpreter: Interpreter = Interpreter::new();
+    let error = interpreter.eval_str("(define (f x) (car x))\n(f 1)").unwrap_err();
+    assert!(error.backtrace.iter().any(|frame| frame.procedure == "car"));
+    assert_eq!(error.location.as_deref(), Some("2:1"));
+    let message: String = error.to_string();
+    assert!(message.starts_with("2:1: "));
+    assert!(message.contains("in <car>"));
+}
+#[test]
+fn traceback() {
+    let interpreter: Interpreter = Interpreter::new();
+    interpreter.eval_str("(define (f x) (g x)) (define (g x) (+ 1 (car x)))").unwrap();
+    let error = interpreter.eval_str("(f 5)").unwrap_err();
+    let traceback = error.traceback.as_ref().unwrap();
+    let procedures: Vec<&str> = traceback.frames.iter().map(|frame| frame.procedure.as_str()).collect();
+    assert_eq!(procedures, vec!["f", "g", "car"]);
+    assert_eq!(traceback.frames[1].call_site, "(g x)");
+    assert!(error.to_string().contains("Traceback (most recent call last):\n  (f 5), in f\n  (g x), in g\n  (car x), in car"));
+    interpreter.env().call_stack.set_depth(2);
+    interpreter.eval_str("(define (loop n) (if (= n 0) (car n) (loop (- n 1))))").unwrap();
+    let traceback = interpreter.eval_str("(loop 3)").unwrap_err().traceback.clone().unwrap();
+    assert_eq!(traceback.frames.len(), 2);
+    assert_eq!(traceback.omitted, 3);
+    assert_eq!(interpreter.env().call_stack.height(), 0);
+    interpreter.env().call_stack.set_depth(0);
+    assert!(interpreter.eval_str("(loop 3)").unwrap_err().traceback.is_none());
+}
@@ -0,0 +1,171 @@
+use mini_lisp_interpreter::alloc_counter;
+use mini_lisp_interpreter::error::ErrorEval;
+use mini_lisp_interpreter::eval_env::EvalEnv;
+use mini_lisp_interpreter::interpreter::Interpreter;
+use mini_lisp_interpreter::sandbox::Limits;
+use mini_lisp_interpreter::value::{Value, Foreign};
+use std::any::Any;
+use std::cell::RefCell;
+use std::rc::Rc;
+#[test]
+fn eval_str() {
+    let interpreter: Interpreter = Interpreter::new();
+    assert_eq!(interpreter.eval_str("(define x 2) (* x 21)").unwrap().to_string(), "42");
+    assert_eq!(interpreter.eval_str("").unwrap().to_string(), "()");
+    assert!(interpreter.eval_str("(car 1)").is_err());
+    assert!(interpreter.eval_str("(+ 1").is_err());
+}
+#[test]
+fn define_global() {
+    let interpreter: Interpreter = Interpreter::new();
+    interpreter.define_global("name", "lisp");
+    interpreter.define_global("numbers", vec![1.0, 2.0, 3.0]);
+    assert_eq!(interpreter.eval_str("name").unwrap().to_string(), "\"lisp\"");
+    assert_eq!(interpreter.eval_str("(length numbers)").unwrap().to_string(), "3");
+    interpreter.eval_str("(define answer (list #t #f))").unwrap();
+    assert_eq!(interpreter.get_global::<Vec<bool>>("answer").unwrap(), vec![true, false]);
+    assert!(interpreter.get_global::<f64>("answer").is_err());
+}
+#[test]
+fn register_fn() {
+    let interpreter: Interpreter = Interpreter::new();
+    interpreter.register_fn("add", |a: f64, b: f64| a + b);
+    interpreter.register_fn("sum", |xs: Vec<f64>| xs.iter().sum::<f64>());
+    interpreter.register_fn("greet", |name: Option<String>| format!("hello, {}", name.unwrap_or("world".to_string())));
+    interpreter.register_fn("checked-sqrt", |x: f64| if x < 0.0 { Err("negative") } else { Ok(x.sqrt()) });
+    assert_eq!(interpreter.eval_str("(add 1 2)").unwrap().to_string(), "3");
+    assert_eq!(interpreter.eval_str("(sum (list 1 2 3))").unwrap().to_string(), "6");
+    assert_eq!(interpreter.eval_str("(greet '())").unwrap().to_string(), "\"hello, world\"");
+    assert_eq!(interpreter.eval_str("(map sum '((1 2) (3 4)))").unwrap().to_string(), "(3 7)");
+    assert_eq!(interpreter.eval_str("(checked-sqrt 4)").unwrap().to_string(), "2");
+    assert!(interpreter.eval_str("(checked-sqrt -1)").is_err());
+    assert!(interpreter.eval_str("(add 1)").is_err());
+    assert!(interpreter.eval_str("(add 1 \"2\")").is_err());
+}
+#[test]
+fn closure_state() {
+    let interpreter: Interpreter = Interpreter::new();
+    let log: Rc<RefCell<Vec<String>>> = Rc::new(RefCell::new(Vec::new()));
+    let sink: Rc<RefCell<Vec<String>>> = log.clone();
+    interpreter.register_fn("log!", move |message: String| sink.borrow_mut().push(message));
+    interpreter.register_builtin("count-args", |params: Vec<Value>, _env| Ok(Value::NumericValue(params.len() as f64)));
+    interpreter.eval_str("(log! \"a\") (log! \"b\")").unwrap();
+    assert_eq!(*log.borrow(), vec!["a".to_string(), "b".to_string()]);
+    assert_eq!(interpreter.eval_str("(count-args 1 2 3)").unwrap().to_string(), "3");
+}
+struct Handle {
+    id: u64,
+}
+impl Foreign for Handle {
+    fn type_name(&self) -> &str {
+        "handle"
+    }
+    fn display(&self) -> String {
+        format!("#<handle {}>", self.id)
+    }
+    fn equals(&self, other: &dyn Foreign) -> bool {
+        (other as &dyn Any).downcast_ref::<Handle>().is_some_and(|other| other.id == self.id)
+    }
+    fn hash_code(&self) -> Option<u64> {
+        Some(self.id)
+    }
+}
+struct Opaque;
+impl Foreign for Opaque {
+    fn type_name(&self) -> &str {
+        "opaque"
+    }
+}
+#[test]
+fn foreign_value() {
+    let interpreter: Interpreter = Interpreter::new();
+    interpreter.define_global("h1", Value::foreign(Handle { id: 1 }));
+    interpreter.define_global("h1-copy", Value::foreign(Handle { id: 1 }));
+    interpreter.define_global("opaque", Value::foreign(Opaque));
+    interpreter.register_fn("handle-id", |handle: Rc<Handle>| handle.id as f64);
+    interpreter.register_fn("open", |id: f64| Rc::new(Handle { id: id as u64 }));
+    assert_eq!(interpreter.eval_str("h1").unwrap().to_string(), "#<handle 1>");
+    assert_eq!(interpreter.eval_str("opaque").unwrap().to_string(), "#<opaque>");
+    assert_eq!(interpreter.eval_str("(handle-id (open 7))").unwrap().to_string(), "7");
+    assert!(interpreter.eval_str("(handle-id opaque)").is_err());
+    assert_eq!(interpreter.eval_str("(eq? h1 h1)").unwrap().to_string(), "#t");
+    assert_eq!(interpreter.eval_str("(eq? h1 h1-copy)").unwrap().to_string(), "#f");
+    assert_eq!(interpreter.eval_str("(equal? (list h1) (list h1-copy))").unwrap().to_string(), "#t");
+    assert_eq!(interpreter.eval_str("(equal? opaque opaque)").unwrap().to_string(), "#t");
+    assert_eq!(interpreter.eval_str("(atom? opaque)").unwrap().to_string(), "#t");
+    assert_eq!(interpreter.eval_str("(procedure? opaque)").unwrap().to_string(), "#f");
+    assert_eq!(interpreter.eval_str("(not opaque)").unwrap().to_string(), "#f");
+    let value: Value = interpreter.eval_str("h1").unwrap();
+    assert_eq!(value.downcast_ref::<Handle>().map(|handle| handle.id), Some(1));
+    assert!(value.downcast_ref::<Opaque>().is_none());
+    assert_eq!(interpreter.get_global::<Rc<Handle>>("h1").unwrap().id, 1);
+}
+struct Adder {
+    offset: f64,
+}
+impl Foreign for Adder {
+    fn type_name(&self) -> &str {
+        "adder"
+    }
+    fn callable(&self) -> bool {
+        true
+    }
+    fn call(&self, args: Vec<Value>, _env: Rc<EvalEnv>) -> Result<Value, ErrorEval> {
+        match args.as_slice() {
+            [Value::NumericValue(n)] => Ok(Value::NumericValue(n + self.offset)),
+            _ => Err(ErrorEval::runtime("adder", "Expect one number")),
+        }
+    }
+}
+#[test]
+fn apply_procedure() {
+    let interpreter: Interpreter = Interpreter::new();
+    interpreter.define_global("add10", Value::foreign(Adder { offset: 10.0 }));
+    assert_eq!(interpreter.eval_str("(add10 1)").unwrap().to_string(), "11");
+    assert_eq!(interpreter.eval_str("(procedure? add10)").unwrap().to_string(), "#t");
+    assert_eq!(interpreter.eval_str("(map add10 '(1 2))").unwrap().to_string(), "(11 12)");
+    assert_eq!(interpreter.eval_str("(apply add10 '(5))").unwrap().to_string(), "15");
+    assert!(interpreter.eval_str("(add10 \"x\")").is_err());
+    assert_eq!(interpreter.eval_str("(apply and '(1 2 3))").unwrap().to_string(), "3");
+    assert_eq!(interpreter.eval_str("(map or '(#f 1) '(2 #f))").unwrap().to_string(), "(2 1)");
+    assert_eq!(interpreter.eval_str("(apply if '(#f 1 2))").unwrap().to_string(), "2");
+    assert_eq!(interpreter.eval_str("(fold-left and #t '(1 2))").unwrap().to_string(), "2");
+}
+#[test]
+fn exit_does_not_kill_the_host() {
+    let interpreter: Interpreter = Interpreter::new();
+    assert_eq!(interpreter.eval_str("(define x 1) (exit 3) (define x 2)").unwrap_err().exit_code(), Some(3));
+    assert_eq!(interpreter.eval_str("x").unwrap().to_string(), "1");
+    assert_eq!(interpreter.eval_str("(if (exit 4) 1 2)").unwrap_err().exit_code(), Some(4));
+    assert_eq!(interpreter.eval_str("(and #t (exit))").unwrap_err().exit_code(), Some(0));
+    assert_eq!(interpreter.eval_str("((lambda () (exit_force 5)))").unwrap_err().exit_code(), Some(5));
+    assert_eq!(interpreter.eval_str("(exit \"x\")").unwrap_err().exit_code(), None);
+    let error = interpreter.eval_str("(error \"boom\")").unwrap_err();
+    assert_eq!(error.exit_code(), None);
+    assert!(error.to_string().contains("boom"));
+    assert_eq!(interpreter.eval_str("(sort (list 3 1 2))").unwrap().to_string(), "(1 2 3)");
+    assert_eq!(interpreter.eval_str("(sort (list \"b\" \"a\"))").unwrap().to_string(), "(\"a\" \"b\")");
+    assert!(interpreter.eval_str("(sort (list 1 \"a\"))").is_err());
+}
+#[test]
+fn max_cells_needs_the_counting_allocator() {
+    // 这个测试程序没有安装 CountingAllocator
+    assert!(!alloc_counter::installed());
+    assert!(EvalEnv::sandboxed(&["+"], &[], Limits { max_cells: Some(100), ..Limits::default() }).is_err());
+    assert!(EvalEnv::sandboxed(&["+"], &[], Limits { max_steps: Some(100), ..Limits::default() }).is_ok());
+}
+#[test]
+fn fresh_env_keeps_configuration() {
+    let env: EvalEnv = EvalEnv::sandboxed(&["+", "display"], &["define"], Limits::default()).unwrap();
+    let env: Rc<EvalEnv> = Rc::new(env);
+    env.output.capture();
+    let interpreter: Interpreter = Interpreter::with_env(env.clone());
+    interpreter.eval_str("(define x 1)").unwrap();
+    let fresh: Interpreter = Interpreter::with_env(Rc::new(env.fresh()));
+    assert!(fresh.eval_str("x").is_err());
+    assert!(fresh.eval_str("(* 2 3)").is_err());
+    assert!(fresh.env().sandbox.is_some());
+    assert!(Rc::ptr_eq(&fresh.env().debugger, &env.debugger));
+    fresh.eval_str("(display (+ 1 2))").unwrap();
+    assert_eq!(env.output.take(), "3");
+}
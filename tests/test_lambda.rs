@@ -0,0 +1,44 @@
+mod common;
+use common::eval;
+use mini_lisp_interpreter::error::ErrorKind;
+use mini_lisp_interpreter::interpreter::Interpreter;
+#[test]
+fn rest_parameters() {
+    let interpreter: Interpreter = Interpreter::new();
+    interpreter.eval_str("(define (f a . rest) (list a rest))").unwrap();
+    assert_eq!(eval(&interpreter, "(f 1 2 3)"), "(1 (2 3))");
+    assert_eq!(eval(&interpreter, "(f 1)"), "(1 ())");
+    assert_eq!(eval(&interpreter, "((lambda args args) 1 2)"), "(1 2)");
+    assert_eq!(eval(&interpreter, "(define (g . args) (length args)) (g 1 2 3)"), "3");
+}
+#[test]
+fn optional_and_keyword_parameters() {
+    let interpreter: Interpreter = Interpreter::new();
+    interpreter.eval_str("(define (g a #!optional (b (+ a 1)) c) (list a b c))").unwrap();
+    assert_eq!(eval(&interpreter, "(g 1)"), "(1 2 #f)");
+    assert_eq!(eval(&interpreter, "(g 1 5 6)"), "(1 5 6)");
+    interpreter.eval_str("(define (connect host #:key (port 80) secure) (list host port secure))").unwrap();
+    assert_eq!(eval(&interpreter, "(connect \"a\")"), "(\"a\" 80 #f)");
+    assert_eq!(eval(&interpreter, "(connect \"a\" #:secure #t #:port 443)"), "(\"a\" 443 #t)");
+    assert!(interpreter.eval_str("(connect \"a\" #:timeout 1)").is_err());
+}
+#[test]
+fn case_lambda() {
+    let interpreter: Interpreter = Interpreter::new();
+    interpreter.eval_str("(define area (case-lambda ((r) (* r r)) ((w h) (* w h)) ((a b . more) more)))").unwrap();
+    assert_eq!(eval(&interpreter, "(area 3)"), "9");
+    assert_eq!(eval(&interpreter, "(area 2 4)"), "8");
+    assert_eq!(eval(&interpreter, "(area 1 2 3 4)"), "(3 4)");
+    let error = interpreter.eval_str("(area)").unwrap_err();
+    assert!(matches!(&error.kind, ErrorKind::ArityMismatch { procedure, got: 0, .. } if procedure == "case-lambda"));
+}
+#[test]
+fn arity_mismatch_is_catchable() {
+    let interpreter: Interpreter = Interpreter::new();
+    interpreter.eval_str("(define (k a b) a)").unwrap();
+    let error = interpreter.eval_str("(k 1)").unwrap_err();
+    assert!(matches!(&error.kind, ErrorKind::ArityMismatch { expected, got: 1, .. } if expected == "2"));
+    assert!(matches!(interpreter.eval_str("(k 1 2 3)").unwrap_err().kind, ErrorKind::ArityMismatch { got: 3, .. }));
+    assert!(matches!(interpreter.eval_str("((lambda (a #!optional b) a))").unwrap_err().kind, ErrorKind::ArityMismatch { .. }));
+    assert_eq!(eval(&interpreter, "(k 1 2)"), "1");
+}
@@ -0,0 +1,53 @@
+mod common;
+use common::eval;
+use mini_lisp_interpreter::error::ErrorKind;
+use mini_lisp_interpreter::interpreter::Interpreter;
+#[test]
+fn let_family() {
+    let interpreter: Interpreter = Interpreter::new();
+    assert_eq!(eval(&interpreter, "(let () 1)"), "1");
+    assert_eq!(eval(&interpreter, "(let* ((x 1) (y (+ x 1))) (list x y))"), "(1 2)");
+    assert_eq!(eval(&interpreter, "(letrec ((ev? (lambda (n) (if (= n 0) #t (od? (- n 1))))) (od? (lambda (n) (if (= n 0) #f (ev? (- n 1)))))) (ev? 10))"), "#t");
+    assert_eq!(eval(&interpreter, "(letrec* ((a 1) (b (+ a 1))) b)"), "2");
+    assert!(interpreter.eval_str("(letrec ((a 1) (b (+ a 1))) b)").is_err());
+    assert_eq!(eval(&interpreter, "(let loop ((i 0) (acc '())) (if (= i 5) acc (loop (+ i 1) (cons i acc))))"), "(4 3 2 1 0)");
+    assert!(interpreter.eval_str("loop").is_err());
+}
+#[test]
+fn body_errors_propagate() {
+    let interpreter: Interpreter = Interpreter::new();
+    assert!(matches!(interpreter.eval_str("(let ((x 1)) (car x))").unwrap_err().kind, ErrorKind::TypeError { .. }));
+    assert!(matches!(interpreter.eval_str("(let ((x)) x)").unwrap_err().kind, ErrorKind::BadSyntax(_)));
+}
+#[test]
+fn multiple_values() {
+    let interpreter: Interpreter = Interpreter::new();
+    assert_eq!(eval(&interpreter, "(call-with-values (lambda () (values 1 2)) +)"), "3");
+    assert_eq!(eval(&interpreter, "(call-with-values (lambda () 5) list)"), "(5)");
+    assert_eq!(eval(&interpreter, "(let-values (((a b) (values 1 2)) ((c . d) (values 3 4 5))) (list a b c d))"), "(1 2 3 (4 5))");
+    assert_eq!(eval(&interpreter, "(let*-values (((a b) (values 1 2)) ((c) (+ a b))) c)"), "3");
+    interpreter.eval_str("(define-values (q . r) (values 7 8 9))").unwrap();
+    assert_eq!(eval(&interpreter, "(list q r)"), "(7 (8 9))");
+    assert!(matches!(interpreter.eval_str("(let-values (((a b) (values 1))) a)").unwrap_err().kind, ErrorKind::ArityMismatch { .. }));
+}
+#[test]
+fn internal_defines_stay_local() {
+    let interpreter: Interpreter = Interpreter::new();
+    interpreter.eval_str("(define (f) (define (ev? n) (if (= n 0) #t (od? (- n 1)))) (define (od? n) (if (= n 0) #f (ev? (- n 1)))) (ev? 4))").unwrap();
+    assert_eq!(eval(&interpreter, "(f)"), "#t");
+    assert_eq!(eval(&interpreter, "(let () (define z 3) z)"), "3");
+    assert_eq!(eval(&interpreter, "(defined_all? 'ev?)"), "#f");
+    assert_eq!(eval(&interpreter, "(defined_all? 'z)"), "#f");
+}
+#[test]
+fn named_let_loops_in_constant_depth() {
+    let interpreter: Interpreter = Interpreter::new();
+    assert_eq!(eval(&interpreter, "(let loop ((i 0)) (if (< i 100000) (loop (+ i 1)) i))"), "100000");
+    assert_eq!(eval(&interpreter, "(let loop ((i 0) (acc 0)) (cond ((= i 10000) acc) (else (loop (+ i 1) (+ acc 1)))))"), "10000");
+    assert_eq!(eval(&interpreter, "(let loop ((i 0)) (when (< i 10000) (begin (+ i 1) (loop (+ i 1)))))"), "()");
+    assert_eq!(eval(&interpreter, "(let loop ((i 10000)) (unless (= i 0) (loop (- i 1))))"), "()");
+    assert_eq!(eval(&interpreter, "(let f ((n 5)) (if (= n 0) 1 (* n (f (- n 1)))))"), "120");
+    assert_eq!(eval(&interpreter, "(let loop ((i 0)) (define (loop x) (list 'inner x)) (loop i))"), "(inner 0)");
+    assert_eq!(eval(&interpreter, "(let loop ((i 0)) (cond ((= i 3) => (lambda (x) (list x i))) (else (loop (+ i 1)))))"), "(#t 3)");
+    assert!(matches!(interpreter.eval_str("(let loop ((i 0)) (if (= i 5) (car i) (loop (+ i 1))))").unwrap_err().kind, ErrorKind::TypeError { .. }));
+}
@@ -0,0 +1,83 @@
+mod common;
+use common::eval;
+use mini_lisp_interpreter::interpreter::Interpreter;
+#[test]
+fn accessors() {
+    let interpreter: Interpreter = Interpreter::new();
+    assert_eq!(eval(&interpreter, "(cadr '(1 2 3))"), "2");
+    assert_eq!(eval(&interpreter, "(cdddr '(1 2 3 4))"), "(4)");
+    assert_eq!(eval(&interpreter, "(cdar '((1 . 2)))"), "2");
+    assert_eq!(eval(&interpreter, "(list-ref '(a b c) 2)"), "c");
+    assert_eq!(eval(&interpreter, "(list-tail '(a b c) 1)"), "(b c)");
+    assert_eq!(eval(&interpreter, "(last '(1 2 3))"), "3");
+    assert_eq!(eval(&interpreter, "(take '(1 2 3 4) 2)"), "(1 2)");
+    assert_eq!(eval(&interpreter, "(drop '(1 2 3 4) 2)"), "(3 4)");
+    assert_eq!(eval(&interpreter, "(reverse '(1 2 3))"), "(3 2 1)");
+    assert!(interpreter.eval_str("(list-ref '(1) 5)").is_err());
+    assert!(interpreter.eval_str("(cadr '(1))").is_err());
+}
+#[test]
+fn folds_and_searches() {
+    let interpreter: Interpreter = Interpreter::new();
+    assert_eq!(eval(&interpreter, "(fold-left cons '() '(1 2 3))"), "(((() . 1) . 2) . 3)");
+    assert_eq!(eval(&interpreter, "(fold-right cons '() '(1 2 3))"), "(1 2 3)");
+    assert_eq!(eval(&interpreter, "(fold-left + 0 '(1 2) '(10 20))"), "33");
+    assert_eq!(eval(&interpreter, "(assoc \"b\" '((\"a\" . 1) (\"b\" . 2)))"), "(\"b\" . 2)");
+    assert_eq!(eval(&interpreter, "(assq 'c '((a 1) (b 2)))"), "#f");
+    assert_eq!(eval(&interpreter, "(assv 2 '((1 . one) (2 . two)))"), "(2 . two)");
+    assert_eq!(eval(&interpreter, "(member '(1) '(a (1) b))"), "((1) b)");
+    assert_eq!(eval(&interpreter, "(member 2.5 '(1 2 3) <)"), "(3)");
+    assert_eq!(eval(&interpreter, "(memq 'b '(a b c))"), "(b c)");
+    assert_eq!(eval(&interpreter, "(list-index even? '(1 3 4))"), "2");
+    assert_eq!(eval(&interpreter, "(any even? '(1 3 4))"), "#t");
+    assert_eq!(eval(&interpreter, "(every + '(1 2) '(3 4))"), "6");
+    assert_eq!(eval(&interpreter, "(every even? '())"), "#t");
+}
+#[test]
+fn builders() {
+    let interpreter: Interpreter = Interpreter::new();
+    assert_eq!(eval(&interpreter, "(delete 2 '(1 2 3 2))"), "(1 3)");
+    assert_eq!(eval(&interpreter, "(remove even? '(1 2 3 4))"), "(1 3)");
+    assert_eq!(eval(&interpreter, "(call-with-values (lambda () (partition even? '(1 2 3 4))) list)"), "((2 4) (1 3))");
+    assert_eq!(eval(&interpreter, "(iota 5)"), "(0 1 2 3 4)");
+    assert_eq!(eval(&interpreter, "(iota 3 1 2)"), "(1 3 5)");
+    assert_eq!(eval(&interpreter, "(append-map (lambda (x) (list x x)) '(1 2))"), "(1 1 2 2)");
+    assert_eq!(eval(&interpreter, "(delete-duplicates '(1 2 1 3 2))"), "(1 2 3)");
+    assert_eq!(eval(&interpreter, "(for-each car '((1) (2)))"), "()");
+    assert_eq!(eval(&interpreter, "(length (iota 3000))"), "3000");
+}
+#[test]
+fn variadic_map() {
+    let interpreter: Interpreter = Interpreter::new();
+    assert_eq!(eval(&interpreter, "(map + '(1 2 3) '(10 20))"), "(11 22)");
+    assert_eq!(eval(&interpreter, "(map (lambda (x y z) (list x y z)) '(1 2) '(a b) '(#t #f))"), "((1 a #t) (2 b #f))");
+    assert_eq!(eval(&interpreter, "(map car '())"), "()");
+    assert!(interpreter.eval_str("(map car 5)").is_err());
+}
+#[test]
+fn dotted_pairs() {
+    let interpreter: Interpreter = Interpreter::new();
+    assert_eq!(eval(&interpreter, "(cons 1 2)"), "(1 . 2)");
+    assert_eq!(eval(&interpreter, "(cons 1 (cons 2 3))"), "(1 2 . 3)");
+    assert_eq!(eval(&interpreter, "(list (cons 'a \"b\") (cons #t 'c))"), "((a . \"b\") (#t . c))");
+    assert_eq!(eval(&interpreter, "'(1 . (2 . ()))"), "(1 2)");
+    assert_eq!(eval(&interpreter, "(cons 1 car)"), "(1 . #<procedure>)");
+}
+#[test]
+fn eq_on_pairs() {
+    let interpreter: Interpreter = Interpreter::new();
+    assert_eq!(eval(&interpreter, "(eq? '(a (1 . 2)) '(a (1 . 2)))"), "#t");
+    assert_eq!(eval(&interpreter, "(eq? '(a (1 . 2)) '(a (1 . 3)))"), "#f");
+    assert_eq!(eval(&interpreter, "(eq? (list car) (list car))"), "#t");
+    assert_eq!(eval(&interpreter, "(eq? (list (lambda (x) x)) (list (lambda (x) x)))"), "#f");
+    assert_eq!(eval(&interpreter, "(eq? '(1) 1)"), "#f");
+}
+#[test]
+fn empty_lists_and_improper_tails() {
+    let interpreter: Interpreter = Interpreter::new();
+    assert_eq!(eval(&interpreter, "(length '())"), "0");
+    assert_eq!(eval(&interpreter, "(append '(a b) '(c . d))"), "(a b c . d)");
+    assert_eq!(eval(&interpreter, "(append '() 'a)"), "a");
+    assert_eq!(eval(&interpreter, "(append '(1) '() '(2 3))"), "(1 2 3)");
+    assert_eq!(eval(&interpreter, "(append)"), "()");
+}
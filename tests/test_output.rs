@@ -0,0 +1,44 @@
+use mini_lisp_interpreter::command_line::Config;
+use mini_lisp_interpreter::interpreter::Interpreter;
+use mini_lisp_interpreter::output::diff;
+use mini_lisp_interpreter::reader_file::ReaderFile;
+#[test]
+fn capture() {
+    let interpreter: Interpreter = Interpreter::new();
+    interpreter.env().output.capture();
+    interpreter.eval_str("(display \"a\") (display '(1 \"b\")) (newline) (displayln 2) (print 3 \"c\")").unwrap();
+    interpreter.eval_str("((lambda () (display 'inner)))").unwrap();
+    assert_eq!(interpreter.env().output.take(), "a(1 \"b\")\n2\n3\n\"c\"\ninner");
+    assert_eq!(interpreter.env().output.take(), "");
+    interpreter.env().output.capture();
+    interpreter.eval_str("(test-begin \"g\") (test-equal 1 2) (test-end)").unwrap();
+    assert_eq!(interpreter.env().output.take(), "FAIL g / 2: expected 1, got 2\ng: 0 passed, 1 failed\n");
+}
+#[test]
+fn top_level_results() {
+    let interpreter: Interpreter = Interpreter::new();
+    interpreter.env().output.capture();
+    let code: i32 = ReaderFile::from_expression("(define x 2) (display x) (newline) (* x 21) '() \"s\"".to_string(), None, interpreter.env()).call();
+    assert_eq!(code, 0);
+    assert_eq!(interpreter.env().output.take(), "2\n42\n\"s\"\n");
+}
+#[test]
+fn golden_diff() {
+    assert_eq!(diff("a\nb\n", "a\nb\n"), None);
+    assert_eq!(diff("a\nb\nc\n", "a\nx\nc\n").unwrap(), " a\n-b\n+x\n c");
+    assert_eq!(diff("a\n", "a\nb\n").unwrap(), " a\n+b");
+    assert_eq!(diff("a\nb\n", "b\n").unwrap(), "-a\n b");
+    assert_eq!(diff("a\n", "a").unwrap(), " a\n\\ Trailing newline differs");
+}
+#[test]
+fn check_options() {
+    let args = |line: &str| line.split(' ').map(String::from).collect::<Vec<String>>().into_iter();
+    let config: Config = Config::build(args("minilisp --check out.txt --bless script.scm x")).unwrap();
+    assert_eq!(config.check_file.as_deref(), Some("out.txt"));
+    assert!(config.bless);
+    assert_eq!(config.input_file_path.as_deref(), Some("script.scm"));
+    assert_eq!(config.script_args, vec!["x".to_string()]);
+    assert!(Config::build(args("minilisp --check")).is_err());
+    let config: Config = Config::build(args("minilisp test dir")).unwrap();
+    assert_eq!(config.test_dir.as_deref(), Some("dir"));
+}
@@ -0,0 +1,43 @@
+use mini_lisp_interpreter::conformance::{run_file, run_source, Outcome, Report};
+use mini_lisp_interpreter::error::ErrorKind;
+use mini_lisp_interpreter::eval_env::EvalEnv;
+use std::path::PathBuf;
+use std::rc::Rc;
+#[test]
+fn r7rs() {
+    let dir: PathBuf = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/r7rs");
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().path()).filter(|path| path.extension().is_some_and(|ext| ext == "scm")).collect();
+    paths.sort();
+    let reports: Vec<Report> = paths.iter().map(|path| run_file(path).unwrap()).collect();
+    for report in reports.iter() {
+        println!("{}", report);
+    }
+    assert!(reports.iter().all(|report| report.failed() == 0));
+}
+#[test]
+fn harness() {
+    let source: &str = "(define x 2)\n(test 4 (* x x))\n(test 5 (+ x x))\n(test-error (car '()))\n(test-error (car '(1)))\n(test 1 (car 1))\n(test 3 (+ 1 2))\n(undefined-procedure)";
+    let xfail: Vec<String> = vec!["(car 1)".to_string(), "(+ 1 2)".to_string()];
+    let report: Report = run_source(source, &xfail, Rc::new(EvalEnv::new())).unwrap();
+    let outcomes: Vec<Outcome> = report.cases.iter().map(|case| case.outcome.clone()).collect();
+    assert_eq!(outcomes.len(), 7);
+    assert_eq!(outcomes[0], Outcome::Pass);
+    assert_eq!(outcomes[1], Outcome::Fail("expected 5 but got 4".to_string()));
+    assert_eq!(outcomes[2], Outcome::Pass);
+    assert!(matches!(outcomes[3], Outcome::Fail(_)));
+    assert_eq!(outcomes[4], Outcome::ExpectedFail);
+    assert_eq!(outcomes[5], Outcome::UnexpectedPass);
+    assert!(matches!(outcomes[6], Outcome::Fail(_)));
+    assert_eq!(report.cases[1].line, 3);
+    assert_eq!((report.passed(), report.failed()), (3, 4));
+    assert!(report.to_string().ends_with("<string>: 2 passed, 4 failed, 1 expected failures"));
+}
+#[test]
+fn exit_and_abort_end_the_run() {
+    for source in ["(test-error (exit 3))\n(test 1 1)", "(test 1 (exit 3))", "(test (exit 3) 1)", "(exit 3)"] {
+        assert_eq!(run_source(source, &[], Rc::new(EvalEnv::new())).unwrap_err().exit_code(), Some(3), "{}", source);
+    }
+    let env: Rc<EvalEnv> = Rc::new(EvalEnv::new());
+    env.debugger.set_io(Box::new(std::io::Cursor::new(b"q\n".to_vec())), Box::new(std::io::sink()));
+    assert!(matches!(run_source("(test-error (break))", &[], env).unwrap_err().kind, ErrorKind::Aborted));
+}
@@ -0,0 +1,42 @@
+use mini_lisp_interpreter::tokenizer::{IncrementalReader, Datum};
+use mini_lisp_interpreter::error::ErrorRead;
+#[test]
+fn incremental_reader() {
+    let mut reader: IncrementalReader = IncrementalReader::new();
+    reader.feed("(define (f x)\n");
+    assert_eq!(reader.next_datum(), Ok(None));
+    assert!(reader.is_pending());
+    reader.feed("  \"a)b\" ; )\n  x) (f 1)\n");
+    assert_eq!(reader.next_datum(), Ok(Some(Datum { text: "(define (f x)\n  \"a)b\" ; )\n  x)".to_string(), line: 1, column: 1 })));
+    assert_eq!(reader.next_datum(), Ok(Some(Datum { text: "(f 1)".to_string(), line: 3, column: 6 })));
+    assert_eq!(reader.next_datum(), Ok(None));
+    assert!(!reader.is_pending());
+
+    reader.feed("#| a #| b |# |# '#;(x) y )\n");
+    assert_eq!(reader.next_datum(), Ok(Some(Datum { text: "'#;(x) y".to_string(), line: 4, column: 17 })));
+    assert_eq!(reader.next_datum(), Err(ErrorRead::SyntaxFailure));
+    assert_eq!(reader.next_datum(), Ok(None));
+}
+#[test]
+fn multiple_datums_per_chunk() {
+    let mut reader: IncrementalReader = IncrementalReader::new();
+    reader.feed("1 (+ 2 3) \"x\" y");
+    assert_eq!(reader.next_datum(), Ok(Some(Datum { text: "1".to_string(), line: 1, column: 1 })));
+    assert_eq!(reader.next_datum(), Ok(Some(Datum { text: "(+ 2 3)".to_string(), line: 1, column: 3 })));
+    assert_eq!(reader.next_datum(), Ok(Some(Datum { text: "\"x\"".to_string(), line: 1, column: 11 })));
+    // 末尾的原子可能在下一段输入中继续
+    assert_eq!(reader.next_datum(), Ok(None));
+    reader.feed("z ; trailing comment");
+    assert_eq!(reader.next_datum(), Ok(Some(Datum { text: "yz".to_string(), line: 1, column: 15 })));
+    assert_eq!(reader.next_datum(), Ok(None));
+    reader.finish();
+    assert_eq!(reader.next_datum(), Ok(None));
+    assert!(!reader.is_pending());
+
+    let mut reader: IncrementalReader = IncrementalReader::new();
+    reader.feed("'a b");
+    reader.finish();
+    assert_eq!(reader.next_datum(), Ok(Some(Datum { text: "'a".to_string(), line: 1, column: 1 })));
+    assert_eq!(reader.next_datum(), Ok(Some(Datum { text: "b".to_string(), line: 1, column: 4 })));
+    assert_eq!(reader.next_datum(), Ok(None));
+}
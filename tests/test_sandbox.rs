@@ -0,0 +1,88 @@
+use mini_lisp_interpreter::alloc_counter::CountingAllocator;
+use mini_lisp_interpreter::eval_env::EvalEnv;
+use mini_lisp_interpreter::interpreter::Interpreter;
+use mini_lisp_interpreter::sandbox::{Limits, SAFE_BUILTINS, SAFE_SPECIAL_FORMS};
+use mini_lisp_interpreter::value::Value;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+/// 限制分配单元数需要安装计数分配器
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+fn sandbox(builtins: &[&str], special_forms: &[&str], limits: Limits) -> Interpreter {
+    Interpreter::with_env(Rc::new(EvalEnv::sandboxed(builtins, special_forms, limits).unwrap()))
+}
+#[test]
+fn allow_list() {
+    let interpreter: Interpreter = sandbox(&["+", "exit"], &["quote", "define"], Limits::default());
+    assert_eq!(interpreter.eval_str("(define x 1) (+ x 2)").unwrap().to_string(), "3");
+    assert!(interpreter.eval_str("(* 2 3)").is_err());
+    assert!(interpreter.eval_str("(lambda (x) x)").is_err());
+    assert!(interpreter.eval_str("(exit 3)").is_err());
+    assert!(EvalEnv::sandboxed(&["no-such-builtin"], &[], Limits::default()).is_err());
+    let safe: Interpreter = sandbox(SAFE_BUILTINS, SAFE_SPECIAL_FORMS, Limits::default());
+    assert_eq!(safe.eval_str("((lambda (x) (* x x)) 4)").unwrap().to_string(), "16");
+    assert!(safe.eval_str("(eval '(+ 1 2))").is_err());
+}
+#[test]
+fn limits() {
+    let program: &str = "(define (loop n) (if (= n 0) 0 (loop (- n 1))))";
+    let steps: Interpreter = sandbox(SAFE_BUILTINS, &["define", "if"], Limits { max_steps: Some(1000), ..Limits::default() });
+    steps.eval_str(program).unwrap();
+    assert_eq!(steps.eval_str("(loop 5)").unwrap().to_string(), "0");
+    assert!(steps.eval_str("(loop 100000)").unwrap_err().to_string().contains("Step limit"));
+    let depth: Interpreter = sandbox(SAFE_BUILTINS, &["define", "if"], Limits { max_depth: Some(200), ..Limits::default() });
+    depth.eval_str(program).unwrap();
+    assert_eq!(depth.eval_str("(loop 5)").unwrap().to_string(), "0");
+    assert!(depth.eval_str("(loop 1000)").unwrap_err().to_string().contains("Recursion depth"));
+    assert_eq!(depth.eval_str("(loop 5)").unwrap().to_string(), "0");
+    let cells: Interpreter = sandbox(SAFE_BUILTINS, &["define", "if", "quote"], Limits { max_cells: Some(10000), ..Limits::default() });
+    cells.eval_str("(define (grow n acc) (if (= n 0) acc (grow (- n 1) (cons n acc))))").unwrap();
+    assert!(cells.eval_str("(grow 100000 '())").unwrap_err().to_string().contains("Allocation limit"));
+    let deadline: Interpreter = sandbox(SAFE_BUILTINS, &["define", "if"], Limits { deadline: Some(Instant::now() + Duration::from_millis(50)), ..Limits::default() });
+    deadline.eval_str(program).unwrap();
+    std::thread::sleep(Duration::from_millis(60));
+    assert!(deadline.eval_str("(loop 5)").unwrap_err().to_string().contains("Deadline"));
+}
+/// 定义, 期望的错误信息与资源限制
+type Case = (&'static str, &'static str, fn() -> Limits);
+#[test]
+fn limits_inside_special_forms() {
+    let forms: [&str; 4] = ["(and #t (f 1))", "(or #f (f 1) 7)", "(if (f 1) 1 2)", "(cond ((f 1) 1) (else 2))"];
+    let special_forms: &[&str] = &["define", "if", "and", "or", "cond", "quote"];
+    let cases: [Case; 4] = [
+        ("(define (f n) (f (+ n 1)))", "Step limit", || Limits { max_steps: Some(1000), ..Limits::default() }),
+        ("(define (f n) (+ 1 (f n)))", "Recursion depth", || Limits { max_depth: Some(100), ..Limits::default() }),
+        ("(define (f n) (f (map list (iota 500))))", "Allocation limit", || Limits { max_cells: Some(10000), ..Limits::default() }),
+        ("(define (f n) (length (iota 100000000)))", "Deadline", || Limits { deadline: Some(Instant::now() + Duration::from_millis(50)), ..Limits::default() }),
+    ];
+    for (definition, message, limits) in cases {
+        for form in forms {
+            let interpreter: Interpreter = sandbox(SAFE_BUILTINS, special_forms, limits());
+            interpreter.eval_str(definition).unwrap();
+            let error = interpreter.eval_str(form).unwrap_err();
+            assert!(error.to_string().contains(message), "{} with {}: {}", form, message, error);
+        }
+    }
+}
+#[test]
+fn limits_inside_builtin_loops() {
+    let steps = || Interpreter::with_env(Rc::new(EvalEnv::safe(Limits { max_steps: Some(1000), ..Limits::default() }).unwrap()));
+    assert!(steps().eval_str("(iota 100000)").unwrap_err().to_string().contains("Step limit"));
+    assert!(steps().eval_str("(length (iota 500))").is_ok());
+    let interpreter: Interpreter = sandbox(SAFE_BUILTINS, &["define"], Limits { max_steps: Some(1000), ..Limits::default() });
+    interpreter.eval_str("(define xs (iota 600))").unwrap();
+    assert!(interpreter.eval_str("(map abs xs)").unwrap_err().to_string().contains("Step limit"));
+    assert!(steps().eval_str("(member 1000 (iota 600))").unwrap_err().to_string().contains("Step limit"));
+    assert!(steps().eval_str("(stream->list (stream-range 0 100000))").unwrap_err().to_string().contains("Step limit"));
+    assert!(steps().eval_str("(let loop ((i 0)) (loop (+ i 1)))").unwrap_err().to_string().contains("Step limit"));
+    let deadline: Interpreter = Interpreter::with_env(Rc::new(EvalEnv::safe(Limits { deadline: Some(Instant::now() + Duration::from_millis(50)), ..Limits::default() }).unwrap()));
+    assert!(deadline.eval_str("(length (iota 100000000))").unwrap_err().to_string().contains("Deadline"));
+}
+#[test]
+fn parameterize_restores_after_limit() {
+    let interpreter: Interpreter = sandbox(SAFE_BUILTINS, &["define", "if", "parameterize"], Limits { max_steps: Some(1000), ..Limits::default() });
+    interpreter.eval_str("(define p (make-parameter 1)) (define (loop n) (loop (+ n 1)))").unwrap();
+    assert!(interpreter.eval_str("(parameterize ((p 2)) (loop 0))").unwrap_err().to_string().contains("Step limit"));
+    let Some(Value::ProcedureValue(p)) = interpreter.env().find_binding(&"p".to_string()) else { panic!("p is not a parameter") };
+    assert_eq!(p(Vec::new(), interpreter.env()).unwrap().to_string(), "1");
+}
@@ -0,0 +1,43 @@
+mod common;
+use common::eval;
+use mini_lisp_interpreter::error::ErrorKind;
+use mini_lisp_interpreter::interpreter::Interpreter;
+#[test]
+fn infinite_streams() {
+    let interpreter: Interpreter = Interpreter::new();
+    interpreter.eval_str("(define (ints n) (stream-cons n (ints (+ n 1))))").unwrap();
+    assert_eq!(eval(&interpreter, "(stream->list 5 (ints 0))"), "(0 1 2 3 4)");
+    assert_eq!(eval(&interpreter, "(stream->list (stream-take 3 (stream-map * (ints 1) (ints 1))))"), "(1 4 9)");
+    assert_eq!(eval(&interpreter, "(stream->list 4 (stream-filter even? (ints 1)))"), "(2 4 6 8)");
+    assert_eq!(eval(&interpreter, "(stream-car (stream-cdr (ints 10)))"), "11");
+    assert_eq!(eval(&interpreter, "(stream-pair? (ints 0))"), "#t");
+}
+#[test]
+fn elements_are_evaluated_on_demand() {
+    let interpreter: Interpreter = Interpreter::new();
+    interpreter.eval_str("(define s (stream-map car (list->stream '(1 (2)))))").unwrap();
+    assert_eq!(eval(&interpreter, "(stream-car (stream-cdr s))"), "2");
+    assert!(interpreter.eval_str("(stream-car s)").is_err());
+    assert_eq!(eval(&interpreter, "(stream-car (stream-cons (car '(3)) (car 1)))"), "3");
+}
+#[test]
+fn finite_streams() {
+    let interpreter: Interpreter = Interpreter::new();
+    assert_eq!(eval(&interpreter, "(stream->list (stream-range 5 0))"), "(5 4 3 2 1)");
+    assert_eq!(eval(&interpreter, "(stream->list (stream-range 0 10 3))"), "(0 3 6 9)");
+    assert_eq!(eval(&interpreter, "(stream->list (list->stream '(1 2 3)))"), "(1 2 3)");
+    assert_eq!(eval(&interpreter, "(stream-null? (stream-filter even? (list->stream '(1 3))))"), "#t");
+    assert_eq!(eval(&interpreter, "(stream-fold + 0 (stream-range 0 20000))"), "199990000");
+    assert!(interpreter.eval_str("(stream-car '())").is_err());
+}
+#[test]
+fn filter_releases_skipped_elements() {
+    let interpreter: Interpreter = Interpreter::new();
+    interpreter.eval_str("(define (nat n) (stream-cons n (nat (+ n 1))))").unwrap();
+    assert_eq!(eval(&interpreter, "(stream-car (stream-filter zero? (stream-range 200000 -10000000)))"), "0");
+    assert_eq!(eval(&interpreter, "(stream-car (stream-filter zero? (nat -110000)))"), "0");
+    interpreter.eval_str("(define s (stream-filter (lambda (x) (if (= x 10) (car x) (> x 10))) (stream-range 0 20)))").unwrap();
+    for _ in 0..2 {
+        assert!(matches!(interpreter.eval_str("(stream-car s)").unwrap_err().kind, ErrorKind::TypeError { .. }));
+    }
+}
@@ -0,0 +1,57 @@
+use mini_lisp_interpreter::error::ErrorKind;
+use mini_lisp_interpreter::interpreter::Interpreter;
+use mini_lisp_interpreter::unit_test::discover;
+use std::path::PathBuf;
+fn counts(interpreter: &Interpreter) -> (usize, usize) {
+    (interpreter.env().test_runner.passed(), interpreter.env().test_runner.failed())
+}
+#[test]
+fn assertions() {
+    let interpreter: Interpreter = Interpreter::new();
+    interpreter.eval_str("(test-begin \"suite\") (define (square x) (* x x))").unwrap();
+    interpreter.eval_str("(test-equal 4 (square 2)) (test-equal \"named\" '(1 4) (map square '(1 2)))").unwrap();
+    interpreter.eval_str("(test-assert (> 2 1)) (test-assert \"named\" 'x)").unwrap();
+    interpreter.eval_str("(test-error (car '())) (test-error #t (car 1)) (test-error \"named\" #t (error \"boom\"))").unwrap();
+    interpreter.eval_str("(test-approximate 1.41 (expt 2 0.5) 0.01)").unwrap();
+    assert_eq!(counts(&interpreter), (8, 0));
+    interpreter.eval_str("(test-equal 5 (square 2)) (test-equal 1 (car 1)) (test-assert #f) (test-error 1)").unwrap();
+    interpreter.eval_str("(test-approximate 1 2 0.5) (test-approximate 1 'a 0.5)").unwrap();
+    assert_eq!(counts(&interpreter), (8, 6));
+    assert_eq!(interpreter.env().test_runner.open_groups(), vec!["suite".to_string()]);
+    assert!(interpreter.eval_str("(test-end \"other\")").is_err());
+    interpreter.eval_str("(test-end \"suite\")").unwrap();
+    assert!(interpreter.env().test_runner.open_groups().is_empty());
+    assert!(interpreter.eval_str("(test-end)").is_err());
+    assert!(interpreter.eval_str("(test-equal 1)").is_err());
+    assert!(interpreter.eval_str("(test-begin 'suite)").is_err());
+}
+#[test]
+fn nested_groups() {
+    let interpreter: Interpreter = Interpreter::new();
+    interpreter.eval_str("(test-begin \"outer\") (test-begin \"inner\") (test-assert #t)").unwrap();
+    assert_eq!(interpreter.env().test_runner.open_groups(), vec!["outer".to_string(), "inner".to_string()]);
+    interpreter.eval_str("(test-end) ((lambda () (test-assert #f))) (test-end \"outer\")").unwrap();
+    assert_eq!(counts(&interpreter), (1, 1));
+}
+#[test]
+fn exit_and_abort_are_not_results() {
+    let interpreter: Interpreter = Interpreter::new();
+    for code in ["(test-error (exit 3))", "(test-equal 1 (exit 3))", "(test-assert (exit 3))", "(test-approximate 1 (exit 3) 0.5)"] {
+        assert_eq!(interpreter.eval_str(code).unwrap_err().exit_code(), Some(3), "{}", code);
+    }
+    interpreter.env().debugger.set_io(Box::new(std::io::Cursor::new(b"q\nq\n".to_vec())), Box::new(std::io::sink()));
+    assert!(matches!(interpreter.eval_str("(test-error (break))").unwrap_err().kind, ErrorKind::Aborted));
+    assert!(matches!(interpreter.eval_str("(test-equal 1 (break))").unwrap_err().kind, ErrorKind::Aborted));
+    assert_eq!(counts(&interpreter), (0, 0));
+}
+#[test]
+fn discover_test_files() {
+    let dir: PathBuf = std::env::temp_dir().join(format!("minilisp-unit-test-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("sub")).unwrap();
+    for name in ["b-test.scm", "sub/a-test.scm", "helper.scm", "test.scm"] {
+        std::fs::write(dir.join(name), "(test-assert #t)").unwrap();
+    }
+    let found: Vec<PathBuf> = discover(&dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(found, vec![dir.join("b-test.scm"), dir.join("sub/a-test.scm")]);
+}